@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use crate::analysis::{constant, Error, Result};
+use crate::analysis::constant::Definitions;
+use crate::parser::ast::function::FunctionSignature;
+use crate::parser::ast::variable::{Identifier, Type, Variable};
+use crate::parser::token::Literal;
+
+#[derive(Debug)]
+struct Alias {
+	parameters: Vec<Identifier>,
+	ty: Type,
+	/// Whether the alias is the target of an impl block, which makes it a type of its own rather
+	/// than another name for the type it stands for, see [`Aliases::underlying`]
+	nominal: bool,
+}
+
+/// Type aliases of a program, for replacing their uses with the types they stand for
+#[derive(Debug, Default)]
+pub struct Aliases {
+	declared: HashMap<Identifier, Alias>,
+	/// Constants and const functions of the program, which lengths of array types are evaluated with
+	constants: Definitions,
+	/// Types that names such as the type parameters of a generic function currently stand for,
+	/// innermost last
+	parameters: Vec<HashMap<Identifier, Type>>,
+}
+
+impl Aliases {
+	pub fn insert(&mut self, ident: Identifier, parameters: Vec<Identifier>, ty: Type) {
+		self.declared.insert(ident, Alias { parameters, ty, nominal: false });
+	}
+
+	/// Makes an alias a type of its own, which is kept by name when expanding types so that its
+	/// methods and the interfaces it implements are not those of every type of the same layout
+	pub fn set_nominal(&mut self, ident: &Identifier) {
+		if let Some(alias) = self.declared.get_mut(ident) {
+			alias.nominal = true;
+		}
+	}
+
+	pub fn set_constants(&mut self, constants: Definitions) {
+		self.constants = constants;
+	}
+
+	pub const fn constants(&self) -> &Definitions {
+		&self.constants
+	}
+
+	/// Makes names stand for the given expanded types until [`Aliases::pop_parameters`] is
+	/// called, shadowing any alias of the same name. Only the innermost parameters are seen
+	pub fn push_parameters(&mut self, parameters: HashMap<Identifier, Type>) {
+		self.parameters.push(parameters);
+	}
+
+	pub fn pop_parameters(&mut self) {
+		self.parameters.pop();
+	}
+
+	/// Replaces every alias within the type with the type it stands for, and evaluates the lengths
+	/// of its arrays. Types of their own are kept by name
+	pub fn expand(&self, ty: &Type) -> Result<Type> {
+		self.expand_within(ty, &mut vec![], true)
+	}
+
+	/// Same as [`Aliases::expand`], also replacing types of their own with their layout, for where
+	/// only the layout of a value matters such as when the program runs
+	pub fn structural(&self, ty: &Type) -> Result<Type> {
+		self.expand_within(ty, &mut vec![], false)
+	}
+
+	/// Layout of an expanded type of its own, eg. `(f32, f32)` for `Vec2`, which is what its fields
+	/// and operators are those of. Other types are their own layout, and constness is kept
+	pub fn underlying(&self, ty: &Type) -> Result<Type> {
+		match ty {
+			Type::Const(ty) => Ok(self.underlying(ty)?.as_const()),
+			Type::Custom { name, template_args } => match self.declared.get(name) {
+				Some(alias) if alias.nominal && self.parameters.last().is_none_or(|x| !x.contains_key(name)) =>
+					self.expand(&substitute(&alias.ty, &alias.parameters, template_args)),
+				_ => Ok(ty.clone()),
+			},
+			ty => Ok(ty.clone()),
+		}
+	}
+
+	/// Same as [`Aliases::expand`], 'expanding' being the aliases currently being expanded, so
+	/// aliases that refer to themselves are caught rather than expanded forever
+	fn expand_within(&self, ty: &Type, expanding: &mut Vec<Identifier>, nominal: bool) -> Result<Type> {
+		Ok(match ty {
+			Type::Custom { name, template_args } => {
+				let template_args = template_args.iter()
+					.map(|x| self.expand_within(x, expanding, nominal))
+					.collect::<Result<Vec<_>>>()?;
+
+				if let Some(ty) = self.parameters.last().and_then(|x| x.get(name)) {
+					return Ok(ty.clone());
+				}
+
+				let Some(alias) = self.declared.get(name) else {
+					return Ok(Type::template(name.clone(), template_args));
+				};
+
+				if expanding.contains(name) {
+					return Error::RecursiveAlias(name.clone()).into();
+				}
+
+				// '<>' leaves every parameter to be inferred
+				let arguments = if template_args == [Type::Infer] {
+					vec![Type::Infer; alias.parameters.len()]
+				} else {
+					template_args
+				};
+
+				if arguments.len() != alias.parameters.len() {
+					return Error::AliasArguments {
+						alias: name.clone(),
+						expected: alias.parameters.len(),
+						given: arguments.len(),
+					}.into();
+				}
+
+				if alias.nominal && nominal {
+					return Ok(Type::template(name.clone(), arguments));
+				}
+
+				expanding.push(name.clone());
+				let expanded = self.expand_within(&substitute(&alias.ty, &alias.parameters, &arguments), expanding, nominal);
+				expanding.pop();
+				expanded?
+			}
+			Type::Pointer(ty) => self.expand_within(ty, expanding, nominal)?.as_pointer(),
+			Type::Nullable(ty) => self.expand_within(ty, expanding, nominal)?.as_nullable(),
+			Type::Const(ty) => self.expand_within(ty, expanding, nominal)?.as_const(),
+			Type::Array { ty, length } => self.expand_within(ty, expanding, nominal)?.as_array(*length),
+			Type::ConstArray { ty, length } =>
+				self.expand_within(ty, expanding, nominal)?.as_array(constant::length(&length.0, &mut &self.constants)?),
+			Type::Slice(ty) => self.expand_within(ty, expanding, nominal)?.as_slice(),
+			Type::Tuple(elements) => Type::Tuple(
+				elements.iter().map(|x| self.expand_within(x, expanding, nominal)).collect::<Result<_>>()?
+			),
+			Type::Function(signature) => {
+				let args = signature.get_args().iter()
+					.map(|x| Ok(Variable::new(x.get_name().clone(), self.expand_within(x.get_type(), expanding, nominal)?)))
+					.collect::<Result<Vec<_>>>()?;
+
+				FunctionSignature::new_named(args, self.expand_within(signature.get_return_type(), expanding, nominal)?).into()
+			}
+			ty => ty.clone(),
+		})
+	}
+}
+
+/// Replaces the parameters of a generic alias within its type with the arguments it was used with
+fn substitute(ty: &Type, parameters: &[Identifier], arguments: &[Type]) -> Type {
+	match ty {
+		Type::Custom { name, template_args } if template_args.is_empty() => parameters.iter()
+			.position(|x| x == name)
+			.map_or_else(|| ty.clone(), |i| arguments[i].clone()),
+		Type::Custom { name, template_args } => Type::template(
+			name.clone(),
+			template_args.iter().map(|x| substitute(x, parameters, arguments)).collect(),
+		),
+		Type::Pointer(ty) => substitute(ty, parameters, arguments).as_pointer(),
+		Type::Nullable(ty) => substitute(ty, parameters, arguments).as_nullable(),
+		Type::Const(ty) => substitute(ty, parameters, arguments).as_const(),
+		Type::Array { ty, length } => substitute(ty, parameters, arguments).as_array(*length),
+		Type::ConstArray { ty, length } =>
+			Type::ConstArray { ty: Box::new(substitute(ty, parameters, arguments)), length: length.clone() },
+		Type::Slice(ty) => substitute(ty, parameters, arguments).as_slice(),
+		Type::Tuple(elements) => Type::Tuple(elements.iter().map(|x| substitute(x, parameters, arguments)).collect()),
+		Type::Function(signature) => FunctionSignature::new_named(
+			signature.get_args().iter()
+				.map(|x| Variable::new(x.get_name().clone(), substitute(x.get_type(), parameters, arguments)))
+				.collect(),
+			substitute(signature.get_return_type(), parameters, arguments),
+		).into(),
+		ty => ty.clone(),
+	}
+}
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+use crate::parser::ast::attribute::Attribute;
+use crate::parser::ast::{Spanned, TopLevelStatement};
+use crate::parser::ast::variable::Identifier;
+
+/// Attributes of every top level declaration, for passes to look up by the declaration's name
+#[derive(Debug, Default)]
+pub struct Registry {
+	declarations: HashMap<Identifier, Vec<Attribute>>,
+}
+
+impl Registry {
+	pub fn collect(statements: &[Spanned<TopLevelStatement>]) -> Self {
+		let declarations = statements.iter()
+			.filter_map(|statement| match &statement.node {
+				TopLevelStatement::Function { ident, attributes, .. }
+				| TopLevelStatement::TypeAlias { ident, attributes, .. }
+				| TopLevelStatement::Interface { ident, attributes, .. } => Some((ident, attributes)),
+				TopLevelStatement::GlobalVariable { global: variable, attributes, .. }
+				| TopLevelStatement::Const { constant: variable, attributes, .. } => Some((variable.get_name(), attributes)),
+				// nothing can be written on these
+				TopLevelStatement::Import(_) | TopLevelStatement::Use(_) | TopLevelStatement::Impl { .. } => None,
+			})
+			.filter(|(_, attributes)| !attributes.is_empty())
+			.map(|(ident, attributes)| (ident.clone(), attributes.iter().map(|x| x.node.clone()).collect()))
+			.collect();
+
+		Self { declarations }
+	}
+
+	pub fn get(&self, ident: &Identifier) -> &[Attribute] {
+		self.declarations.get(ident).map_or(&[], Vec::as_slice)
+	}
+
+	/// Whether the declaration has the attribute with the given name, eg. "inline"
+	pub fn has(&self, ident: &Identifier, name: &str) -> bool {
+		self.get(ident).iter().any(|x| x.name() == name)
+	}
+
+	/// Message of the declaration's `@deprecated`, if it has one
+	pub fn deprecated(&self, ident: &Identifier) -> Option<&str> {
+		self.get(ident).iter().find_map(|x| match x {
+			Attribute::Deprecated(message) => Some(message.as_str()),
+			_ => None,
+		})
+	}
+
+	/// Calling convention of the declaration's `@extern`, if it has one
+	pub fn extern_abi(&self, ident: &Identifier) -> Option<&str> {
+		self.get(ident).iter().find_map(|x| match x {
+			Attribute::Extern(abi) => Some(abi.as_str()),
+			_ => None,
+		})
+	}
+
+	/// Every declaration with the attribute with the given name, sorted by name
+	pub fn with(&self, name: &str) -> Vec<&Identifier> {
+		let mut declarations: Vec<_> = self.declarations.iter()
+			.filter(|(_, attributes)| attributes.iter().any(|x| x.name() == name))
+			.map(|(ident, _)| ident)
+			.collect();
+		declarations.sort_by(|a, b| a.0.cmp(&b.0));
+		declarations
+	}
+}
@@ -0,0 +1,182 @@
+use crate::parser::ast::{Expression, operator, Spanned, Statement, TopLevelStatement};
+use crate::parser::ast::function::{Capture, CaptureMode, Function};
+use crate::parser::ast::variable::{Identifier, Type};
+
+/// How a variable is being used at the point it is referenced
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Usage {
+	Read,
+	/// Assigned to, or has its address taken
+	Place,
+}
+
+#[derive(Debug)]
+struct Local {
+	ident: Identifier,
+	mutable: bool,
+}
+
+#[derive(Debug)]
+struct LambdaFrame {
+	/// Amount of scopes that were open when the lambda began, any variable resolved to a scope
+	/// below this depth is a capture
+	depth: usize,
+	captures: Vec<Capture>,
+}
+
+#[derive(Debug, Default)]
+struct CaptureResolver {
+	scopes: Vec<Vec<Local>>,
+	lambdas: Vec<LambdaFrame>,
+}
+
+/// Fills in the captures of every lambda within the given statements
+pub fn analyse(statements: &mut [Spanned<TopLevelStatement>]) {
+	for statement in statements {
+		match &mut statement.node {
+			TopLevelStatement::Function { function, .. } => CaptureResolver::default().function(function),
+			TopLevelStatement::Impl { methods, .. } => for method in methods {
+				CaptureResolver::default().function(&mut method.function);
+			},
+			_ => {}
+		}
+	}
+}
+
+impl CaptureResolver {
+	fn function(&mut self, function: &mut Function) {
+		self.scopes.push(
+			function.signature.get_args().iter()
+				.map(|arg| Local {
+					ident: arg.get_name().clone(),
+					mutable: !matches!(arg.get_type(), Type::Const(_)),
+				})
+				.collect()
+		);
+		self.expression(&mut function.body, Usage::Read);
+		self.scopes.pop();
+	}
+
+	fn lambda(&mut self, function: &mut Function) {
+		self.lambdas.push(LambdaFrame {
+			depth: self.scopes.len(),
+			captures: vec![],
+		});
+
+		self.function(function);
+
+		if let Some(frame) = self.lambdas.pop() {
+			function.captures = frame.captures;
+		}
+	}
+
+	fn declare(&mut self, ident: Identifier, mutable: bool) {
+		if let Some(scope) = self.scopes.last_mut() {
+			scope.push(Local { ident, mutable });
+		}
+	}
+
+	fn reference(&mut self, ident: &Identifier, usage: Usage) {
+		// innermost declaration wins, names that are not found are globals and never captured
+		let Some((depth, local)) = self.scopes.iter()
+			.enumerate()
+			.rev()
+			.find_map(|(depth, scope)| scope.iter().rev().find(|x| &x.ident == ident).map(|x| (depth, x)))
+			else { return; };
+
+		let mode = if local.mutable && usage == Usage::Place {
+			CaptureMode::Reference
+		} else {
+			CaptureMode::Value
+		};
+
+		// every lambda between the use and the declaration has to carry the variable through
+		for frame in self.lambdas.iter_mut().filter(|frame| frame.depth > depth) {
+			match frame.captures.iter_mut().find(|x| &x.ident == ident) {
+				Some(capture) if mode == CaptureMode::Reference => capture.mode = mode,
+				Some(_) => {}
+				None => frame.captures.push(Capture::new(ident.clone(), mode)),
+			}
+		}
+	}
+
+	fn statement(&mut self, statement: &mut Statement) {
+		match statement {
+			Statement::Expression(expr) | Statement::Return(expr) | Statement::Delete(expr) | Statement::Defer(expr)
+			| Statement::Assert(expr) =>
+				self.expression(expr, Usage::Read),
+			Statement::Declaration { var, initialisation } => {
+				if let Some(initialisation) = initialisation {
+					self.expression(initialisation, Usage::Read);
+				}
+				self.declare(var.get_name().clone(), !matches!(var.get_type(), Type::Const(_)));
+			}
+			Statement::Destructure { pattern, initialisation, otherwise } => {
+				self.expression(initialisation, Usage::Read);
+				// the else runs before the variables of the pattern exist
+				if let Some(otherwise) = otherwise {
+					self.expression(otherwise, Usage::Read);
+				}
+				for var in pattern.node.bindings() {
+					self.declare(var.get_name().clone(), !matches!(var.get_type(), Type::Const(_)));
+				}
+			}
+			Statement::Error => {}
+		}
+	}
+
+	fn expression(&mut self, expr: &mut Expression, usage: Usage) {
+		match expr {
+			Expression::Binary { lhs, operator: operator::Binary::Assignment, rhs, .. } => {
+				self.expression(lhs, Usage::Place);
+				self.expression(rhs, Usage::Read);
+			}
+			// writing to a field writes to the object it belongs to
+			Expression::Binary { lhs, operator: operator::Binary::Dot, rhs, .. } => {
+				self.expression(lhs, usage);
+				self.expression(rhs, Usage::Read);
+			}
+			Expression::Binary { lhs, rhs, .. } => {
+				self.expression(lhs, Usage::Read);
+				self.expression(rhs, Usage::Read);
+			}
+			Expression::Unary { operator: operator::Unary::Reference, expr } => self.expression(expr, Usage::Place),
+			Expression::Unary { expr, .. } | Expression::Cast { from: expr, .. } | Expression::Dyn { data: expr, .. }
+			| Expression::DynMethod { object: expr, .. } =>
+				self.expression(expr, Usage::Read),
+			Expression::FunctionCall { function, arguments } => {
+				self.expression(function, Usage::Read);
+				for argument in arguments {
+					self.expression(argument, Usage::Read);
+				}
+			}
+			Expression::Array(elements) | Expression::Tuple(elements) | Expression::New { arguments: elements, .. } => {
+				for element in elements {
+					self.expression(element, Usage::Read);
+				}
+			}
+			Expression::NewArray { length, .. } => self.expression(length, Usage::Read),
+			Expression::If { condition, then, otherwise } => {
+				self.expression(condition, Usage::Read);
+				self.expression(then, Usage::Read);
+				if let Some(otherwise) = otherwise {
+					self.expression(otherwise, Usage::Read);
+				}
+			}
+			Expression::ObjectReference(ident) => self.reference(ident, usage),
+			Expression::ArrayAccess { expr, index } => {
+				self.expression(expr, usage);
+				self.expression(index, Usage::Read);
+			}
+			Expression::Literal(_) | Expression::Error => {}
+			Expression::Lambda(function) => self.lambda(function),
+			Expression::Scope(body) => {
+				self.scopes.push(vec![]);
+				for statement in body {
+					self.statement(statement);
+				}
+				self.scopes.pop();
+			}
+		}
+	}
+}
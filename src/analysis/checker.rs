@@ -0,0 +1,1554 @@
+use std::collections::{HashMap, HashSet};
+use crate::analysis::{Error, Result};
+use crate::analysis::alias::Aliases;
+use crate::analysis::constant::{self, Constants, Definitions};
+use crate::analysis::defer;
+use crate::parser::ast::{Expression, Method, operator, Spanned, Statement, TopLevelStatement, Visibility};
+use crate::parser::ast::attribute::Attribute;
+use crate::parser::ast::function::{CaptureMode, Function, FunctionSignature, Generic};
+use crate::parser::ast::pattern::Pattern;
+use crate::parser::ast::variable::{Identifier, Type, Variable};
+use crate::parser::token::{Literal, Located, Trace};
+
+/// Type an impl block or interface refers to itself with
+const SELF: &str = "Self";
+
+/// Type checks every function, filling in the types of declarations that were left to be inferred.
+/// Generic functions are checked once for any types they could be called with, and again for
+/// every set of types they are called with. Those instances are added to the program as functions
+/// of their own, eg. `total<Circle>`
+pub fn check(statements: &mut Vec<Spanned<TopLevelStatement>>) -> Result {
+	let mut checker = Checker::default();
+	checker.interfaces.extend(well_known());
+
+	// aliases and interfaces can be used before they are declared, so are all known before any
+	// type is expanded
+	for statement in statements.iter() {
+		match &statement.node {
+			TopLevelStatement::TypeAlias { ident, parameters, ty, .. } =>
+				checker.aliases.insert(ident.clone(), parameters.clone(), ty.clone()),
+			TopLevelStatement::Interface { ident, methods, .. } => {
+				checker.interfaces.insert(ident.clone(), methods.clone());
+			}
+			_ => {}
+		}
+	}
+
+	// methods belong to the alias they are implemented for, not to every type of its layout
+	for statement in statements.iter() {
+		if let TopLevelStatement::Impl { ty: Type::Custom { name, .. }, .. } = &statement.node {
+			checker.aliases.set_nominal(name);
+		}
+	}
+
+	checker.constants(statements)?;
+
+	for statement in statements.iter() {
+		let expand = |ty: Type| checker.aliases.expand(&ty).map_err(|x| x.at(&statement.trace));
+
+		// tests are called by the test runner, which has nothing to pass them
+		if let TopLevelStatement::Function { ident, generics, function, attributes, .. } = &statement.node {
+			let test = attributes.iter().any(|x| x.node == Attribute::Test);
+			if test && (!generics.is_empty() || !function.signature.get_args().is_empty()) {
+				return Error::InvalidTest(ident.clone()).at(&statement.trace).into();
+			}
+		}
+
+		// a second definition would replace the first, such as two tests of the same name
+		let defined = match &statement.node {
+			TopLevelStatement::Function { ident, .. } => Some(ident),
+			TopLevelStatement::GlobalVariable { global, .. } => Some(global.get_name()),
+			_ => None,
+		};
+		if let Some(ident) = defined {
+			if checker.globals.contains_key(ident) || checker.templates.contains_key(ident) {
+				return Error::Redefinition(ident.clone()).at(&statement.trace).into();
+			}
+		}
+
+		match &statement.node {
+			TopLevelStatement::Function { ident, generics, function, .. } if !generics.is_empty() => {
+				checker.interfaces_exist(generics.iter().flat_map(|x| &x.bounds)).map_err(|x| x.at(&statement.trace))?;
+				checker.templates.insert(ident.clone(), Template {
+					generics: generics.clone(),
+					function: function.clone(),
+					trace: statement.trace.clone(),
+				});
+			}
+			TopLevelStatement::Function { ident, function, .. } => {
+				let ty = expand(function.as_type())?;
+				checker.globals.insert(ident.clone(), ty);
+			}
+			TopLevelStatement::GlobalVariable { global, .. } => {
+				let ty = expand(global.get_type().clone())?;
+				checker.sized(&ty).map_err(|x| x.at(&statement.trace))?;
+				checker.globals.insert(global.get_name().clone(), ty);
+			}
+			TopLevelStatement::Impl { ty, interface, methods } => {
+				let target = expand(ty.clone())?;
+
+				checker.aliases.push_parameters(HashMap::from([(SELF.into(), target.clone())]));
+				let result = checker.register_impl(&target, interface.as_ref(), methods);
+				checker.aliases.pop_parameters();
+				result.map_err(|x| x.at(&statement.trace))?;
+			}
+			// aliases, interfaces and constants were gathered above, and imports resolved by the module loader
+			TopLevelStatement::TypeAlias { .. } | TopLevelStatement::Interface { .. } | TopLevelStatement::Const { .. }
+			| TopLevelStatement::Import(_) | TopLevelStatement::Use(_) => {}
+		}
+	}
+
+	for statement in statements.iter_mut() {
+		match &mut statement.node {
+			TopLevelStatement::Function { function, generics, constant, .. } if generics.is_empty() => {
+				checker.function(function).map_err(|x| x.at(&statement.trace))?;
+				if *constant {
+					checker.aliases.constants().check_function(function).map_err(|x| x.at(&statement.trace))?;
+				}
+			}
+			TopLevelStatement::Function { ident, .. } => checker.generic(ident).map_err(|x| x.at(&statement.trace))?,
+			// left as the literal it evaluated to, once the expression is known to be of its type
+			TopLevelStatement::Const { constant, value, .. } => {
+				checker.check(value, constant.get_type()).map_err(|x| x.at(&statement.trace))?;
+				if let Some(literal) = checker.aliases.constants().get(constant.get_name()) {
+					value.node = Expression::Literal(literal.clone());
+				}
+			}
+			TopLevelStatement::Impl { ty, methods, .. } => {
+				let target = checker.aliases.expand(ty).map_err(|x| x.at(&statement.trace))?;
+
+				checker.aliases.push_parameters(HashMap::from([(SELF.into(), target)]));
+				let result = methods.iter_mut()
+					.try_for_each(|x| checker.function(&mut x.function).map(|_| ()).map_err(|error| error.at(&x.trace)));
+				checker.aliases.pop_parameters();
+				result?;
+			}
+			_ => {}
+		}
+	}
+
+	statements.append(&mut checker.instances);
+	Ok(())
+}
+
+/// Checks that a value of type 'given' can be used where 'expected' is wanted, and returns
+/// 'expected' with any of its [`Type::Infer`] holes filled in from 'given'
+pub fn coerce(expected: &Type, given: &Type) -> Option<Type> {
+	// constness of a value being read does not matter, only where it is stored
+	let given = given.value_under_cost();
+
+	match expected {
+		Type::Const(expected) => coerce_inner(expected, &given).map(|x| x.as_const()),
+		expected => coerce_inner(expected, &given),
+	}
+}
+
+/// Same as [`coerce`] but for types behind a pointer or inside of another type, where constness
+/// can be added but never removed
+fn coerce_inner(expected: &Type, given: &Type) -> Option<Type> {
+	match (expected, given) {
+		(Type::Infer, given) => Some(given.clone()),
+		(Type::Const(expected), given) => coerce_inner(expected, &given.value_under_cost()).map(|x| x.as_const()),
+		(_, Type::Const(_)) => None,
+		(Type::Pointer(expected), Type::Pointer(given)) => coerce_inner(expected, given).map(|x| x.as_pointer()),
+		(Type::Nullable(expected), Type::Pointer(given) | Type::Nullable(given)) =>
+			coerce_inner(expected, given).map(|x| x.as_nullable()),
+		(Type::Array { ty: expected, length }, Type::Array { ty: given, length: given_length })
+		if length == given_length => coerce_inner(expected, given).map(|x| x.as_array(*length)),
+		// only reachable behind a pointer, which is where an array forgets its length
+		(Type::Slice(expected), Type::Array { ty: given, .. } | Type::Slice(given)) =>
+			coerce_inner(expected, given).map(|x| x.as_slice()),
+		(Type::Tuple(expected), Type::Tuple(given)) if expected.len() == given.len() => Some(Type::Tuple(
+			expected.iter().zip(given).map(|(e, g)| coerce_inner(e, g)).collect::<Option<_>>()?
+		)),
+		(Type::Custom { name, template_args }, Type::Custom { name: given_name, template_args: given_args })
+		if name == given_name => {
+			if template_args == &[Type::Infer] {
+				return Some(given.clone());
+			}
+
+			if template_args.len() != given_args.len() {
+				return None;
+			}
+
+			Some(Type::template(
+				name.clone(),
+				template_args.iter().zip(given_args).map(|(e, g)| coerce_inner(e, g)).collect::<Option<_>>()?,
+			))
+		}
+		(Type::Function(expected), Type::Function(given)) => {
+			if expected.get_args().len() != given.get_args().len() {
+				return None;
+			}
+
+			let args = expected.get_args().iter()
+				.zip(given.get_args())
+				.map(|(e, g)| coerce_inner(e.get_type(), g.get_type()))
+				.collect::<Option<_>>()?;
+
+			Some(FunctionSignature::new(args, coerce_inner(expected.get_return_type(), given.get_return_type())?).into())
+		}
+		(expected, given) if expected == given => Some(given.clone()),
+		_ => None
+	}
+}
+
+/// Function of an impl block taking a 'self', which can be called as a method
+#[derive(Debug, Clone)]
+struct Callable {
+	target: Type,
+	/// Type of the 'self' argument, either the target or a pointer to it
+	receiver: Type,
+	function: Identifier,
+}
+
+/// Generic function, which is only checked once it is known what types it is called with
+#[derive(Debug, Clone)]
+struct Template {
+	generics: Vec<Generic>,
+	function: Function,
+	trace: Trace,
+}
+
+#[derive(Debug, Default)]
+struct Checker {
+	globals: HashMap<Identifier, Type>,
+	/// Methods of every type, by the name they are called with
+	methods: HashMap<Identifier, Vec<Callable>>,
+	/// Methods each interface asks for, in which 'Self' stands for the implementing type
+	interfaces: HashMap<Identifier, Vec<(Identifier, FunctionSignature)>>,
+	/// Interfaces along with the types that implement them, and the functions that do so in the
+	/// order the interface declares them, which is the vtable of the type's interface objects
+	implemented: HashMap<(Identifier, Type), Vec<Identifier>>,
+	templates: HashMap<Identifier, Template>,
+	/// Names of the instances of generic functions, by the function and the types they are for
+	instantiated: HashMap<(Identifier, Vec<Type>), Identifier>,
+	/// Checked instances, added to the program once every function is checked
+	instances: Vec<Spanned<TopLevelStatement>>,
+	aliases: Aliases,
+	scopes: Vec<HashMap<Identifier, Type>>,
+	/// Return types of the functions currently being checked, innermost last
+	returns: Vec<Type>,
+	/// Nullable pointers known not to be null, in frames that end along with the branch or scope
+	/// they were checked in
+	narrowed: Vec<HashSet<Identifier>>,
+	/// Variables of the functions being checked that have their address taken somewhere, which
+	/// are never known not to be null as they could be set to null through a pointer at any point
+	addressed: Vec<HashSet<Identifier>>,
+}
+
+impl Checker {
+	/// Evaluates every constant, as types can use them before anything else is checked. They are
+	/// known by their value wherever a constant expression is asked for and by their type
+	/// everywhere else
+	fn constants(&mut self, statements: &mut [Spanned<TopLevelStatement>]) -> Result {
+		let mut definitions = Definitions::default();
+		let mut constants = vec![];
+
+		// every constant is known before any is evaluated, as they can refer to each other in any order
+		for statement in statements.iter_mut() {
+			if let TopLevelStatement::Function { ident, generics, function, constant: true, .. } = &statement.node {
+				// generic functions are only ever checked for the types they are called with
+				if generics.is_empty() {
+					let signature = function.signature.clone().into();
+					let Type::Function(signature) = self.aliases.structural(&signature).map_err(|x| x.at(&statement.trace))? else {
+						unreachable!("expanding a function type gives a function type");
+					};
+					definitions.insert_function(ident.clone(), Function::new(*signature, function.body.clone()));
+				}
+				continue;
+			}
+
+			let TopLevelStatement::Const { constant, value, .. } = &mut statement.node else { continue; };
+
+			let ty = self.aliases.expand(constant.get_type()).map_err(|x| x.at(&statement.trace))?;
+			self.globals.insert(constant.get_name().clone(), ty.as_const());
+
+			// constants are evaluated and run knowing only the layout of their type
+			let ty = self.aliases.structural(&ty).map_err(|x| x.at(&statement.trace))?;
+			*constant = Variable::new(constant.get_name().clone(), ty.clone());
+
+			definitions.insert(constant.get_name().clone(), ty, value.clone());
+			constants.push((constant.get_name().clone(), statement.trace.clone()));
+		}
+
+		for (constant, trace) in constants {
+			definitions.value(&constant).map_err(|x| x.at(&trace))?;
+		}
+
+		self.aliases.set_constants(definitions);
+		Ok(())
+	}
+
+	/// Registers the functions of an impl block, checking that they are those of its interface if
+	/// it has one. 'Self' is expected to stand for the target while this runs
+	fn register_impl(&mut self, target: &Type, interface: Option<&Identifier>, methods: &[Spanned<Method>]) -> Result {
+		for method in methods {
+			if self.globals.contains_key(&method.ident) {
+				return Error::DuplicateMethod { ty: target.clone(), method: method_name(&method.ident) }.at(&method.trace).into();
+			}
+
+			let function = self.aliases.expand(&method.function.as_type()).map_err(|x| x.at(&method.trace))?;
+
+			let receiver = method.function.signature.get_args().first().filter(|x| x.get_name().0 == "self");
+			if let Some(receiver) = receiver {
+				let receiver = self.aliases.expand(receiver.get_type()).map_err(|x| x.at(&method.trace))?;
+				if !receives(target, &receiver) {
+					return Error::InvalidSelf { ty: target.clone(), given: receiver }.at(&method.trace).into();
+				}
+
+				self.methods.entry(method_name(&method.ident)).or_default().push(Callable {
+					target: target.clone(),
+					receiver,
+					function: method.ident.clone(),
+				});
+			}
+
+			self.globals.insert(method.ident.clone(), function);
+		}
+
+		let Some(interface) = interface else {
+			return Ok(());
+		};
+
+		self.interfaces_exist([interface])?;
+		let required = &self.interfaces[interface];
+
+		for method in methods {
+			if !required.iter().any(|(name, _)| *name == method_name(&method.ident)) {
+				return Error::NotInInterface { interface: interface.clone(), method: method_name(&method.ident) }
+					.at(&method.trace).into();
+			}
+		}
+
+		let mut vtable = vec![];
+		for (name, signature) in required {
+			let Some(method) = methods.iter().find(|x| method_name(&x.ident) == *name) else {
+				return Error::MissingMethod { interface: interface.clone(), method: name.clone() }.into();
+			};
+
+			// holes in the interface's signature are left for each implementation to fill
+			let expected = self.aliases.expand(&signature.as_unnamed_type())?;
+			let given = &self.globals[&method.ident];
+			if coerce(&expected, given).as_ref() != Some(given) {
+				return Error::SignatureMismatch { method: name.clone(), expected, given: given.clone() }.at(&method.trace).into();
+			}
+			vtable.push(method.ident.clone());
+		}
+
+		self.implemented.insert((interface.clone(), target.value_under_cost()), vtable);
+		Ok(())
+	}
+
+	fn interfaces_exist<'a>(&self, interfaces: impl IntoIterator<Item = &'a Identifier>) -> Result {
+		interfaces.into_iter()
+			.find(|x| !self.interfaces.contains_key(*x))
+			.map_or(Ok(()), |interface| Error::UnknownInterface(interface.clone()).into())
+	}
+
+	fn function(&mut self, function: &mut Function) -> Result<Type> {
+		let args = function.signature.get_args().iter()
+			.map(|arg| {
+				let ty = self.aliases.expand(arg.get_type())?;
+				self.sized(&ty)?;
+				Ok(Variable::new(arg.get_name().clone(), ty))
+			})
+			.collect::<Result<Vec<_>>>()?;
+
+		let written = function.signature.get_return_type().clone();
+		let returns = self.aliases.expand(&written)?;
+		self.sized(&returns)?;
+		function.signature = FunctionSignature::new_named(args, returns.clone());
+
+		self.scopes.push(
+			function.signature.get_args().iter()
+				.map(|arg| (arg.get_name().clone(), arg.get_type().clone()))
+				.collect()
+		);
+		self.returns.push(returns.clone());
+
+		let mut found = HashSet::new();
+		addressed(&function.body.node, &mut found);
+		self.addressed.push(found);
+
+		// functions returning unit are free to discard the value of their body
+		let result = if returns == Type::Unit {
+			self.expression(&mut function.body, None).map(|_| Type::Unit)
+		} else {
+			self.check_written(&mut function.body, &written, &returns)
+		};
+
+		self.addressed.pop();
+		self.returns.pop();
+		self.scopes.pop();
+
+		let returns = result?;
+		if returns.contains_infer() {
+			return Error::CannotInfer("return type".into()).into();
+		}
+		self.sized(&returns)?;
+
+		function.signature = FunctionSignature::new_named(function.signature.get_args().to_vec(), returns);
+		Ok(function.as_type())
+	}
+
+	fn declare(&mut self, ident: Identifier, ty: Type) {
+		// a new variable shadowing a checked one has not been checked itself
+		self.forget(&ident);
+
+		if let Some(scope) = self.scopes.last_mut() {
+			scope.insert(ident, ty);
+		}
+	}
+
+	fn lookup(&self, ident: &Identifier) -> Result<Type> {
+		self.scopes.iter()
+			.rev()
+			.find_map(|scope| scope.get(ident))
+			.or_else(|| self.globals.get(ident))
+			.map(|ty| self.narrow(ident, ty.clone()))
+			.ok_or_else(|| if self.templates.contains_key(ident) {
+				Error::GenericValue(ident.clone())
+			} else {
+				Error::UnknownVariable(ident.clone())
+			})
+	}
+
+	/// Type of a variable once it is known not to be null, if it is
+	fn narrow(&self, ident: &Identifier, ty: Type) -> Type {
+		if !self.narrowed.iter().any(|x| x.contains(ident)) || self.addressed.iter().any(|x| x.contains(ident)) {
+			return ty;
+		}
+
+		match ty {
+			Type::Const(ty) => self.narrow(ident, *ty).as_const(),
+			Type::Nullable(pointee) => Type::Pointer(pointee),
+			ty => ty,
+		}
+	}
+
+	/// Stops treating a variable as checked, as it may be given another value
+	fn forget(&mut self, ident: &Identifier) {
+		for frame in &mut self.narrowed {
+			frame.remove(ident);
+		}
+	}
+
+	/// Checks an expression with the given variables known not to be null
+	fn narrowed<T>(&mut self, idents: Vec<Identifier>, check: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+		self.narrowed.push(idents.into_iter().collect());
+		let result = check(self);
+		self.narrowed.pop();
+		result
+	}
+
+	fn statement(&mut self, statement: &mut Spanned<Statement>, expected: Option<&Type>) -> Result<Type> {
+		self.statement_node(&mut statement.node, expected).map_err(|error| error.at(&statement.trace))
+	}
+
+	fn statement_node(&mut self, statement: &mut Statement, expected: Option<&Type>) -> Result<Type> {
+		match statement {
+			Statement::Expression(expr) => match expected {
+				Some(expected) => self.check(expr, expected),
+				None => self.expression(expr, None),
+			},
+			Statement::Declaration { var, initialisation } => {
+				let written = var.get_type().clone();
+				let mut ty = self.aliases.expand(&written)?;
+
+				if let Some(initialisation) = initialisation {
+					ty = self.check_written(initialisation, &written, &ty)?;
+				}
+
+				self.bind(var, ty)?;
+				Ok(Type::Unit)
+			}
+			Statement::Destructure { pattern, initialisation, otherwise } => {
+				let hint = self.aliases.expand(&written(&pattern.node))?;
+				let given = self.expression(initialisation, Some(&hint))?;
+
+				// the variables of the pattern do not exist yet where it does not match
+				match otherwise {
+					Some(otherwise) => {
+						self.expression(otherwise, None)?;
+						if !diverges(otherwise) {
+							return Error::FallthroughElse.at(&otherwise.trace).into();
+						}
+					}
+					None if pattern.is_refutable() => return Error::RefutablePattern.at(&pattern.trace).into(),
+					None => {}
+				}
+
+				self.pattern(pattern, given)?;
+				Ok(Type::Unit)
+			}
+			Statement::Return(expr) => {
+				let returns = self.returns.last().cloned().unwrap_or(Type::Unit);
+				self.check(expr, &returns)?;
+
+				// a return never produces a value where it is, so it fits whatever was expected
+				Ok(expected.cloned().unwrap_or(Type::Unit))
+			}
+			Statement::Delete(expr) => match self.expression(expr, None)?.value_under_cost() {
+				// deleting null does nothing
+				Type::Pointer(_) | Type::Nullable(_) => Ok(Type::Unit),
+				ty => Error::NotAPointer(ty).into(),
+			},
+			Statement::Assert(condition) => {
+				self.check(condition, &Type::Bool)?;
+				Ok(Type::Unit)
+			}
+			Statement::Defer(expr) => {
+				if defer::returns(expr) {
+					return Error::ReturnInDefer.at(&expr.trace).into();
+				}
+
+				self.expression(expr, None)?;
+				Ok(Type::Unit)
+			}
+			Statement::Error => Error::Unparsed.into(),
+		}
+	}
+
+	/// Checks that values of the type have a size, so can be passed around and stored
+	fn sized(&self, ty: &Type) -> Result {
+		if is_unsized(&self.aliases.structural(ty)?) {
+			return Error::Unsized(ty.clone()).into();
+		}
+		Ok(())
+	}
+
+	/// Declares a variable with the type it was found to have
+	fn bind(&mut self, var: &mut Variable, ty: Type) -> Result {
+		if ty.contains_infer() {
+			return Error::CannotInfer(var.get_name().clone()).into();
+		}
+
+		// the program runs knowing only the layout of the variable
+		let layout = self.aliases.structural(&ty)?;
+		if is_unsized(&layout) {
+			return Error::Unsized(ty).into();
+		}
+
+		*var = Variable::new(var.get_name().clone(), layout);
+		self.declare(var.get_name().clone(), ty);
+		Ok(())
+	}
+
+	/// Checks that a value of the given type can be taken apart by the pattern, declaring the
+	/// variables it binds
+	fn pattern(&mut self, pattern: &mut Spanned<Pattern>, given: Type) -> Result {
+		self.pattern_node(&mut pattern.node, given).map_err(|error| error.at(&pattern.trace))
+	}
+
+	fn pattern_node(&mut self, pattern: &mut Pattern, given: Type) -> Result {
+		match pattern {
+			Pattern::Binding(var) => {
+				let written = var.get_type().clone();
+				let expected = self.aliases.expand(&written)?;
+				let ty = self.fits(&written, &expected, given)?;
+				self.bind(var, ty)
+			}
+			Pattern::Wildcard => Ok(()),
+			Pattern::Literal(literal) => {
+				let ty = Self::literal(literal, Some(&given.value_under_cost()));
+				if matches!(literal, Literal::String(_)) || coerce(&given, &ty).is_none() {
+					return Error::PatternMismatch(given).into();
+				}
+				Ok(())
+			}
+			Pattern::Tuple(elements) => {
+				let types = match self.aliases.underlying(&given)?.value_under_cost() {
+					Type::Tuple(types) if types.len() == elements.len() => types,
+					_ => return Error::Destructure { expected: elements.len(), given }.into(),
+				};
+
+				for (element, ty) in elements.iter_mut().zip(types) {
+					self.pattern(element, ty)?;
+				}
+				Ok(())
+			}
+			Pattern::Array { elements, rest } => {
+				let fits = |length: usize| if *rest { length >= elements.len() } else { length == elements.len() };
+				let Type::Array { ty, length } = self.aliases.underlying(&given)?.value_under_cost() else {
+					return Error::PatternMismatch(given).into();
+				};
+				if !fits(length) {
+					return Error::PatternMismatch(given).into();
+				}
+
+				for element in elements {
+					self.pattern(element, ty.as_ref().clone())?;
+				}
+				Ok(())
+			}
+		}
+	}
+
+	/// Finds the type of an expression and checks that it is usable as the expected type
+	fn check(&mut self, expr: &mut Spanned<Expression>, expected: &Type) -> Result<Type> {
+		self.check_written(expr, expected, expected)
+	}
+
+	/// Same as [`Checker::check`], for an expected type that was written with aliases, which are
+	/// named when the expression does not fit, as they are what the reader wrote
+	fn check_written(&mut self, expr: &mut Spanned<Expression>, written: &Type, expected: &Type) -> Result<Type> {
+		let given = self.expression(expr, Some(expected))?;
+
+		// pointers are made into interface objects wherever one is expected
+		if let (Type::Dyn(interface), Type::Pointer(pointee)) = (expected.value_under_cost(), given.value_under_cost()) {
+			self.make_dyn(expr, interface, &pointee).map_err(|x| x.at(&expr.trace))?;
+			return Ok(expected.clone());
+		}
+
+		self.fits(written, expected, given).map_err(|x| x.at(&expr.trace))
+	}
+
+	/// Wraps a pointer in an interface object, if what it points to implements the interface
+	fn make_dyn(&self, expr: &mut Spanned<Expression>, interface: Identifier, pointee: &Type) -> Result {
+		let Some(vtable) = self.implemented.get(&(interface.clone(), pointee.value_under_cost())) else {
+			return Error::Unimplemented { ty: pointee.value_under_cost(), interface }.into();
+		};
+
+		let trace = expr.trace.clone();
+		let data = std::mem::replace(expr, Spanned::new(Expression::Error, trace.clone()));
+		*expr = Spanned::new(Expression::Dyn { data: Box::new(data), interface, vtable: vtable.clone() }, trace);
+		Ok(())
+	}
+
+	/// Type of a method of an interface object, without its 'self' as the object is passed as it
+	fn dyn_method(&mut self, interface: &Identifier, method: &Identifier) -> Result<Type> {
+		let signature = self.interfaces.get(interface)
+			.and_then(|x| x.iter().find(|(name, _)| name == method))
+			.filter(|(_, signature)| signature.get_args().first().is_some_and(|x| x.get_name().0 == "self"));
+
+		let Some((_, signature)) = signature else {
+			return Error::UnknownMethod { ty: Type::Dyn(interface.clone()), method: method.clone() }.into();
+		};
+
+		// the type behind the object is unknown, so 'Self' is left as a hole that must not show up
+		let rest = FunctionSignature::new(
+			signature.get_args()[1..].iter().map(|x| x.get_type().clone()).collect(),
+			signature.get_return_type().clone(),
+		);
+		self.aliases.push_parameters(HashMap::from([(SELF.into(), Type::Infer)]));
+		let ty = self.aliases.expand(&rest.into());
+		self.aliases.pop_parameters();
+
+		let ty = ty?;
+		if ty.contains_infer() {
+			return Error::NotObjectSafe { interface: interface.clone(), method: method.clone() }.into();
+		}
+		Ok(ty)
+	}
+
+	/// Coerces the given type to the expected one, see [`Checker::check_written`]. A type of its
+	/// own and its layout are made into each other as they are, eg. `let v: Vec2 = (1.0, 2.0)`
+	fn fits(&self, written: &Type, expected: &Type, given: Type) -> Result<Type> {
+		if let Some(ty) = coerce(expected, &given) {
+			return Ok(ty);
+		}
+		if coerce(&self.aliases.underlying(expected)?, &given).is_some() {
+			return Ok(expected.clone());
+		}
+
+		coerce(expected, &self.aliases.underlying(&given)?).ok_or_else(|| if written == expected {
+			Error::TypeMismatch { expected: expected.clone(), given }
+		} else {
+			Error::AliasMismatch { alias: Box::new(written.clone()), expected: Box::new(expected.clone()), given }
+		})
+	}
+
+	/// Finds the type of an expression, the expected type is only a hint used for literals and
+	/// is not checked against
+	fn expression(&mut self, expr: &mut Spanned<Expression>, expected: Option<&Type>) -> Result<Type> {
+		self.expression_node(&mut expr.node, expected).map_err(|error| error.at(&expr.trace))
+	}
+
+	fn expression_node(&mut self, expr: &mut Expression, expected: Option<&Type>) -> Result<Type> {
+		// literals are of the layout of the type they are made into
+		let hint = expected.map(|x| self.aliases.underlying(&x.value_under_cost())).transpose()?;
+
+		match expr {
+			Expression::Literal(literal) => Ok(Self::literal(literal, hint.as_ref())),
+			Expression::ObjectReference(ident) => self.lookup(ident),
+			Expression::Binary { .. } => self.binary(expr, hint.as_ref()),
+			Expression::Unary { .. } => self.unary(expr, hint.as_ref()),
+			Expression::Cast { from, to } => {
+				let ty = self.aliases.expand(to)?;
+				*to = self.aliases.structural(&ty)?;
+				if let Type::Dyn(_) = ty.value_under_cost() {
+					return self.check(from, &ty);
+				}
+
+				self.expression(from, None)?;
+				Ok(ty)
+			}
+			Expression::Dyn { interface, .. } => Ok(Type::Dyn(interface.clone())),
+			Expression::DynMethod { object, method, .. } => match self.expression(object, None)?.value_under_cost() {
+				Type::Dyn(interface) => self.dyn_method(&interface, method),
+				ty => Error::UnknownMethod { ty, method: method.clone() }.into(),
+			},
+			Expression::FunctionCall { function, arguments } => {
+				self.method_call(function, arguments)?;
+				self.instantiate(function, arguments)?;
+
+				let ty = self.expression(function, None)?;
+				let Type::Function(signature) = ty.value_under_cost() else {
+					return Error::NotCallable(ty).into();
+				};
+
+				if signature.get_args().len() != arguments.len() {
+					return Error::ArgumentCount {
+						expected: signature.get_args().len(),
+						given: arguments.len(),
+					}.into();
+				}
+
+				for (argument, arg) in arguments.iter_mut().zip(signature.get_args()) {
+					self.check(argument, arg.get_type())?;
+				}
+
+				Ok(signature.get_return_type().clone())
+			}
+			Expression::ArrayAccess { .. } => self.array_access(expr),
+			Expression::Array(elements) => self.array(elements, hint.as_ref()),
+			Expression::Tuple(elements) => {
+				let hints = match hint {
+					Some(Type::Tuple(hints)) if hints.len() == elements.len() => hints,
+					_ => vec![Type::Infer; elements.len()],
+				};
+
+				// elements are copied out of wherever they came from, so are never constant themselves
+				Ok(Type::Tuple(elements.iter_mut()
+					.zip(hints)
+					.map(|(element, hint)| Ok(self.check(element, &hint.value_under_cost())?.value_under_cost()))
+					.collect::<Result<_>>()?))
+			}
+			Expression::New { ty: allocated, arguments, .. } => {
+				let mut ty = self.aliases.expand(allocated)?;
+
+				// template arguments left out of the allocated type come from the pointer it is stored in
+				if let Some(Type::Pointer(pointee) | Type::Nullable(pointee)) = &hint {
+					if let Some(filled) = coerce(&ty, pointee) {
+						ty = filled;
+					}
+				}
+
+				*allocated = self.aliases.structural(&ty)?;
+				if ty.contains_infer() || allocated.value_under_cost().size_of().is_none() {
+					return Error::NotConstructible(ty).into();
+				}
+
+				match arguments.as_mut_slice() {
+					[] => {}
+					[value] => { self.check(value, &ty)?; }
+					arguments => return Error::ArgumentCount { expected: 1, given: arguments.len() }.into(),
+				}
+
+				Ok(ty.as_pointer())
+			}
+			Expression::NewArray { ty: element, length, .. } => {
+				let ty = self.aliases.expand(element)?;
+				self.sized(&ty)?;
+				*element = self.aliases.structural(&ty)?;
+				self.check(length, &Type::USize)?;
+
+				// a length only known when running allocates a slice rather than an array
+				match constant::length(length, &mut self.aliases.constants()) {
+					Ok(length) => Ok(ty.as_array(length).as_pointer()),
+					Err(error) if matches!(error.kind(), Error::NotConstant) => Ok(ty.as_slice().as_pointer()),
+					Err(error) => Err(error),
+				}
+			}
+			Expression::Lambda(function) => self.function(function),
+			Expression::Scope(body) => self.scope(body, expected),
+			Expression::If { condition, then, otherwise } => self.conditional(condition, then, otherwise.as_deref_mut(), expected),
+			Expression::Error => Error::Unparsed.into(),
+		}
+	}
+
+	fn array_access(&mut self, expr: &mut Expression) -> Result<Type> {
+		let Expression::ArrayAccess { expr: array, index } = expr else {
+			unreachable!("only called with array accesses");
+		};
+
+		let ty = self.expression(array, None)?;
+
+		// types indexed through 'Index' are so through pointers too, as arrays are
+		let target = match ty.value_under_cost() {
+			Type::Pointer(pointee) => pointee.value_under_cost(),
+			ty => ty,
+		};
+		if let Some(function) = self.overloaded(("Index", "index"), &target) {
+			return self.overload_index(expr, function, &ty);
+		}
+
+		// elements of a constant array are constant themselves
+		let layout = self.aliases.underlying(&ty)?;
+		let element = match layout.value_under_cost() {
+			Type::Array { ty: element, .. } if matches!(layout, Type::Const(_)) => element.as_const(),
+			Type::Array { ty: element, .. } => *element,
+			Type::Nullable(_) => return Error::MaybeNull(ty).into(),
+			Type::Pointer(array) => match self.aliases.underlying(&array)?.value_under_cost() {
+				Type::Array { ty: element, .. } | Type::Slice(element) if matches!(array.as_ref(), Type::Const(_)) =>
+					element.as_const(),
+				Type::Array { ty: element, .. } | Type::Slice(element) => *element,
+				_ => return Error::NotIndexable(ty).into(),
+			},
+			_ => return Error::NotIndexable(ty).into(),
+		};
+
+		let index_ty = self.expression(index, Some(&Type::USize))?.value_under_cost();
+		if !index_ty.is_integer() {
+			return Error::TypeMismatch { expected: Type::USize, given: index_ty }.into();
+		}
+
+		Ok(element)
+	}
+
+	/// Turns a method call such as `p.length()` into a call of the function it names, eg.
+	/// `Point::length(&p)`, taking the address of or dereferencing the receiver to fit its 'self'
+	fn method_call(&mut self, function: &mut Spanned<Expression>, arguments: &mut Vec<Spanned<Expression>>) -> Result {
+		let (ty, name) = match &mut function.node {
+			Expression::Binary { lhs, operator: operator::Binary::Dot, rhs, .. } => match &rhs.node {
+				Expression::ObjectReference(name) => (self.expression(lhs, None)?, name.clone()),
+				_ => return Ok(()),
+			},
+			_ => return Ok(()),
+		};
+
+		match ty.value_under_cost() {
+			Type::Nullable(_) => return Error::MaybeNull(ty).into(),
+			// called through the object's vtable, which holds the methods in the order the interface declares them
+			Type::Dyn(interface) => {
+				let slot = self.interfaces.get(&interface).and_then(|x| x.iter().position(|(method, _)| method == &name));
+				let Some(slot) = slot else {
+					return Error::UnknownMethod { ty, method: name }.into();
+				};
+
+				let Expression::Binary { lhs: object, .. } = std::mem::replace(&mut function.node, Expression::Error) else {
+					unreachable!("the function was found to be a method");
+				};
+				function.node = Expression::DynMethod { object, method: name, slot };
+				return Ok(());
+			}
+			_ => {}
+		}
+
+		let methods: Vec<_> = self.methods.get(&name).into_iter().flatten().filter(|x| receives(&x.target, &ty)).collect();
+		let method = match methods.as_slice() {
+			[method] => method,
+			[] => return Error::UnknownMethod { ty, method: name }.into(),
+			// duplicate methods are refused with the impl blocks, but one is never picked at random
+			_ => return Error::AmbiguousMethod { ty, method: name }.into(),
+		};
+
+		let by_pointer = matches!(method.receiver.value_under_cost(), Type::Pointer(_));
+		let callee = Expression::ObjectReference(method.function.clone());
+		let Expression::Binary { lhs: receiver, .. } = std::mem::replace(&mut function.node, callee) else {
+			unreachable!("the function was found to be a method");
+		};
+
+		arguments.insert(0, receive(*receiver, &ty, by_pointer).0);
+		Ok(())
+	}
+
+	/// Function overloading an operator for a type through one of the [`well_known`] interfaces,
+	/// if the type is one of its own that implements the interface. Values only of its layout keep
+	/// the operators built into them
+	fn overloaded(&self, (interface, method): (&'static str, &str), ty: &Type) -> Option<Identifier> {
+		if !matches!(ty, Type::Custom { .. }) {
+			return None;
+		}
+
+		let interface = Identifier::from(interface);
+		let vtable = self.implemented.get(&(interface.clone(), ty.clone()))?;
+		self.interfaces[&interface].iter().position(|(name, _)| name.0 == method).map(|i| vtable[i].clone())
+	}
+
+	/// Call of the function overloading an operator, given the first operand already checked to be
+	/// of type 'ty', which has its address taken or is dereferenced to fit the 'self' as for methods
+	fn operator_call(
+		&mut self,
+		function: Identifier,
+		first: Spanned<Expression>,
+		ty: &Type,
+		rest: Vec<Spanned<Expression>>,
+	) -> Result<(Spanned<Expression>, Type)> {
+		let Type::Function(signature) = self.globals[&function].clone() else {
+			unreachable!("operators are overloaded by functions");
+		};
+
+		let Some((receiver, others)) = signature.get_args().split_first().filter(|(_, x)| x.len() == rest.len()) else {
+			return Error::ArgumentCount { expected: signature.get_args().len(), given: rest.len() + 1 }.into();
+		};
+
+		let trace = first.trace.clone();
+		let (first, given) = receive(first, ty, matches!(receiver.get_type().value_under_cost(), Type::Pointer(_)));
+		self.fits(receiver.get_type(), receiver.get_type(), given).map_err(|x| x.at(&trace))?;
+
+		let mut arguments = vec![first];
+		for (mut argument, arg) in rest.into_iter().zip(others) {
+			self.check(&mut argument, arg.get_type())?;
+			arguments.push(argument);
+		}
+
+		let function = Box::new(Spanned::new(Expression::ObjectReference(function), trace.clone()));
+		Ok((Spanned::new(Expression::FunctionCall { function, arguments }, trace), signature.get_return_type().clone()))
+	}
+
+	/// Replaces a binary operator with a call of the function overloading it, eg. `a != b` with
+	/// `!Vector::equals(a, b)`
+	fn overload_binary(&mut self, expr: &mut Expression, function: Identifier, ty: &Type) -> Result<Type> {
+		let Expression::Binary { lhs, operator, rhs, .. } = std::mem::replace(expr, Expression::Error) else {
+			unreachable!("only binary operators are replaced here");
+		};
+
+		let (call, returns) = self.operator_call(function, *lhs, ty, vec![*rhs])?;
+		if operator != operator::Binary::NotEquals {
+			*expr = call.node;
+			return Ok(returns);
+		}
+
+		if returns != Type::Bool {
+			return Error::InvalidOperand { operator: format!("{operator:?}"), ty: ty.clone() }.into();
+		}
+
+		*expr = Expression::Unary { operator: operator::Unary::Not, expr: Box::new(call) };
+		Ok(Type::Bool)
+	}
+
+	/// Replaces an array access with a dereference of the pointer the function overloading it
+	/// returns, eg. `v[i]` with `*Vector::index(&v, i)`, which can be assigned to
+	fn overload_index(&mut self, expr: &mut Expression, function: Identifier, ty: &Type) -> Result<Type> {
+		let Expression::ArrayAccess { expr: array, index } = std::mem::replace(expr, Expression::Error) else {
+			unreachable!("only array accesses are replaced here");
+		};
+
+		let (call, returns) = self.operator_call(function, *array, ty, vec![*index])?;
+		let Type::Pointer(element) = returns.value_under_cost() else {
+			return Error::NotAPointer(returns).into();
+		};
+
+		*expr = Expression::Unary { operator: operator::Unary::Dereference, expr: Box::new(call) };
+		Ok(*element)
+	}
+
+	/// Points a call of a generic function at its instance for the types of the arguments, which
+	/// is checked the first time the function is called with them
+	fn instantiate(&mut self, function: &mut Spanned<Expression>, arguments: &mut [Spanned<Expression>]) -> Result {
+		let Expression::ObjectReference(ident) = &mut function.node else {
+			return Ok(());
+		};
+
+		// locals shadow every top level name
+		let Some(template) = self.templates.get(ident).filter(|_| !self.scopes.iter().any(|x| x.contains_key(ident))) else {
+			return Ok(());
+		};
+
+		let generics = template.generics.clone();
+		let parameters = template.function.signature.get_args().to_vec();
+		if parameters.len() != arguments.len() {
+			return Error::ArgumentCount { expected: parameters.len(), given: arguments.len() }.into();
+		}
+
+		// the parameters of an instance being checked are not those of the function it calls
+		self.aliases.push_parameters(HashMap::new());
+		let parameters = parameters.iter().map(|x| self.aliases.expand(x.get_type())).collect::<Result<Vec<_>>>();
+		self.aliases.pop_parameters();
+
+		let names: Vec<_> = generics.iter().map(|x| x.ident.clone()).collect();
+		let mut inferred = HashMap::new();
+		for (argument, parameter) in arguments.iter_mut().zip(&parameters?) {
+			let given = self.expression(argument, None)?;
+			infer(&names, parameter, &given, &mut inferred);
+		}
+
+		let types = names.iter()
+			.map(|x| inferred.remove(x).ok_or_else(|| Error::CannotInfer(x.clone())))
+			.collect::<Result<Vec<_>>>()?;
+
+		for (generic, ty) in generics.iter().zip(&types) {
+			if let Some(interface) = generic.bounds.iter().find(|x| !self.implemented.contains_key(&((*x).clone(), ty.clone()))) {
+				return Error::Unimplemented { ty: ty.clone(), interface: interface.clone() }.into();
+			}
+		}
+
+		let key = (ident.clone(), types);
+		*ident = match self.instantiated.get(&key) {
+			Some(instance) => instance.clone(),
+			None => self.instance(key)?,
+		};
+		Ok(())
+	}
+
+	/// Checks a generic function for any types it could be called with, its type parameters
+	/// standing for types of their own that only have the methods of the interfaces they are bound
+	/// by. The function is left as it is, as each instance is checked again for its own types
+	fn generic(&mut self, ident: &Identifier) -> Result {
+		let Template { generics, mut function, .. } = self.templates[ident].clone();
+
+		// what is made up for the parameters is forgotten once the function is checked
+		let globals = self.globals.clone();
+		let methods = self.methods.clone();
+		let implemented = self.implemented.clone();
+		let instantiated = self.instantiated.clone();
+		let instances = self.instances.len();
+
+		let result = generics.iter()
+			.try_for_each(|generic| generic.bounds.iter().try_for_each(|x| self.opaque_impl(&generic.ident, x)))
+			.and_then(|()| {
+				self.aliases.push_parameters(generics.iter().map(|x| (x.ident.clone(), Type::custom(x.ident.clone()))).collect());
+				let result = self.function(&mut function);
+				self.aliases.pop_parameters();
+				result
+			});
+
+		self.globals = globals;
+		self.methods = methods;
+		self.implemented = implemented;
+		self.instantiated = instantiated;
+		self.instances.truncate(instances);
+		result.map(|_| ())
+	}
+
+	/// Implements an interface for a type parameter bound by it, with functions that only have the
+	/// signatures the interface asks for, eg. `T::area` for `T: Shape`
+	fn opaque_impl(&mut self, parameter: &Identifier, interface: &Identifier) -> Result {
+		let target = Type::custom(parameter.clone());
+
+		self.aliases.push_parameters(HashMap::from([(SELF.into(), target.clone())]));
+		let signatures = self.interfaces[interface].iter()
+			.map(|(name, signature)| {
+				let receiver = signature.get_args().first().filter(|x| x.get_name().0 == "self");
+				let receiver = receiver.map(|x| self.aliases.expand(x.get_type())).transpose()?;
+				Ok((name.clone(), self.aliases.expand(&signature.as_type())?, receiver))
+			})
+			.collect::<Result<Vec<_>>>();
+		self.aliases.pop_parameters();
+
+		let mut vtable = vec![];
+		for (name, ty, receiver) in signatures? {
+			let function: Identifier = format!("{parameter}::{name}").into();
+			if let Some(receiver) = receiver {
+				self.methods.entry(name).or_default().push(Callable { target: target.clone(), receiver, function: function.clone() });
+			}
+			self.globals.insert(function.clone(), ty);
+			vtable.push(function);
+		}
+
+		self.implemented.insert((interface.clone(), target), vtable);
+		Ok(())
+	}
+
+	/// Checks a generic function with its type parameters standing for the given types
+	fn instance(&mut self, (ident, types): (Identifier, Vec<Type>)) -> Result<Identifier> {
+		let name: Identifier = format!("{ident}<{}>", types.iter().map(Type::name).collect::<Vec<_>>().join(", ")).into();
+		let Template { generics, mut function, trace } = self.templates[&ident].clone();
+
+		self.aliases.push_parameters(generics.into_iter().map(|x| x.ident).zip(types.iter().cloned()).collect());
+
+		// registered before the body is checked so the instance can call itself
+		self.instantiated.insert((ident, types), name.clone());
+		let result = self.aliases.expand(&function.as_type()).and_then(|ty| {
+			self.globals.insert(name.clone(), ty);
+
+			// the instance cannot see the locals of the function it is called from
+			let scopes = std::mem::take(&mut self.scopes);
+			let narrowed = std::mem::take(&mut self.narrowed);
+			let result = self.function(&mut function);
+			self.scopes = scopes;
+			self.narrowed = narrowed;
+			result
+		});
+
+		self.aliases.pop_parameters();
+		result.map_err(|x| x.at(&trace))?;
+
+		self.instances.push(Spanned::new(TopLevelStatement::Function {
+			ident: name.clone(),
+			generics: vec![],
+			function,
+			attributes: vec![],
+			visibility: Visibility::Private,
+			constant: false,
+		}, trace));
+		Ok(name)
+	}
+
+	fn scope(&mut self, body: &mut Vec<Spanned<Statement>>, expected: Option<&Type>) -> Result<Type> {
+		self.scopes.push(HashMap::new());
+		self.narrowed.push(HashSet::new());
+
+		let mut ty = Ok(Type::Unit);
+		let len = body.len();
+		for (i, statement) in body.iter_mut().enumerate() {
+			// only the last statement gives the scope its value
+			let expected = if i + 1 == len { expected } else { None };
+			ty = self.statement(statement, expected);
+			if ty.is_err() { break; }
+
+			// after 'if p == null { return }' the rest of the scope only runs when p is not null
+			if let Statement::Expression(expr) = &statement.node {
+				let checked = checked_after(expr);
+				if let Some(frame) = self.narrowed.last_mut() {
+					frame.extend(checked);
+				}
+			}
+		}
+
+		self.narrowed.pop();
+		self.scopes.pop();
+
+		let ty = ty?;
+		defer::lower(body, &ty, self.returns.last().unwrap_or(&Type::Unit));
+		Ok(ty)
+	}
+
+	fn literal(literal: &Literal, hint: Option<&Type>) -> Type {
+		match literal {
+			Literal::Integer(_) => match hint {
+				Some(hint) if hint.is_integer() => hint.clone(),
+				_ => Type::I32,
+			},
+			Literal::Float(_) => match hint {
+				Some(hint) if hint.is_float() => hint.clone(),
+				_ => Type::F64,
+			},
+			Literal::String(string) => Type::U8.as_const().as_array(string.len()).as_pointer(),
+			Literal::Character(_) => Type::Char,
+			Literal::Bool(_) => Type::Bool,
+			Literal::Unit => Type::Unit,
+			Literal::Null => match hint {
+				Some(hint @ Type::Nullable(_)) => hint.clone(),
+				_ => Type::Infer.as_nullable(),
+			},
+		}
+	}
+
+	/// Checks both branches of an 'if', which have to agree on their type unless one of them
+	/// never finishes, such as a branch ending in a return
+	fn conditional(
+		&mut self,
+		condition: &mut Spanned<Expression>,
+		then: &mut Spanned<Expression>,
+		otherwise: Option<&mut Spanned<Expression>>,
+		expected: Option<&Type>,
+	) -> Result<Type> {
+		self.check(condition, &Type::Bool)?;
+		let (when_true, when_false) = null_checks(&condition.node);
+
+		let then_ty = self.narrowed(when_true, |x| x.expression(then, expected))?;
+
+		let Some(otherwise) = otherwise else {
+			return Ok(Type::Unit);
+		};
+
+		if diverges(then) {
+			self.narrowed(when_false, |x| x.expression(otherwise, expected))
+		} else {
+			self.narrowed(when_false, |x| x.check(otherwise, &then_ty))
+		}
+	}
+
+	fn array(&mut self, elements: &mut [Spanned<Expression>], hint: Option<&Type>) -> Result<Type> {
+		let element = match hint {
+			Some(Type::Array { ty, .. }) => ty.as_ref().clone(),
+			_ => Type::Infer,
+		};
+
+		let mut element = element;
+		for expr in elements.iter_mut() {
+			element = self.check(expr, &element)?;
+		}
+
+		if element.contains_infer() {
+			return Error::EmptyArray.into();
+		}
+
+		Ok(element.as_array(elements.len()))
+	}
+
+	fn binary(&mut self, expr: &mut Expression, hint: Option<&Type>) -> Result<Type> {
+		use operator::Binary as B;
+
+		let Expression::Binary { lhs, operator, rhs, ty: operand } = expr else {
+			unreachable!("only called with binary expressions");
+		};
+		let operator = *operator;
+
+		let invalid = |ty: Type| Error::InvalidOperand { operator: format!("{operator:?}"), ty };
+
+		match operator {
+			B::Assignment => {
+				if !matches!(&lhs.node, Expression::ObjectReference(_) | Expression::ArrayAccess { .. } | Expression::Unary {
+					operator: operator::Unary::Dereference, ..
+				}) && !is_field(lhs) {
+					return Error::NotAssignable(self.expression(lhs, None)?).into();
+				}
+
+				if let Expression::ObjectReference(ident) = &lhs.node {
+					self.forget(ident);
+				}
+
+				let ty = self.expression(lhs, None)?;
+				if matches!(ty, Type::Const(_)) {
+					return Error::NotAssignable(ty).into();
+				}
+
+				self.check(rhs, &ty)?;
+				Ok(Type::Unit)
+			}
+			B::Dot => {
+				let ty = self.expression(lhs, None)?;
+				let layout = self.aliases.underlying(&ty)?;
+
+				if let Expression::Literal(Literal::Integer(field)) = &rhs.node {
+					let Type::Tuple(elements) = layout.value_under_cost() else {
+						return Error::NotAStruct(ty).into();
+					};
+
+					let Some(element) = usize::try_from(*field).ok().and_then(|x| elements.get(x)) else {
+						return Error::UnknownField { ty, field: field.to_string() }.into();
+					};
+
+					// fields of a constant tuple are constant themselves
+					return Ok(if matches!(layout, Type::Const(_)) { element.as_const() } else { element.clone() });
+				}
+
+				if let (Type::Dyn(interface), Expression::ObjectReference(method)) = (ty.value_under_cost(), &rhs.node) {
+					return self.dyn_method(&interface, method);
+				}
+
+				// arrays and slices know their length, which is their only field
+				let sized = match layout.value_under_cost() {
+					Type::Pointer(ty) => matches!(self.aliases.underlying(&ty)?.value_under_cost(), Type::Array { .. } | Type::Slice(_)),
+					ty => matches!(ty, Type::Array { .. }),
+				};
+
+				match &rhs.node {
+					Expression::ObjectReference(field) if sized && field.0 == "len" => Ok(Type::USize),
+					_ => Error::NotAStruct(ty).into(),
+				}
+			}
+			// the right side only runs when the left side did not already decide the result
+			B::And | B::Or => {
+				self.check(lhs, &Type::Bool)?;
+
+				let (when_true, when_false) = null_checks(&lhs.node);
+				let checked = if operator == B::And { when_true } else { when_false };
+				self.narrowed(checked, |x| x.check(rhs, &Type::Bool))?;
+
+				Ok(Type::Bool)
+			}
+			B::Equals | B::NotEquals | B::Less | B::LessOrEquals | B::Greater | B::GreaterOrEquals => {
+				let ty = self.expression(lhs, None)?.value_under_cost();
+				if let Some(function) = overload(operator).and_then(|x| self.overloaded(x, &ty)) {
+					return self.overload_binary(expr, function, &ty);
+				}
+
+				self.check(rhs, &ty)?;
+
+				let layout = self.aliases.underlying(&ty)?;
+				let ordered = layout.is_numeric() || layout == Type::Char;
+				if !matches!(operator, B::Equals | B::NotEquals) && !ordered {
+					return invalid(ty).into();
+				}
+
+				*operand = layout.value_under_cost();
+				Ok(Type::Bool)
+			}
+			B::Add | B::Minus | B::Multiply | B::Divide | B::Mod => {
+				let ty = self.expression(lhs, hint)?.value_under_cost();
+				if let Some(function) = overload(operator).and_then(|x| self.overloaded(x, &ty)) {
+					return self.overload_binary(expr, function, &ty);
+				}
+
+				let layout = self.aliases.underlying(&ty)?.value_under_cost();
+				if !layout.is_numeric() {
+					return invalid(ty).into();
+				}
+
+				self.check(rhs, &ty)?;
+				*operand = layout;
+				Ok(ty)
+			}
+			B::ShiftLeft | B::ShiftRight | B::BitAnd | B::BitOr | B::BitXor => {
+				let ty = self.expression(lhs, hint)?.value_under_cost();
+				let layout = self.aliases.underlying(&ty)?;
+				if !(layout.is_integer() || (layout == Type::Bool && !matches!(operator, B::ShiftLeft | B::ShiftRight))) {
+					return invalid(ty).into();
+				}
+
+				self.check(rhs, &ty)?;
+				*operand = layout.value_under_cost();
+				Ok(ty)
+			}
+		}
+	}
+
+	fn unary(&mut self, unary: &mut Expression, hint: Option<&Type>) -> Result<Type> {
+		use operator::Unary as U;
+
+		let Expression::Unary { operator, expr } = unary else {
+			unreachable!("only called with unary expressions");
+		};
+
+		match operator {
+			U::Negate => {
+				let ty = self.expression(expr, hint)?.value_under_cost();
+				if let Some(function) = self.overloaded(("Negate", "negate"), &ty) {
+					let Expression::Unary { expr, .. } = std::mem::replace(unary, Expression::Error) else {
+						unreachable!("the expression was found to be a negation");
+					};
+
+					let (call, returns) = self.operator_call(function, *expr, &ty, vec![])?;
+					*unary = call.node;
+					return Ok(returns);
+				}
+
+				if self.aliases.underlying(&ty)?.is_numeric() { Ok(ty) } else { Error::InvalidOperand { operator: "Negate".into(), ty }.into() }
+			}
+			U::Not => {
+				let ty = self.expression(expr, hint)?.value_under_cost();
+				let layout = self.aliases.underlying(&ty)?;
+				if layout.is_integer() || layout == Type::Bool { Ok(ty) } else { Error::InvalidOperand { operator: "Not".into(), ty }.into() }
+			}
+			U::Reference => {
+				let pointee = match hint {
+					Some(Type::Pointer(pointee)) => Some(pointee.as_ref()),
+					_ => None,
+				};
+				Ok(self.expression(expr, pointee)?.as_pointer())
+			}
+			U::Dereference => match self.expression(expr, hint.map(Type::as_pointer).as_ref())?.value_under_cost() {
+				Type::Pointer(ty) => Ok(*ty),
+				ty @ Type::Nullable(_) => Error::MaybeNull(ty).into(),
+				ty => Error::NotAPointer(ty).into(),
+			},
+		}
+	}
+}
+
+/// Interfaces every program knows of, through which types other than numbers take operators, eg.
+/// `a + b` is `Add::add(a, b)` for a type implementing `Add`
+fn well_known() -> Vec<(Identifier, Vec<(Identifier, FunctionSignature)>)> {
+	let this = Type::custom(SELF);
+	let method = |name: &'static str, args: &[(&'static str, Type)], returns: Type| (name.into(), FunctionSignature::new_named(
+		args.iter().map(|(name, ty)| Variable::new(*name, ty.clone())).collect(),
+		returns,
+	));
+	let binary = |interface: &'static str, name: &'static str, returns: Type| (
+		interface.into(),
+		vec![method(name, &[("self", this.clone()), ("other", this.clone())], returns)],
+	);
+
+	vec![
+		binary("Add", "add", this.clone()),
+		binary("Subtract", "subtract", this.clone()),
+		binary("Multiply", "multiply", this.clone()),
+		binary("Divide", "divide", this.clone()),
+		binary("Remainder", "remainder", this.clone()),
+		binary("Equals", "equals", Type::Bool),
+		("Negate".into(), vec![method("negate", &[("self", this.clone())], this.clone())]),
+		// the element type is up to each implementation, and is assigned to through the pointer
+		("Index".into(), vec![method("index", &[("self", this.as_pointer()), ("index", Type::USize)], Type::Infer.as_pointer())]),
+	]
+}
+
+/// Well-known interface and method a binary operator is overloaded through, see [`well_known`]
+const fn overload(operator: operator::Binary) -> Option<(&'static str, &'static str)> {
+	use operator::Binary as B;
+
+	Some(match operator {
+		B::Add => ("Add", "add"),
+		B::Minus => ("Subtract", "subtract"),
+		B::Multiply => ("Multiply", "multiply"),
+		B::Divide => ("Divide", "divide"),
+		B::Mod => ("Remainder", "remainder"),
+		B::Equals | B::NotEquals => ("Equals", "equals"),
+		_ => return None,
+	})
+}
+
+/// Takes the address of or dereferences a receiver of type 'ty' to fit a 'self' taken by pointer
+/// or by value, along with the type it then has
+fn receive(receiver: Spanned<Expression>, ty: &Type, by_pointer: bool) -> (Spanned<Expression>, Type) {
+	let trace = receiver.trace.clone();
+	let wrap = |operator, expr| Spanned::new(Expression::Unary { operator, expr: Box::new(expr) }, trace);
+
+	match (by_pointer, ty.value_under_cost()) {
+		(true, Type::Pointer(_)) => (receiver, ty.clone()),
+		(true, _) => (wrap(operator::Unary::Reference, receiver), ty.as_pointer()),
+		(false, Type::Pointer(pointee)) => (wrap(operator::Unary::Dereference, receiver), *pointee),
+		(false, _) => (receiver, ty.clone()),
+	}
+}
+
+/// Finds the types the type parameters of a generic function stand for from an argument given for
+/// a parameter of the function, any mismatch is left for the argument's own check to report
+fn infer(parameters: &[Identifier], parameter: &Type, given: &Type, inferred: &mut HashMap<Identifier, Type>) {
+	match (parameter.value_under_cost(), given.value_under_cost()) {
+		(Type::Custom { name, template_args }, given) if template_args.is_empty() && parameters.contains(&name) => {
+			inferred.entry(name).or_insert(given);
+		}
+		(Type::Pointer(parameter), Type::Pointer(given))
+		| (Type::Nullable(parameter), Type::Pointer(given) | Type::Nullable(given))
+		| (Type::Array { ty: parameter, .. }, Type::Array { ty: given, .. })
+		| (Type::Slice(parameter), Type::Array { ty: given, .. } | Type::Slice(given)) =>
+			infer(parameters, &parameter, &given, inferred),
+		(Type::Tuple(parameter), Type::Tuple(given))
+		| (Type::Custom { template_args: parameter, .. }, Type::Custom { template_args: given, .. }) =>
+			for (parameter, given) in parameter.iter().zip(&given) {
+				infer(parameters, parameter, given, inferred);
+			},
+		(Type::Function(parameter), Type::Function(given)) => {
+			for (parameter, given) in parameter.get_args().iter().zip(given.get_args()) {
+				infer(parameters, parameter.get_type(), given.get_type(), inferred);
+			}
+			infer(parameters, parameter.get_return_type(), given.get_return_type(), inferred);
+		}
+		_ => {}
+	}
+}
+
+/// Name a function of an impl block is called with as a method, eg. 'length' for `Point::length`
+fn method_name(ident: &Identifier) -> Identifier {
+	ident.0.rsplit_once("::").map_or(ident.0.as_str(), |(_, name)| name).to_string().into()
+}
+
+/// Whether a method of 'target' can be called on a value of type 'receiver', either directly or
+/// through a pointer
+fn receives(target: &Type, receiver: &Type) -> bool {
+	match receiver.value_under_cost() {
+		Type::Pointer(pointee) => pointee.value_under_cost() == target.value_under_cost(),
+		receiver => receiver == target.value_under_cost(),
+	}
+}
+
+/// Variables whose address is taken anywhere within an expression, including those shared with
+/// the lambdas within it, which can write to them
+fn addressed(expr: &Expression, found: &mut HashSet<Identifier>) {
+	match expr {
+		Expression::Unary { operator: operator::Unary::Reference, expr } => {
+			if let Expression::ObjectReference(ident) = &expr.node {
+				found.insert(ident.clone());
+			}
+			addressed(&expr.node, found);
+		}
+		Expression::Lambda(function) => {
+			found.extend(function.captures.iter().filter(|x| x.mode == CaptureMode::Reference).map(|x| x.ident.clone()));
+			addressed(&function.body.node, found);
+		}
+		Expression::Scope(body) => for statement in body {
+			match &statement.node {
+				Statement::Expression(expr) | Statement::Return(expr) | Statement::Delete(expr) | Statement::Defer(expr)
+				| Statement::Assert(expr) | Statement::Declaration { initialisation: Some(expr), .. } =>
+					addressed(&expr.node, found),
+				Statement::Destructure { initialisation, otherwise, .. } =>
+					for expr in std::iter::once(initialisation).chain(otherwise.as_deref()) {
+						addressed(&expr.node, found);
+					},
+				Statement::Declaration { initialisation: None, .. } | Statement::Error => {}
+			}
+		},
+		expr => for child in defer::children(expr) {
+			addressed(child, found);
+		},
+	}
+}
+
+/// Whether an expression is a field of a tuple, eg. `t.0`, which can be assigned to
+fn is_field(expr: &Expression) -> bool {
+	matches!(expr, Expression::Binary { operator: operator::Binary::Dot, rhs, .. }
+		if matches!(rhs.node, Expression::Literal(Literal::Integer(_))))
+}
+
+/// Variables a condition compares against null, those known not to be null when the condition
+/// holds, and those known not to be null when it does not
+fn null_checks(condition: &Expression) -> (Vec<Identifier>, Vec<Identifier>) {
+	use operator::Binary as B;
+
+	match condition {
+		Expression::Binary { lhs, operator: operator @ (B::Equals | B::NotEquals), rhs, .. } => {
+			let checked = match (&lhs.node, &rhs.node) {
+				(Expression::ObjectReference(ident), Expression::Literal(Literal::Null))
+				| (Expression::Literal(Literal::Null), Expression::ObjectReference(ident)) => vec![ident.clone()],
+				_ => vec![],
+			};
+
+			if *operator == B::NotEquals { (checked, vec![]) } else { (vec![], checked) }
+		}
+		Expression::Binary { lhs, operator: B::And, rhs, .. } => {
+			let (mut when_true, _) = null_checks(&lhs.node);
+			when_true.extend(null_checks(&rhs.node).0);
+			(when_true, vec![])
+		}
+		Expression::Binary { lhs, operator: B::Or, rhs, .. } => {
+			let (_, mut when_false) = null_checks(&lhs.node);
+			when_false.extend(null_checks(&rhs.node).1);
+			(vec![], when_false)
+		}
+		Expression::Unary { operator: operator::Unary::Not, expr } => {
+			let (when_true, when_false) = null_checks(&expr.node);
+			(when_false, when_true)
+		}
+		_ => (vec![], vec![]),
+	}
+}
+
+/// Variables known not to be null after an 'if' finishes, as the branch taken when they are
+/// null never does
+fn checked_after(expr: &Expression) -> Vec<Identifier> {
+	let Expression::If { condition, then, otherwise } = expr else {
+		return vec![];
+	};
+
+	let (when_true, when_false) = null_checks(&condition.node);
+	match otherwise {
+		_ if diverges(then) => when_false,
+		Some(otherwise) if diverges(otherwise) => when_true,
+		_ => vec![],
+	}
+}
+
+/// Type written for the value a pattern takes apart, where the parts that are not written are
+/// left to be inferred, eg. `(_, i32)` for `(x, y: i32)`
+fn written(pattern: &Pattern) -> Type {
+	match pattern {
+		Pattern::Binding(var) => var.get_type().clone(),
+		Pattern::Tuple(elements) => Type::Tuple(elements.iter().map(|x| written(x)).collect()),
+		Pattern::Wildcard | Pattern::Literal(_) | Pattern::Array { .. } => Type::Infer,
+	}
+}
+
+/// Whether an expression never finishes, as it always returns from the function it is in
+fn diverges(expr: &Expression) -> bool {
+	match expr {
+		Expression::Scope(body) => body.iter().any(|statement| match &statement.node {
+			Statement::Return(_) => true,
+			Statement::Expression(expr) => diverges(expr),
+			_ => false,
+		}),
+		Expression::If { then, otherwise: Some(otherwise), .. } => diverges(then) && diverges(otherwise),
+		_ => false,
+	}
+}
+
+/// Whether the layout holds a slice anywhere other than directly behind a pointer, which is the
+/// only place a value without a size can be
+fn is_unsized(layout: &Type) -> bool {
+	match layout {
+		Type::Slice(_) => true,
+		Type::Pointer(ty) | Type::Nullable(ty) => match ty.value_under_cost() {
+			Type::Slice(element) => is_unsized(&element),
+			ty => is_unsized(&ty),
+		},
+		Type::Const(ty) | Type::Array { ty, .. } | Type::ConstArray { ty, .. } => is_unsized(ty),
+		Type::Tuple(elements) => elements.iter().any(is_unsized),
+		Type::Function(signature) =>
+			signature.get_args().iter().any(|x| is_unsized(x.get_type())) || is_unsized(signature.get_return_type()),
+		_ => false,
+	}
+}
@@ -0,0 +1,525 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::analysis::{Error, Result};
+use crate::parser::ast::{Expression, operator, Spanned, Statement};
+use crate::parser::ast::function::Function;
+use crate::parser::ast::variable::{Identifier, Type};
+use crate::parser::token::{Literal, Located};
+
+/// Most steps evaluating a constant can take, so that evaluating one always finishes
+const STEP_LIMIT: usize = 1_000_000;
+/// Most calls of const functions that can be in progress at once
+const RECURSION_LIMIT: usize = 128;
+
+/// Values of the constants and the const functions a constant expression can refer to
+pub trait Constants {
+	/// Value of a constant, or `None` when the name is not that of a constant
+	fn value(&mut self, ident: &Identifier) -> Result<Option<Literal>>;
+
+	/// Const function of the name, if there is one
+	fn function(&self, ident: &Identifier) -> Option<Rc<Function>>;
+}
+
+/// Constants of a program, evaluated the first time they are used so they can refer to each other
+/// in any order, along with the const functions they can call
+#[derive(Debug, Default)]
+pub struct Definitions {
+	expressions: HashMap<Identifier, (Type, Spanned<Expression>)>,
+	values: HashMap<Identifier, Literal>,
+	functions: HashMap<Identifier, Rc<Function>>,
+	/// Constants currently being evaluated, to catch those that refer to themselves
+	evaluating: Vec<Identifier>,
+}
+
+impl Definitions {
+	pub fn insert(&mut self, ident: Identifier, ty: Type, value: Spanned<Expression>) {
+		self.expressions.insert(ident, (ty, value));
+	}
+
+	pub fn insert_function(&mut self, ident: Identifier, function: Function) {
+		self.functions.insert(ident, Rc::new(function));
+	}
+
+	/// Value of a constant that has been evaluated
+	pub fn get(&self, ident: &Identifier) -> Option<&Literal> {
+		self.values.get(ident)
+	}
+
+	/// Checks that a const function could be run while compiling, so only uses its arguments,
+	/// constants and other const functions. Anything touching memory, such as pointers and
+	/// globals, or the outside world, which is only reachable through other functions, is refused
+	pub fn check_function(&self, function: &Function) -> Result {
+		let scope = function.signature.get_args().iter().map(|x| x.get_name().clone()).collect();
+		Purity { definitions: self, scopes: vec![scope] }.expression(&function.body)
+	}
+}
+
+impl Constants for Definitions {
+	fn value(&mut self, ident: &Identifier) -> Result<Option<Literal>> {
+		if let Some(value) = self.values.get(ident) {
+			return Ok(Some(value.clone()));
+		}
+
+		let Some((ty, expr)) = self.expressions.get(ident).cloned() else {
+			return Ok(None);
+		};
+
+		if self.evaluating.contains(ident) {
+			return Error::RecursiveConstant(ident.clone()).into();
+		}
+
+		self.evaluating.push(ident.clone());
+		let value = evaluate(&expr, &ty, self);
+		self.evaluating.pop();
+
+		let value = value?;
+		self.values.insert(ident.clone(), value.clone());
+		Ok(Some(value))
+	}
+
+	fn function(&self, ident: &Identifier) -> Option<Rc<Function>> {
+		self.functions.get(ident).cloned()
+	}
+}
+
+/// Constants once they have all been evaluated
+impl Constants for &Definitions {
+	fn value(&mut self, ident: &Identifier) -> Result<Option<Literal>> {
+		Ok(self.values.get(ident).cloned())
+	}
+
+	fn function(&self, ident: &Identifier) -> Option<Rc<Function>> {
+		self.functions.get(ident).cloned()
+	}
+}
+
+/// Evaluates an expression of type 'ty' while compiling, which can only use literals, operators,
+/// constants and calls of const functions. Integers are checked to fit their type after every
+/// operation
+pub fn evaluate(expr: &Spanned<Expression>, ty: &Type, constants: &mut impl Constants) -> Result<Literal> {
+	let mut evaluator = Evaluator {
+		constants,
+		calls: vec![Call { scopes: vec![], returns: ty.clone() }],
+		steps: 0,
+	};
+
+	match evaluator.evaluate(expr, ty) {
+		Ok(value) => Ok(value),
+		Err(Exit::Error(error)) => Err(error),
+		Err(Exit::Return(_)) => Error::NotConstant.at(&expr.trace).into(),
+	}
+}
+
+/// Evaluates the length of an array type, see [`evaluate`]
+pub fn length(expr: &Spanned<Expression>, constants: &mut impl Constants) -> Result<usize> {
+	match evaluate(expr, &Type::USize, constants)? {
+		Literal::Integer(length) => usize::try_from(length).map_err(|_| Error::Overflow(Type::USize).at(&expr.trace)),
+		literal => Error::TypeMismatch { expected: Type::USize, given: type_of(&literal) }.at(&expr.trace).into(),
+	}
+}
+
+/// Way the evaluation of an expression stopped early
+enum Exit {
+	/// Returned from the const function being called
+	Return(Literal),
+	Error(Error),
+}
+
+impl From<Error> for Exit {
+	fn from(value: Error) -> Self {
+		Self::Error(value)
+	}
+}
+
+/// Const function being called, the outermost call being the constant expression itself
+struct Call {
+	/// Locals along with their types, innermost scope last
+	scopes: Vec<HashMap<Identifier, (Type, Literal)>>,
+	returns: Type,
+}
+
+/// Runs constant expressions without any memory other than their locals, so that they cannot
+/// affect or depend on anything but other constants
+struct Evaluator<'a, C> {
+	constants: &'a mut C,
+	calls: Vec<Call>,
+	steps: usize,
+}
+
+impl<C: Constants> Evaluator<'_, C> {
+	fn evaluate(&mut self, expr: &Spanned<Expression>, ty: &Type) -> std::result::Result<Literal, Exit> {
+		self.steps += 1;
+		if self.steps > STEP_LIMIT {
+			return Err(Error::StepLimit(STEP_LIMIT).at(&expr.trace).into());
+		}
+
+		self.node(&expr.node, &ty.value_under_cost()).map_err(|x| match x {
+			Exit::Error(error) => Exit::Error(error.at(&expr.trace)),
+			Exit::Return(value) => Exit::Return(value),
+		})
+	}
+
+	fn node(&mut self, expr: &Expression, ty: &Type) -> std::result::Result<Literal, Exit> {
+		match expr {
+			Expression::Literal(Literal::Integer(x)) => Ok(fits(i128::from(*x), ty)?),
+			Expression::Literal(literal @ (Literal::Float(_) | Literal::Bool(_) | Literal::Character(_) | Literal::Unit)) =>
+				Ok(literal.clone()),
+			Expression::ObjectReference(ident) => match self.local(ident) {
+				Some((_, value)) => Ok(value.clone()),
+				None => Ok(self.constants.value(ident)?.ok_or(Error::NotConstant)?),
+			},
+			Expression::FunctionCall { function, arguments } => self.call(function, arguments),
+			Expression::Scope(body) => self.scope(body),
+			Expression::If { condition, then, otherwise } => match self.evaluate(condition, &Type::Bool)? {
+				Literal::Bool(true) => self.evaluate(then, ty),
+				Literal::Bool(false) => otherwise.as_ref().map_or(Ok(Literal::Unit), |x| self.evaluate(x, ty)),
+				literal => Err(Error::TypeMismatch { expected: Type::Bool, given: type_of(&literal) }.into()),
+			},
+			Expression::Unary { operator, expr } => self.unary(*operator, expr, ty),
+			Expression::Binary { lhs, operator, rhs, .. } => self.binary(lhs, *operator, rhs, ty),
+			_ => Err(Error::NotConstant.into()),
+		}
+	}
+
+	fn unary(&mut self, operator: operator::Unary, expr: &Spanned<Expression>, ty: &Type) -> std::result::Result<Literal, Exit> {
+		use operator::Unary as U;
+
+		Ok(match (operator, self.evaluate(expr, ty)?) {
+			(U::Negate, Literal::Integer(x)) => fits(-i128::from(x), ty)?,
+			(U::Negate, Literal::Float(x)) => Literal::Float(-x),
+			(U::Not, Literal::Bool(x)) => Literal::Bool(!x),
+			// every bit of an unsigned integer is part of its value
+			(U::Not, Literal::Integer(x)) => match range(ty) {
+				(0, max) => fits(max ^ i128::from(x), ty)?,
+				_ => Literal::Integer(!x),
+			},
+			(U::Negate | U::Not, literal) => invalid(operator, &literal)?,
+			(U::Reference | U::Dereference, _) => return Err(Error::NotConstant.into()),
+		})
+	}
+
+	fn binary(
+		&mut self,
+		lhs: &Spanned<Expression>,
+		operator: operator::Binary,
+		rhs: &Spanned<Expression>,
+		ty: &Type,
+	) -> std::result::Result<Literal, Exit> {
+		use operator::Binary as B;
+
+		match operator {
+			B::Assignment => self.assign(lhs, rhs),
+			B::Dot => Err(Error::NotConstant.into()),
+			// the right side only runs when the left side did not already decide the result
+			B::And | B::Or => match self.evaluate(lhs, &Type::Bool)? {
+				Literal::Bool(x) if x == (operator == B::Or) => Ok(Literal::Bool(x)),
+				Literal::Bool(_) => self.evaluate(rhs, &Type::Bool),
+				literal => Ok(invalid(operator, &literal)?),
+			},
+			// operands of a comparison have a type of their own, which is only known to be some integer
+			B::Equals | B::NotEquals | B::Less | B::LessOrEquals | B::Greater | B::GreaterOrEquals => {
+				let lhs = self.evaluate(lhs, &Type::I64)?;
+				Ok(compare(operator, &lhs, &self.evaluate(rhs, &Type::I64)?)?)
+			}
+			_ => {
+				let lhs = self.evaluate(lhs, ty)?;
+				let rhs = self.evaluate(rhs, ty)?;
+				Ok(arithmetic(operator, &lhs, &rhs, ty)?)
+			}
+		}
+	}
+
+	fn local(&mut self, ident: &Identifier) -> Option<&mut (Type, Literal)> {
+		self.calls.last_mut()?.scopes.iter_mut().rev().find_map(|x| x.get_mut(ident))
+	}
+
+	fn declare(&mut self, ident: Identifier, ty: Type, value: Literal) {
+		if let Some(scope) = self.calls.last_mut().and_then(|x| x.scopes.last_mut()) {
+			scope.insert(ident, (ty, value));
+		}
+	}
+
+	fn assign(&mut self, lhs: &Spanned<Expression>, rhs: &Spanned<Expression>) -> std::result::Result<Literal, Exit> {
+		let Expression::ObjectReference(ident) = &lhs.node else {
+			return Err(Error::NotConstant.at(&lhs.trace).into());
+		};
+
+		let ty = self.local(ident).map(|(ty, _)| ty.clone()).ok_or_else(|| Error::NotConstant.at(&lhs.trace))?;
+		let value = self.evaluate(rhs, &ty)?;
+		if let Some((_, local)) = self.local(ident) {
+			*local = value;
+		}
+
+		Ok(Literal::Unit)
+	}
+
+	fn scope(&mut self, body: &[Spanned<Statement>]) -> std::result::Result<Literal, Exit> {
+		if let Some(call) = self.calls.last_mut() {
+			call.scopes.push(HashMap::new());
+		}
+
+		let value = body.iter().try_fold(Literal::Unit, |_, statement| self.statement(statement));
+
+		if let Some(call) = self.calls.last_mut() {
+			call.scopes.pop();
+		}
+		value
+	}
+
+	fn statement(&mut self, statement: &Spanned<Statement>) -> std::result::Result<Literal, Exit> {
+		match &statement.node {
+			// the type of an inferred local is not known yet, so its integers are only checked to be some integer
+			Statement::Expression(expr) => self.evaluate(expr, &Type::I64),
+			Statement::Declaration { var, initialisation: Some(initialisation) } => {
+				let ty = match var.get_type().value_under_cost() {
+					Type::Infer => Type::I64,
+					ty => ty,
+				};
+
+				let value = self.evaluate(initialisation, &ty)?;
+				self.declare(var.get_name().clone(), ty, value);
+				Ok(Literal::Unit)
+			}
+			// a failed assertion stops compiling, as the constant cannot have the value it would have had
+			Statement::Assert(condition) => match self.evaluate(condition, &Type::Bool)? {
+				Literal::Bool(true) => Ok(Literal::Unit),
+				Literal::Bool(false) => Err(Error::AssertionFailed.at(&statement.trace).into()),
+				literal => Err(Error::TypeMismatch { expected: Type::Bool, given: type_of(&literal) }.at(&condition.trace).into()),
+			},
+			Statement::Return(expr) => {
+				let returns = self.calls.last().map_or(Type::Unit, |x| x.returns.clone());
+				Err(Exit::Return(self.evaluate(expr, &returns)?))
+			}
+			_ => Err(Error::NotConstant.at(&statement.trace).into()),
+		}
+	}
+
+	fn call(&mut self, function: &Spanned<Expression>, arguments: &[Spanned<Expression>]) -> std::result::Result<Literal, Exit> {
+		let called = match &function.node {
+			Expression::ObjectReference(ident) => self.constants.function(ident),
+			_ => None,
+		};
+
+		let Some(called) = called else {
+			return Err(Error::NotConstant.at(&function.trace).into());
+		};
+
+		if self.calls.len() > RECURSION_LIMIT {
+			return Err(Error::RecursionLimit(RECURSION_LIMIT).at(&function.trace).into());
+		}
+
+		let args = called.signature.get_args();
+		if args.len() != arguments.len() {
+			return Err(Error::ArgumentCount { expected: args.len(), given: arguments.len() }.at(&function.trace).into());
+		}
+
+		let mut locals = HashMap::new();
+		for (arg, argument) in args.iter().zip(arguments) {
+			let ty = arg.get_type().value_under_cost();
+			locals.insert(arg.get_name().clone(), (ty.clone(), self.evaluate(argument, &ty)?));
+		}
+
+		let returns = called.signature.get_return_type().clone();
+		self.calls.push(Call { scopes: vec![locals], returns: returns.clone() });
+		let value = self.evaluate(&called.body, &returns);
+		self.calls.pop();
+
+		match value {
+			Ok(value) | Err(Exit::Return(value)) => Ok(value),
+			Err(error) => Err(error),
+		}
+	}
+}
+
+/// Walks the body of a const function looking for anything that cannot run while compiling, see
+/// [`Definitions::check_function`]
+struct Purity<'a> {
+	definitions: &'a Definitions,
+	scopes: Vec<Vec<Identifier>>,
+}
+
+impl Purity<'_> {
+	fn expression(&mut self, expr: &Spanned<Expression>) -> Result {
+		use operator::Unary as U;
+
+		// only what the evaluator knows how to run is let through, so a const function that could
+		// not be evaluated is refused where it is declared rather than where it is used
+		let refused = match &expr.node {
+			Expression::Unary { operator: U::Reference | U::Dereference, .. } | Expression::Dyn { .. } | Expression::DynMethod { .. }
+			| Expression::Literal(Literal::Null) => "use pointers",
+			Expression::New { .. } | Expression::NewArray { .. } => "allocate memory",
+			Expression::Lambda(_) => "create lambdas",
+			Expression::Literal(Literal::String(_)) => "use strings",
+			Expression::Array(_) | Expression::Tuple(_) => "create arrays or tuples",
+			Expression::ArrayAccess { .. } => "index arrays",
+			Expression::Cast { .. } => "cast values",
+			Expression::Binary { operator: operator::Binary::Dot, .. } => "access fields",
+			Expression::ObjectReference(ident) if !self.known(ident) => "use globals that are not constant",
+			Expression::FunctionCall { function, arguments } => {
+				match &function.node {
+					Expression::ObjectReference(ident) if self.definitions.functions.contains_key(ident) => {}
+					_ => return Error::NotConstFunction("call functions that are not const").at(&function.trace).into(),
+				}
+
+				return arguments.iter().try_for_each(|x| self.expression(x));
+			}
+			Expression::Scope(body) => {
+				self.scopes.push(vec![]);
+				let result = body.iter().try_for_each(|x| self.statement(x));
+				self.scopes.pop();
+				return result;
+			}
+			expr => return children(expr).into_iter().try_for_each(|x| self.expression(x)),
+		};
+
+		Error::NotConstFunction(refused).at(&expr.trace).into()
+	}
+
+	fn statement(&mut self, statement: &Spanned<Statement>) -> Result {
+		match &statement.node {
+			Statement::Expression(expr) | Statement::Return(expr) | Statement::Assert(expr) => self.expression(expr),
+			Statement::Declaration { var, initialisation: Some(initialisation) } => {
+				self.expression(initialisation)?;
+				self.declare(var.get_name().clone());
+				Ok(())
+			}
+			Statement::Declaration { .. } => Error::NotConstFunction("declare variables without a value").at(&statement.trace).into(),
+			Statement::Destructure { .. } => Error::NotConstFunction("destructure values").at(&statement.trace).into(),
+			Statement::Defer(_) => Error::NotConstFunction("defer statements").at(&statement.trace).into(),
+			Statement::Delete(_) => Error::NotConstFunction("free memory").at(&statement.trace).into(),
+			Statement::Error => Ok(()),
+		}
+	}
+
+	fn declare(&mut self, ident: Identifier) {
+		if let Some(scope) = self.scopes.last_mut() {
+			scope.push(ident);
+		}
+	}
+
+	/// Whether a name is that of a local, a constant or a const function
+	fn known(&self, ident: &Identifier) -> bool {
+		self.scopes.iter().any(|x| x.contains(ident))
+			|| self.definitions.expressions.contains_key(ident)
+			|| self.definitions.functions.contains_key(ident)
+	}
+}
+
+fn children(expr: &Expression) -> Vec<&Spanned<Expression>> {
+	match expr {
+		Expression::Binary { lhs, rhs, .. } | Expression::ArrayAccess { expr: lhs, index: rhs } => vec![lhs, rhs],
+		Expression::Unary { expr, .. } | Expression::Cast { from: expr, .. } | Expression::NewArray { length: expr, .. }
+		| Expression::Dyn { data: expr, .. } | Expression::DynMethod { object: expr, .. } => vec![expr],
+		Expression::FunctionCall { function, arguments } => std::iter::once(function.as_ref()).chain(arguments).collect(),
+		Expression::Array(elements) | Expression::Tuple(elements) | Expression::New { arguments: elements, .. } =>
+			elements.iter().collect(),
+		Expression::If { condition, then, otherwise } => [condition, then].into_iter().chain(otherwise).map(AsRef::as_ref).collect(),
+		Expression::Scope(_) | Expression::Lambda(_) | Expression::ObjectReference(_) | Expression::Literal(_) | Expression::Error =>
+			vec![],
+	}
+}
+
+fn arithmetic(operator: operator::Binary, lhs: &Literal, rhs: &Literal, ty: &Type) -> Result<Literal> {
+	use operator::Binary as B;
+
+	match (lhs, rhs) {
+		(Literal::Integer(lhs), Literal::Integer(rhs)) => {
+			let (lhs, rhs) = (i128::from(*lhs), i128::from(*rhs));
+			let bits = match ty {
+				Type::I8 | Type::U8 => 8,
+				Type::I32 | Type::U32 => 32,
+				_ => 64,
+			};
+
+			fits(match operator {
+				B::Add => lhs + rhs,
+				B::Minus => lhs - rhs,
+				B::Multiply => lhs * rhs,
+				B::Divide | B::Mod if rhs == 0 => return Error::DivisionByZero.into(),
+				B::Divide => lhs / rhs,
+				B::Mod => lhs % rhs,
+				B::ShiftLeft | B::ShiftRight if !(0..bits).contains(&rhs) => return Error::Overflow(ty.clone()).into(),
+				B::ShiftLeft => lhs << rhs,
+				B::ShiftRight => lhs >> rhs,
+				B::BitAnd => lhs & rhs,
+				B::BitOr => lhs | rhs,
+				B::BitXor => lhs ^ rhs,
+				operator => return invalid(operator, &Literal::Integer(0)),
+			}, ty)
+		}
+		(Literal::Float(lhs), Literal::Float(rhs)) => Ok(Literal::Float(match operator {
+			B::Add => lhs + rhs,
+			B::Minus => lhs - rhs,
+			B::Multiply => lhs * rhs,
+			B::Divide => lhs / rhs,
+			B::Mod => lhs % rhs,
+			operator => return invalid(operator, &Literal::Float(0.0)),
+		})),
+		(Literal::Bool(lhs), Literal::Bool(rhs)) => Ok(Literal::Bool(match operator {
+			B::BitAnd => lhs & rhs,
+			B::BitOr => lhs | rhs,
+			B::BitXor => lhs ^ rhs,
+			operator => return invalid(operator, &Literal::Bool(false)),
+		})),
+		(lhs, _) => invalid(operator, lhs),
+	}
+}
+
+fn compare(operator: operator::Binary, lhs: &Literal, rhs: &Literal) -> Result<Literal> {
+	use operator::Binary as B;
+
+	let ordering = match (lhs, rhs) {
+		(Literal::Integer(lhs), Literal::Integer(rhs)) => lhs.partial_cmp(rhs),
+		(Literal::Float(lhs), Literal::Float(rhs)) => lhs.partial_cmp(rhs),
+		(Literal::Character(lhs), Literal::Character(rhs)) => lhs.partial_cmp(rhs),
+		(Literal::Bool(lhs), Literal::Bool(rhs)) if matches!(operator, B::Equals | B::NotEquals) => lhs.partial_cmp(rhs),
+		(lhs, _) => return invalid(operator, lhs),
+	};
+
+	// only NaN is unordered, and it is equal to nothing
+	Ok(Literal::Bool(ordering.map_or(operator == B::NotEquals, |x| match operator {
+		B::Equals => x.is_eq(),
+		B::NotEquals => x.is_ne(),
+		B::Less => x.is_lt(),
+		B::LessOrEquals => x.is_le(),
+		B::Greater => x.is_gt(),
+		_ => x.is_ge(),
+	})))
+}
+
+/// Smallest and largest values of an integer type, other types are given those of an i64
+fn range(ty: &Type) -> (i128, i128) {
+	match ty {
+		Type::I8 => (i8::MIN.into(), i8::MAX.into()),
+		Type::I32 => (i32::MIN.into(), i32::MAX.into()),
+		Type::U8 => (0, u8::MAX.into()),
+		Type::U32 => (0, u32::MAX.into()),
+		Type::U64 | Type::USize => (0, u64::MAX.into()),
+		_ => (i64::MIN.into(), i64::MAX.into()),
+	}
+}
+
+/// Integer literal of the value, if the value fits in the type
+fn fits(value: i128, ty: &Type) -> Result<Literal> {
+	let (min, max) = range(ty);
+	if !(min..=max).contains(&value) {
+		return Error::Overflow(ty.clone()).into();
+	}
+
+	// integers are kept as i64, so the largest unsigned ones do not fit either
+	i64::try_from(value).map(Literal::Integer).map_err(|_| Error::Overflow(ty.clone()))
+}
+
+const fn type_of(literal: &Literal) -> Type {
+	match literal {
+		Literal::Integer(_) => Type::I64,
+		Literal::Float(_) => Type::F64,
+		Literal::Bool(_) => Type::Bool,
+		Literal::Character(_) => Type::Char,
+		Literal::String(_) | Literal::Unit | Literal::Null => Type::Unit,
+	}
+}
+
+fn invalid<T>(operator: impl std::fmt::Debug, operand: &Literal) -> Result<T> {
+	Error::InvalidOperand { operator: format!("{operator:?}"), ty: type_of(operand) }.into()
+}
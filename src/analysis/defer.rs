@@ -0,0 +1,141 @@
+use crate::parser::ast::{Expression, Spanned, Statement};
+use crate::parser::ast::variable::{Type, Variable};
+
+/// Name of the variables holding the value of a scope or return while deferred code runs, which
+/// cannot clash with a local as locals cannot contain `::`
+const VALUE: &str = "defer::value";
+
+/// Removes the defer statements of a type checked scope, copying the deferred code to every
+/// point the scope is left at. The rest of the scope after a defer is moved into a scope of its
+/// own, whose value is kept while the deferred code runs:
+///
+/// `{ a; defer d; b }` becomes `{ a; let value = { b }; d; value }`
+///
+/// and every return within that rest runs the deferred code after evaluating what it returns.
+/// 'value' is the type of the scope, and 'returns' the return type of the function it is in
+pub fn lower(body: &mut Vec<Spanned<Statement>>, value: &Type, returns: &Type) {
+	let Some(position) = body.iter().position(|x| matches!(x.node, Statement::Defer(_))) else {
+		return;
+	};
+
+	let mut rest = body.split_off(position + 1);
+	let Some(Statement::Defer(deferred)) = body.pop().map(|x| x.node) else {
+		unreachable!("the statement was found to be a defer");
+	};
+
+	// code deferred later in the scope runs first, so is placed within this
+	lower(&mut rest, value, returns);
+	for statement in &mut rest {
+		before_returns(statement, &deferred, returns);
+	}
+
+	let trace = deferred.trace.clone();
+	body.push(Spanned::new(Statement::Declaration {
+		var: Variable::new(VALUE, value.as_const()),
+		initialisation: Some(Spanned::new(Expression::Scope(rest), trace.clone())),
+	}, trace.clone()));
+	body.push(Spanned::new(Statement::Expression(deferred), trace.clone()));
+	body.push(Spanned::new(Expression::ObjectReference(VALUE.into()).into(), trace));
+}
+
+/// Whether an expression returns from the function it is in, which deferred code may not
+pub fn returns(expr: &Expression) -> bool {
+	let mut found = false;
+	visit(expr, &mut |statement| found |= matches!(statement, Statement::Return(_)));
+	found
+}
+
+/// Runs the deferred code before every return within a statement
+fn before_returns(statement: &mut Spanned<Statement>, deferred: &Spanned<Expression>, returns: &Type) {
+	if let Statement::Return(expr) = &mut statement.node {
+		let trace = statement.trace.clone();
+		let expr = std::mem::replace(expr, Spanned::new(Expression::Error, trace.clone()));
+
+		statement.node = Expression::Scope(vec![
+			Spanned::new(Statement::Declaration {
+				var: Variable::new(VALUE, returns.as_const()),
+				initialisation: Some(expr),
+			}, trace.clone()),
+			Spanned::new(Statement::Expression(deferred.clone()), trace.clone()),
+			Spanned::new(Statement::Return(Spanned::new(Expression::ObjectReference(VALUE.into()), trace.clone())), trace),
+		]).into();
+		return;
+	}
+
+	match &mut statement.node {
+		Statement::Expression(expr) | Statement::Delete(expr) | Statement::Defer(expr) | Statement::Assert(expr)
+		| Statement::Destructure { initialisation: expr, otherwise: None, .. }
+		| Statement::Declaration { initialisation: Some(expr), .. } =>
+			visit_mut(expr, &mut |x| before_returns(x, deferred, returns)),
+		Statement::Destructure { initialisation, otherwise: Some(otherwise), .. } => {
+			visit_mut(initialisation, &mut |x| before_returns(x, deferred, returns));
+			visit_mut(otherwise, &mut |x| before_returns(x, deferred, returns));
+		}
+		Statement::Declaration { initialisation: None, .. } | Statement::Return(_) | Statement::Error => {}
+	}
+}
+
+/// Calls a function on every statement directly within the scopes of an expression, leaving out
+/// lambdas as their returns are their own
+fn visit(expr: &Expression, f: &mut impl FnMut(&Statement)) {
+	match expr {
+		Expression::Scope(body) => for statement in body {
+			f(&statement.node);
+			match &statement.node {
+				Statement::Expression(expr) | Statement::Return(expr) | Statement::Delete(expr) | Statement::Defer(expr)
+				| Statement::Assert(expr) | Statement::Declaration { initialisation: Some(expr), .. } =>
+					visit(expr, f),
+				Statement::Destructure { initialisation, otherwise, .. } =>
+					for expr in std::iter::once(initialisation).chain(otherwise.as_deref()) {
+						visit(expr, f);
+					},
+				Statement::Declaration { initialisation: None, .. } | Statement::Error => {}
+			}
+		},
+		expr => for child in children(expr) {
+			visit(child, f);
+		},
+	}
+}
+
+fn visit_mut(expr: &mut Expression, f: &mut impl FnMut(&mut Spanned<Statement>)) {
+	match expr {
+		Expression::Scope(body) => body.iter_mut().for_each(f),
+		expr => for child in children_mut(expr) {
+			visit_mut(child, f);
+		},
+	}
+}
+
+/// Expressions directly within an expression, leaving out the statements of scopes and the bodies
+/// of lambdas
+pub fn children(expr: &Expression) -> Vec<&Expression> {
+	match expr {
+		Expression::Binary { lhs, rhs, .. } | Expression::ArrayAccess { expr: lhs, index: rhs } => vec![&lhs.node, &rhs.node],
+		Expression::Unary { expr, .. } | Expression::Cast { from: expr, .. } | Expression::NewArray { length: expr, .. }
+		| Expression::Dyn { data: expr, .. } | Expression::DynMethod { object: expr, .. } => vec![&expr.node],
+		Expression::FunctionCall { function, arguments } => std::iter::once(function.as_ref()).chain(arguments).map(|x| &x.node).collect(),
+		Expression::Array(elements) | Expression::Tuple(elements) | Expression::New { arguments: elements, .. } =>
+			elements.iter().map(|x| &x.node).collect(),
+		Expression::If { condition, then, otherwise } =>
+			[condition, then].into_iter().chain(otherwise).map(|x| &x.node).collect(),
+		Expression::Scope(_) | Expression::Lambda(_) | Expression::ObjectReference(_) | Expression::Literal(_) | Expression::Error =>
+			vec![],
+	}
+}
+
+fn children_mut(expr: &mut Expression) -> Vec<&mut Expression> {
+	match expr {
+		Expression::Binary { lhs, rhs, .. } | Expression::ArrayAccess { expr: lhs, index: rhs } => vec![&mut lhs.node, &mut rhs.node],
+		Expression::Unary { expr, .. } | Expression::Cast { from: expr, .. } | Expression::NewArray { length: expr, .. }
+		| Expression::Dyn { data: expr, .. } | Expression::DynMethod { object: expr, .. } => vec![&mut expr.node],
+		Expression::FunctionCall { function, arguments } =>
+			std::iter::once(function.as_mut()).chain(arguments).map(|x| &mut x.node).collect(),
+		Expression::Array(elements) | Expression::Tuple(elements) | Expression::New { arguments: elements, .. } =>
+			elements.iter_mut().map(|x| &mut x.node).collect(),
+		Expression::If { condition, then, otherwise } =>
+			[condition, then].into_iter().chain(otherwise).map(|x| &mut x.node).collect(),
+		Expression::Scope(_) | Expression::Lambda(_) | Expression::ObjectReference(_) | Expression::Literal(_) | Expression::Error =>
+			vec![],
+	}
+}
@@ -0,0 +1,228 @@
+use crate::parser::ast::variable::{Identifier, Type};
+use crate::parser::token::{Located, Trace};
+
+pub mod capture;
+pub mod checker;
+pub mod attribute;
+pub mod alias;
+pub mod defer;
+pub mod constant;
+
+#[cfg(test)]
+mod test;
+
+pub type Result<T = ()> = std::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	#[error("Could not find variable '{0}'")]
+	UnknownVariable(Identifier),
+
+	#[error("Expected type {expected}, given {given}")]
+	TypeMismatch {
+		expected: Type,
+		given: Type,
+	},
+
+	#[error("Expected type {alias} (aka {expected}), given {given}")]
+	AliasMismatch {
+		alias: Box<Type>,
+		expected: Box<Type>,
+		given: Type,
+	},
+
+	#[error("Type alias '{alias}' takes {expected} template argument(s), given {given}")]
+	AliasArguments {
+		alias: Identifier,
+		expected: usize,
+		given: usize,
+	},
+
+	#[error("Type alias '{0}' refers to itself")]
+	RecursiveAlias(Identifier),
+
+	#[error("Cannot infer the type of '{0}', consider adding a type annotation")]
+	CannotInfer(Identifier),
+
+	#[error("Cannot infer the element type of an empty array")]
+	EmptyArray,
+
+	#[error("Type {0} cannot be called")]
+	NotCallable(Type),
+
+	#[error("Expected {expected} arguments, given {given}")]
+	ArgumentCount {
+		expected: usize,
+		given: usize,
+	},
+
+	#[error("Type {0} cannot be indexed")]
+	NotIndexable(Type),
+
+	#[error("Type {0} is not a pointer")]
+	NotAPointer(Type),
+
+	#[error("Cannot return from deferred code")]
+	ReturnInDefer,
+
+	#[error("Pointer of type {0} may be null, compare it against null before using it")]
+	MaybeNull(Type),
+
+	#[error("Type {0} has no fields")]
+	NotAStruct(Type),
+
+	#[error("Type {ty} has no method '{method}'")]
+	UnknownMethod {
+		ty: Type,
+		method: Identifier,
+	},
+
+	#[error("'{0}' is defined more than once")]
+	Redefinition(Identifier),
+
+	#[error("Method '{method}' of {ty} is defined more than once")]
+	DuplicateMethod {
+		ty: Type,
+		method: Identifier,
+	},
+
+	#[error("Method '{method}' of {ty} is ambiguous, as more than one impl block defines it for the type")]
+	AmbiguousMethod {
+		ty: Type,
+		method: Identifier,
+	},
+
+	#[error("The 'self' of a method of {ty} must be {ty} or a pointer to it, given {given}")]
+	InvalidSelf {
+		ty: Type,
+		given: Type,
+	},
+
+	#[error("Could not find interface '{0}'")]
+	UnknownInterface(Identifier),
+
+	#[error("Missing method '{method}' of interface '{interface}'")]
+	MissingMethod {
+		interface: Identifier,
+		method: Identifier,
+	},
+
+	#[error("Method '{method}' is not part of interface '{interface}'")]
+	NotInInterface {
+		interface: Identifier,
+		method: Identifier,
+	},
+
+	#[error("Method '{method}' must have type {expected} to implement its interface, given {given}")]
+	SignatureMismatch {
+		method: Identifier,
+		expected: Type,
+		given: Type,
+	},
+
+	#[error("Type {ty} does not implement interface '{interface}'")]
+	Unimplemented {
+		ty: Type,
+		interface: Identifier,
+	},
+
+	#[error("Method '{method}' of interface '{interface}' uses 'Self' beyond its 'self', so cannot be called on a 'dyn {interface}'")]
+	NotObjectSafe {
+		interface: Identifier,
+		method: Identifier,
+	},
+
+	#[error("Generic function '{0}' can only be called")]
+	GenericValue(Identifier),
+
+	#[error("Type {ty} has no field '{field}'")]
+	UnknownField {
+		ty: Type,
+		field: String,
+	},
+
+	#[error("Cannot take a value of type {given} apart into {expected} variables")]
+	Destructure {
+		expected: usize,
+		given: Type,
+	},
+
+	#[error("Value of type {0} cannot be taken apart by the pattern")]
+	PatternMismatch(Type),
+
+	#[error("Pattern might not match, so needs an 'else' for when it does not")]
+	RefutablePattern,
+
+	#[error("The 'else' of a pattern must never finish, such as by returning")]
+	FallthroughElse,
+
+	#[error("Operator {operator} cannot be used with type {ty}")]
+	InvalidOperand {
+		operator: String,
+		ty: Type,
+	},
+
+	#[error("Cannot assign to a value of type {0}")]
+	NotAssignable(Type),
+
+	#[error("Type {0} has no known size, so can only be used behind a pointer")]
+	Unsized(Type),
+
+	#[error("Cannot allocate a value of type {0} with 'new'")]
+	NotConstructible(Type),
+
+	#[error("Expression is not constant, only literals, operators, constants and calls of const functions can be evaluated while compiling")]
+	NotConstant,
+
+	#[error("Constant expression overflows type {0}")]
+	Overflow(Type),
+
+	#[error("Division by zero in a constant expression")]
+	DivisionByZero,
+
+	#[error("Constant '{0}' refers to itself")]
+	RecursiveConstant(Identifier),
+
+	#[error("Constant evaluation did not finish within {0} steps")]
+	StepLimit(usize),
+
+	#[error("Constant evaluation went more than {0} const function calls deep")]
+	RecursionLimit(usize),
+
+	#[error("Assertion failed while evaluating a constant")]
+	AssertionFailed,
+
+	#[error("Test '{0}' cannot take arguments or type parameters")]
+	InvalidTest(Identifier),
+
+	#[error("Const functions cannot {0}")]
+	NotConstFunction(&'static str),
+
+	#[error("Cannot check code that failed to parse")]
+	Unparsed,
+
+	#[error("{trace}: {error}")]
+	At {
+		error: Box<Self>,
+		trace: Trace,
+	},
+}
+
+impl Located for Error {
+	fn located(self, trace: Trace) -> Self {
+		Self::At { error: Box::new(self), trace }
+	}
+
+	fn location(&self) -> Option<(&Self, &Trace)> {
+		match self {
+			Self::At { error, trace } => Some((error, trace)),
+			_ => None,
+		}
+	}
+}
+
+impl<T> From<Error> for Result<T> {
+	fn from(value: Error) -> Self {
+		Err(value)
+	}
+}
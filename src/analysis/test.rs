@@ -0,0 +1,616 @@
+#![allow(clippy::unwrap_used)]
+
+use test_case::test_case;
+use crate::analysis;
+use crate::analysis::{capture, checker};
+use crate::parser;
+use crate::parser::ast::{Expression, Spanned, Statement, TopLevelStatement};
+use crate::parser::ast::function::{Capture, CaptureMode, Function, FunctionSignature};
+use crate::analysis::attribute::Registry;
+use crate::parser::ast::attribute::Attribute;
+use crate::parser::ast::variable::{Identifier, Type};
+use crate::parser::token::Located;
+
+/// Finds the first lambda inside of the first function of the program
+fn first_lambda(statements: &[Spanned<TopLevelStatement>]) -> &Function {
+	fn find_expr(expr: &Spanned<Expression>) -> Option<&Function> {
+		match &expr.node {
+			Expression::Lambda(function) => Some(function),
+			Expression::Scope(body) => body.iter().find_map(find_stmt),
+			Expression::Binary { lhs, rhs, .. } => find_expr(lhs).or_else(|| find_expr(rhs)),
+			Expression::Unary { expr, .. } => find_expr(expr),
+			_ => None,
+		}
+	}
+
+	fn find_stmt(statement: &Spanned<Statement>) -> Option<&Function> {
+		match &statement.node {
+			Statement::Expression(expr) | Statement::Return(expr) | Statement::Delete(expr) | Statement::Defer(expr)
+			| Statement::Assert(expr) | Statement::Destructure { initialisation: expr, otherwise: None, .. } => find_expr(expr),
+			Statement::Destructure { initialisation, otherwise: Some(otherwise), .. } =>
+				find_expr(initialisation).or_else(|| find_expr(otherwise)),
+			Statement::Declaration { initialisation, .. } => initialisation.as_ref().and_then(find_expr),
+			Statement::Error => None,
+		}
+	}
+
+	match &statements[0].node {
+		TopLevelStatement::Function { function, .. } => find_expr(&function.body).unwrap(),
+		_ => panic!("Expected a function"),
+	}
+}
+
+fn captures(source: &'static str) -> Vec<Capture> {
+	let mut statements = parser::parse(source.into()).unwrap().into_result().unwrap();
+	capture::analyse(&mut statements);
+	first_lambda(&statements).captures.clone()
+}
+
+#[test]
+fn lambda_without_captures() {
+	assert_eq!(captures("function f() { let g: i32 = function (x: i32) -> i32 => x * 2 }"), vec![]);
+	assert_eq!(captures("function f() { let g: i32 = function () => global }"), vec![]);
+	assert_eq!(captures("function f() { let g: i32 = function () { var a: i32 = 2; a = 3 } }"), vec![]);
+}
+
+#[test]
+fn capture_by_value() {
+	assert_eq!(
+		captures("function f(a: i32) { let g: i32 = function (x: i32) -> i32 => x * a }"),
+		vec![Capture::new("a", CaptureMode::Value)]
+	);
+
+	// constant variables never need to be shared
+	assert_eq!(
+		captures("function f() { let a: i32 = 2; let g: i32 = function () { a = 3 } }"),
+		vec![Capture::new("a", CaptureMode::Value)]
+	);
+}
+
+#[test]
+fn capture_by_reference() {
+	assert_eq!(
+		captures("function f() { var a: i32 = 2; var b: i32 = 3; let g: i32 = function () { a = b } }"),
+		vec![Capture::new("a", CaptureMode::Reference), Capture::new("b", CaptureMode::Value)]
+	);
+
+	assert_eq!(
+		captures("function f() { var a: i32 = 2; let g: i32 = function () { a + 1; &a } }"),
+		vec![Capture::new("a", CaptureMode::Reference)]
+	);
+
+	assert_eq!(
+		captures("function f(p: Point) { let g: i32 = function () { p.x = 2 } }"),
+		vec![Capture::new("p", CaptureMode::Reference)]
+	);
+}
+
+#[test]
+fn nested_lambda_captures() {
+	let mut statements = parser::parse(
+		"function f(a: i32) { let g: i32 = function () => function () => a }".into()
+	).unwrap().into_result().unwrap();
+	capture::analyse(&mut statements);
+
+	let outer = first_lambda(&statements);
+	assert_eq!(outer.captures, vec![Capture::new("a", CaptureMode::Value)]);
+
+	let Expression::Lambda(inner) = &outer.body.node else { panic!("Expected inner lambda") };
+	assert_eq!(inner.captures, vec![Capture::new("a", CaptureMode::Value)]);
+}
+
+#[test]
+fn shadowed_variable_is_not_captured() {
+	assert_eq!(
+		captures("function f(a: i32) { let g: i32 = function (a: i32) => a }"),
+		vec![]
+	);
+}
+
+/// Type checks the source, returning the declared type of every variable in the first function
+fn declarations(source: &'static str) -> analysis::Result<Vec<Type>> {
+	let mut statements = parser::parse(source.into()).unwrap().into_result().unwrap();
+	checker::check(&mut statements)?;
+
+	let TopLevelStatement::Function { function, .. } = &statements[0].node else { panic!("Expected a function") };
+	let Expression::Scope(body) = &function.body.node else { panic!("Expected a scope") };
+
+	Ok(body.iter().flat_map(|x| match &x.node {
+		Statement::Declaration { var, .. } => vec![var.get_type().clone()],
+		Statement::Destructure { pattern, .. } => pattern.bindings().iter().map(|x| x.get_type().clone()).collect(),
+		_ => vec![]
+	}).collect())
+}
+
+#[test_case("let a = 5", & [Type::I32.as_const()]; "Integer literal")]
+#[test_case("var a = 5.0", & [Type::F64]; "Float literal")]
+#[test_case("var a: u8 = 5", & [Type::U8]; "Literal from annotation")]
+#[test_case("var a = 'c'; var b = no_cap", & [Type::Char, Type::Bool]; "Char and bool")]
+#[test_case("let a = 5; var b = a", & [Type::I32.as_const(), Type::I32]; "From variable")]
+#[test_case("var a: u64 = 2; var b = a * 4", & [Type::U64, Type::U64]; "From binary")]
+#[test_case("var a = 2 == 3", & [Type::Bool]; "From comparison")]
+#[test_case("var a = [10, 20, 4, 2, 1]", & [Type::I32.as_array(5)]; "Array literal")]
+#[test_case("var a: [u8; 2] = [1, 2]", & [Type::U8.as_array(2)]; "Array literal from annotation")]
+#[test_case("var a: [_; 2] = [1.0, 2.0]", & [Type::F64.as_array(2)]; "Array with hole")]
+#[test_case("var a = 4; var b = &a; var c = *b", & [Type::I32, Type::I32.as_pointer(), Type::I32]; "Pointers")]
+#[test_case("var a = [1, 2]; var b = a[0]", & [Type::I32.as_array(2), Type::I32]; "Array access")]
+#[test_case("var a = [1, 2]; var s: *[i32] = &a; var b = s[1]", & [Type::I32.as_array(2), Type::I32.as_slice().as_pointer(), Type::I32]; "Slice access")]
+#[test_case("var a = [1, 2]; var n = a.len; var m = (&a).len", & [Type::I32.as_array(2), Type::USize, Type::USize]; "Array length")]
+#[test_case("var a = (1, 2.0)", & [Type::Tuple(vec ! [Type::I32, Type::F64])]; "Tuple")]
+#[test_case("var a: (u8, f32) = (1, 2.0); var b = a.1", & [Type::Tuple(vec ! [Type::U8, Type::F32]), Type::F32]; "Tuple field")]
+#[test_case("let (a, b: u8) = (1, 2)", & [Type::I32.as_const(), Type::U8.as_const()]; "Destructure")]
+#[test_case("var (a, b) = h(); var c = (a, b).0", & [Type::I32, Type::Bool, Type::I32]; "Destructure call")]
+#[test_case("let ((a, _), [b, ..]) = ((1, 2.0), [3, 4, 5])", & [Type::I32.as_const(), Type::I32.as_const()]; "Nested patterns")]
+#[test_case("var (0, a) = h() else { return f() }", & [Type::Bool]; "Refutable pattern")]
+#[test_case("var a = { 5 }", & [Type::I32]; "Scope value")]
+#[test_case("var a = { 5; }", & [Type::I32]; "Terminated scope value")]
+#[test_case("var a = g()", & [Type::template("Array", vec ! [Type::F32])]; "Function call")]
+#[test_case("var a: Array<> = g()", & [Type::template("Array", vec ! [Type::F32])]; "Empty template arguments")]
+#[test_case("var a: Array<_> = g()", & [Type::template("Array", vec ! [Type::F32])]; "Template argument hole")]
+#[test_case("var a = function (x: i32) => x", & [FunctionSignature::new(vec ! [Type::I32], Type::I32).into()]; "Lambda")]
+fn infer_declaration(body: &'static str, expected: &[Type]) {
+	let source = format!("function f() {{ {body} }} function g() -> Array<f32> => g() function h() -> (i32, bool) => h()");
+	let source: &'static str = Box::leak(source.into_boxed_str());
+
+	assert_eq!(declarations(source).unwrap(), expected);
+}
+
+#[test]
+fn cannot_infer() {
+	let t = |s: &'static str| declarations(s).unwrap_err();
+
+	assert!(matches!(t("function f() { var a = [] }").kind(), analysis::Error::EmptyArray));
+	assert!(matches!(
+		t("function f() { var a: Array<> = g() } function g() -> i32 => 2").kind(),
+		analysis::Error::TypeMismatch { .. }
+	));
+	assert!(matches!(t("function f() { var a = b }").kind(), analysis::Error::UnknownVariable(_)));
+}
+
+#[test]
+fn type_mismatch() {
+	let t = |s: &'static str| declarations(s).unwrap_err();
+
+	assert!(matches!(t("function f() { var a: i32 = 2.0 }").kind(), analysis::Error::TypeMismatch { .. }));
+	assert!(matches!(t("function f() { var a: [i32; 3] = [1, 2] }").kind(), analysis::Error::TypeMismatch { .. }));
+	assert!(matches!(t("function f() -> i32 { no_cap }").kind(), analysis::Error::TypeMismatch { .. }));
+	assert!(matches!(t("function f() { let a = 2; a = 3 }").kind(), analysis::Error::NotAssignable(_)));
+	assert!(matches!(t("function f() { var a = 2 + no_cap }").kind(), analysis::Error::TypeMismatch { .. }));
+	assert!(matches!(t("function f() { var a = f(2) }").kind(), analysis::Error::ArgumentCount { .. }));
+}
+
+#[test]
+fn tuples() {
+	let t = |s: &'static str| declarations(s).unwrap_err();
+
+	assert!(matches!(t("function f() { let (a, b) = (1, 2, 3) }").kind(), analysis::Error::Destructure { expected: 2, .. }));
+	assert!(matches!(t("function f() { let (a, b) = 1 }").kind(), analysis::Error::Destructure { expected: 2, .. }));
+	assert!(matches!(t("function f() { var (a: u8, b) = (1.0, 2) }").kind(), analysis::Error::TypeMismatch { .. }));
+	assert!(matches!(t("function f() { var a = (1, 2); var b = a.2 }").kind(), analysis::Error::UnknownField { .. }));
+	assert!(matches!(t("function f() { let a = (1, 2); a.0 = 3 }").kind(), analysis::Error::NotAssignable(_)));
+	assert!(matches!(t("function f() { var a = 1; var b = a.0 }").kind(), analysis::Error::NotAStruct(_)));
+}
+
+#[test]
+fn patterns() {
+	let t = |body: &str| {
+		let source = format!("function f(xs: [i32; 3]) -> i32 {{ {body} }}");
+		declarations(Box::leak(source.into_boxed_str())).map_err(|x| x.kind().to_string())
+	};
+
+	assert!(t("let [a, b, c] = xs; a").is_ok());
+	assert!(t("let [a, ..] = xs; a").is_ok());
+	assert!(t("let [0, a, ..] = xs else { return 1 }; a").is_ok());
+	assert!(t("let (a, b) = (xs, 2); b").is_ok());
+
+	assert_eq!(t("let [a, b] = xs; a").unwrap_err(), "Value of type [i32; 3] cannot be taken apart by the pattern");
+	assert_eq!(t("let [a, b, c, d, ..] = xs; a").unwrap_err(), "Value of type [i32; 3] cannot be taken apart by the pattern");
+	assert_eq!(t("let [a] = (1,); a").unwrap_err(), "Value of type (i32,) cannot be taken apart by the pattern");
+	assert_eq!(t("let [no_cap, ..] = xs else { return 1 }; 0").unwrap_err(), "Value of type i32 cannot be taken apart by the pattern");
+	assert_eq!(t("let [0, a, ..] = xs; a").unwrap_err(), "Pattern might not match, so needs an 'else' for when it does not");
+	assert_eq!(t("let [0, a, ..] = xs else { 1 }; a").unwrap_err(), "The 'else' of a pattern must never finish, such as by returning");
+	assert!(matches!(t("let [0, a, ..] = xs else { a }; a").unwrap_err(), x if x.contains("Could not find variable 'a'")));
+	assert!(declarations("function f((a, 0): (i32, i32)) {}").is_err());
+	assert!(declarations("function f((a, b): (i32, i32)) -> i32 => a + b").is_ok());
+}
+
+#[test]
+fn nullable_pointers() {
+	let t = |body: &str| {
+		let source = format!("function f(p: ?*i32, q: ?*i32) -> i32 {{ {body} }}");
+		declarations(Box::leak(source.into_boxed_str()))
+	};
+	let error = |body: &str| t(body).unwrap_err().kind().to_string();
+
+	assert_eq!(t("var a: ?*i32 = null; var b: ?*i32 = new i32(2); 0").unwrap(), vec![Type::I32.as_nullable(); 2]);
+	assert_eq!(t("var a = p; a = null; 0").unwrap(), [Type::I32.as_nullable()]);
+
+	assert!(t("if p != null { *p } else { 0 }").is_ok());
+	assert!(t("if null == p { 0 } else { *p }").is_ok());
+	assert!(t("if p == null { return 0 }; *p").is_ok());
+	assert!(t("if p == null or q == null { return 0 }; *p + *q").is_ok());
+	assert!(t("if p != null and *p > 2 { *p } else { 0 }").is_ok());
+	assert!(t("if not (p == null) { *p } else { 0 }").is_ok());
+
+	assert_eq!(error("*p"), "Pointer of type ?*i32 may be null, compare it against null before using it");
+	assert_eq!(error("if p == null { 0 } else { *q }"), error("*p"));
+	assert!(t("if p != null or *p > 2 { 1 } else { 0 }").is_err());
+	assert!(t("if p != null { 1 }; *p").is_err());
+	assert!(t("var r = p; if r != null { r = null; *r } else { 0 }").is_err());
+	assert!(t("var r = p; if r == null { return 0 }; let s = &r; *r").is_err());
+	// the pointer could be set to null through another pointer to it, taken before or after the check
+	assert!(t("var r = p; let s = &r; if r != null { *s = null; *r } else { 0 }").is_err());
+	assert!(t("var r = p; if r != null { *r } else { let s = &r; 0 }").is_err());
+
+	// as can lambdas sharing the variable
+	let source = "function f(p: ?*i32) -> i32 { var r = p; let clear = function () { r = null }; if r != null { clear(); *r } else { 0 } }";
+	let mut statements = parser::parse(source.into()).unwrap().into_result().unwrap();
+	analysis::capture::analyse(&mut statements);
+	assert!(matches!(checker::check(&mut statements).unwrap_err().kind(), analysis::Error::MaybeNull(_)));
+	assert!(matches!(t("var a = null; 0").unwrap_err().kind(), analysis::Error::CannotInfer(_)));
+	assert!(matches!(t("var a: *i32 = p; 0").unwrap_err().kind(), analysis::Error::TypeMismatch { .. }));
+}
+
+#[test]
+fn lower_defer() {
+	let mut statements = parser::parse("function f(p: *i32) -> u8 { defer *p = 1; if no_cap { return 2 }; 3 }".into())
+		.unwrap().into_result().unwrap();
+	checker::check(&mut statements).unwrap();
+
+	let TopLevelStatement::Function { function, .. } = &statements[0].node else { panic!("Expected a function") };
+	let Expression::Scope(body) = &function.body.node else { panic!("Expected a scope") };
+
+	// the rest of the scope is kept in a variable, and the deferred code runs before its value is given
+	let [declaration, deferred, value] = body.as_slice() else { panic!("Expected the lowered scope") };
+	assert!(matches!(&declaration.node, Statement::Declaration { var, .. } if var.get_type() == &Type::U8.as_const()));
+	assert!(matches!(&deferred.node, Statement::Expression(expr) if matches!(expr.node, Expression::Binary { .. })));
+	assert!(matches!(&value.node, Statement::Expression(expr) if matches!(expr.node, Expression::ObjectReference(_))));
+
+	assert!(matches!(
+		declarations("function f() { defer { return 2 } }").unwrap_err().kind(),
+		analysis::Error::ReturnInDefer
+	));
+}
+
+#[test]
+fn slices() {
+	let t = |s: &'static str| declarations(s).unwrap_err();
+
+	assert!(matches!(t("function f(s: *[i32]) { var a: [i32] = *s }").kind(), analysis::Error::Unsized(_)));
+	assert!(matches!(t("function f(a: [i32]) -> i32 => a[0]").kind(), analysis::Error::Unsized(_)));
+	assert!(matches!(t("function f(s: *[i32]) -> [i32] => *s").kind(), analysis::Error::Unsized(_)));
+	assert!(matches!(t("function f(t: *(i32, [i32])) {}").kind(), analysis::Error::Unsized(_)));
+	assert!(matches!(t("function f(s: *[[i32]; 2]) {}").kind(), analysis::Error::Unsized(_)));
+	assert!(matches!(t("function f(s: *[i32]) { var t = (1, *s) }").kind(), analysis::Error::Unsized(_)));
+	assert!(matches!(t("function f() { var a = new [[u8]; 2] }").kind(), analysis::Error::Unsized(_)));
+	assert!(declarations("function f(s: *Bytes, t: (*[u8], i32), n: ?*[*[u8]]) {}\ntype Bytes = [u8]").is_ok());
+	assert!(matches!(t("function f() { var a = [1, 2]; var s: *[u8] = &a }").kind(), analysis::Error::TypeMismatch { .. }));
+	assert!(matches!(t("function f(s: *const [i32]) { s[0] = 2 }").kind(), analysis::Error::NotAssignable(_)));
+	assert!(matches!(t("function f(s: *[i32]) { var a = s.length }").kind(), analysis::Error::NotAStruct(_)));
+	assert!(matches!(t("function f() { var a = 2; var b = a.len }").kind(), analysis::Error::NotAStruct(_)));
+}
+
+#[test]
+fn methods() {
+	let t = |body: &str| {
+		let source = format!(r"function f(p: Point, q: *const Point, n: ?*Point) -> f32 {{ {body} }}
+type Point = (f32, f32)
+impl Point {{
+	function new(x: f32, y: f32) -> Point => (x, y)
+	function length(self: *const Point) -> f32 => self.dot(self)
+	function dot(self: *const Point, other: *const Point) -> f32 => (*self).0 * (*other).0 + (*self).1 * (*other).1
+	function scale(self: *Point, by: f32) {{ (*self).0 = (*self).0 * by }}
+	function x(self: Point) -> f32 => self.0
+}}");
+		declarations(Box::leak(source.into_boxed_str()))
+	};
+	let error = |body: &str| t(body).unwrap_err().kind().to_string();
+
+	assert_eq!(
+		t("let a = Point::new(1.0, 2.0); let b = a.length(); let c = q.x(); a.dot(q)").unwrap(),
+		[Type::Tuple(vec![Type::F32; 2]).as_const(), Type::F32.as_const(), Type::F32.as_const()],
+	);
+	assert!(t("var a = p; a.scale(2.0); a.x()").is_ok());
+	assert!(t("if n != null { n.x() } else { 0.0 }").is_ok());
+
+	assert_eq!(error("p.area()"), "Type Point has no method 'area'");
+	assert_eq!(error("let a = 2.0; a.x()"), "Type const f64 has no method 'x'");
+	assert!(matches!(t("q.scale(2.0); 0.0").unwrap_err().kind(), analysis::Error::TypeMismatch { .. }));
+	assert!(matches!(t("n.x()").unwrap_err().kind(), analysis::Error::MaybeNull(_)));
+	assert!(matches!(t("p.length").unwrap_err().kind(), analysis::Error::NotAStruct(_)));
+
+	// methods are of the alias they are implemented for, not of every type of its layout
+	assert_eq!(error("let a: (f32, f32) = (1.0, 2.0); a.x()"), "Type const (f32, f32) has no method 'x'");
+
+	let duplicate = declarations("type Point = (f32, f32)\nimpl Point { function x(self) -> f32 => self.0 }\nimpl Point { function x(self) -> f32 => self.1 }");
+	assert_eq!(duplicate.unwrap_err().kind().to_string(), "Method 'x' of Point is defined more than once");
+
+	let invalid = declarations("type Point = (f32, f32)\nimpl Point { function x(self: f32) -> f32 => self }").unwrap_err();
+	assert_eq!(invalid.kind().to_string(), "The 'self' of a method of Point must be Point or a pointer to it, given f32");
+}
+
+#[test]
+fn interfaces() {
+	let t = |items: &str| {
+		let source = format!(r"function f() {{}}
+type Circle = (f64, f64)
+type Square = (f64,)
+interface Shape {{
+	function area(self: *const Self) -> f64
+	function name() -> i32
+}}
+function total<T: Shape>(items: *const [T]) -> f64 => items[0].area()
+{items}");
+		declarations(Box::leak(source.into_boxed_str())).map(|_| ())
+	};
+	let error = |items: &str| t(items).unwrap_err().kind().to_string();
+
+	let circle = "impl Shape for Circle {
+		function area(self: *const Circle) -> f64 => (*self).0 * (*self).1
+		function name() -> i32 => 1
+	}";
+	assert!(t(circle).is_ok());
+	assert!(t(&format!("{circle}\nfunction g() -> f64 {{ let c: [Circle; 1] = [(1.0, 2.0)]; total(&c) }}")).is_ok());
+	assert!(t("impl Shape for Square { function area(self: *const Self) -> f64 => (*self).0\nfunction name() -> i32 => 2 }").is_ok());
+
+	assert_eq!(error("impl Shape for Square { function name() -> i32 => 2 }"), "Missing method 'area' of interface 'Shape'");
+	assert_eq!(
+		error("impl Shape for Square { function area(self: *Square) -> f64 => 0.0\nfunction name() -> i32 => 2 }"),
+		"Method 'area' must have type function (*const Square) => f64 to implement its interface, given function (*Square) => f64",
+	);
+	assert!(matches!(
+		t(&format!("{}\nfunction size() -> i32 => 0 }}", &circle[..circle.len() - 1])).unwrap_err().kind(),
+		analysis::Error::NotInInterface { .. },
+	));
+	assert!(matches!(t("impl Drawable for Circle {}").unwrap_err().kind(), analysis::Error::UnknownInterface(_)));
+	assert_eq!(
+		error("function g() -> f64 { let s = [(1.0,)]; total(&s) }"),
+		"Type (f64,) does not implement interface 'Shape'",
+	);
+	let circle_object = format!("{circle}\nfunction g() -> f64 {{ var c: Circle = (1.0, 2.0); let s: dyn Shape = &c; s.area() }}");
+	assert!(t(&circle_object).is_ok());
+	assert_eq!(
+		error("function g() { let c = (1.0,); let s: dyn Shape = &c }"),
+		"Type (f64,) does not implement interface 'Shape'",
+	);
+	assert!(matches!(
+		t(&format!("{circle}\nfunction g() {{ var c: Circle = (1.0, 2.0); let s: dyn Shape = &c; s.name() }}")).unwrap_err().kind(),
+		analysis::Error::UnknownMethod { .. },
+	));
+	assert!(matches!(
+		t("interface Eq { function eq(self, other: Self) -> bool }\nfunction g(e: dyn Eq) => e.eq").unwrap_err().kind(),
+		analysis::Error::NotObjectSafe { .. },
+	));
+	assert!(matches!(t("function g() { let f = total }").unwrap_err().kind(), analysis::Error::GenericValue(_)));
+
+	// generic functions are checked even when never called, knowing only the methods of their bounds
+	assert!(t("function g<T: Shape>(x: T) -> f64 => x.area() + total(&[x])").is_ok());
+	assert_eq!(error("function g<T: Shape>(x: T) -> f64 => nope"), "Could not find variable 'nope'");
+	assert_eq!(error("function g<T: Shape>(x: T) -> f64 => x.missing()"), "Type T has no method 'missing'");
+	assert_eq!(error("function g<T>(x: T) -> f64 => x.area()"), "Type T has no method 'area'");
+	assert_eq!(error("function g<T>(x: T) -> T => x + x"), "Operator Add cannot be used with type T");
+	assert_eq!(error("function g<T>(x: T) -> f64 => total(&[x])"), "Type T does not implement interface 'Shape'");
+	assert!(matches!(t("function g<T>() -> i32 => 0\nfunction h() -> i32 => g()").unwrap_err().kind(), analysis::Error::CannotInfer(_)));
+}
+
+#[test]
+fn dyn_method_slots() {
+	let mut statements = parser::parse(r"function f(s: dyn Shape) -> f64 => s.area()
+interface Shape {
+	function name(self) -> i32
+	function area(self) -> f64
+}".into()).unwrap().into_result().unwrap();
+	checker::check(&mut statements).unwrap();
+
+	// methods of interface objects are called by their position in the vtable
+	let TopLevelStatement::Function { function, .. } = &statements[0].node else { panic!("Expected a function") };
+	let Expression::FunctionCall { function, .. } = &function.body.node else { panic!("Expected a call") };
+	assert!(matches!(&function.node, Expression::DynMethod { method, slot: 1, .. } if method.0 == "area"));
+}
+
+#[test]
+fn operator_overloading() {
+	let t = |body: &str| {
+		let source = format!(r"function f() {{ let a: Vec2 = (1.0, 2.0); var b = a; {body} }}
+type Vec2 = (f32, f32)
+impl Add for Vec2 {{ function add(self, other: Self) -> Self => other }}
+impl Equals for Vec2 {{ function equals(self, other: Self) -> bool => true }}
+impl Index for Vec2 {{ function index(self: *Self, i: usize) -> *f32 => &(*self).0 }}");
+		declarations(Box::leak(source.into_boxed_str())).map(|_| ())
+	};
+
+	assert!(t("let c: Vec2 = a + b + (1.0, 1.0)").is_ok());
+	assert!(t("let c: bool = a == b").is_ok());
+	assert!(t("b[0] = b[1] + 1.0").is_ok());
+	assert!(t("let p = &b; p[0] = 1.0").is_ok());
+	assert!(matches!(t("a + 1.0").unwrap_err().kind(), analysis::Error::TypeMismatch { .. }));
+	assert!(matches!(t("a * b").unwrap_err().kind(), analysis::Error::InvalidOperand { .. }));
+	assert!(matches!(t("-a").unwrap_err().kind(), analysis::Error::InvalidOperand { .. }));
+	// tuples of the same layout are not a 'Vec2', so have no '+' and compare element by element
+	assert!(matches!(t("let c: (f32, f32) = a; c + c").unwrap_err().kind(), analysis::Error::InvalidOperand { .. }));
+	assert!(t("let c: (f32, f32) = a; let d: bool = c == (1.0, 2.0)").is_ok());
+	// constant values cannot be indexed through a method taking a pointer to them
+	assert!(matches!(t("a[0] = 1.0").unwrap_err().kind(), analysis::Error::TypeMismatch { .. }));
+
+	let index = declarations("type V = (f32,)\nimpl Index for V { function index(self: *Self, i: usize) -> f32 => 0.0 }");
+	assert!(matches!(index.unwrap_err().kind(), analysis::Error::SignatureMismatch { .. }));
+}
+
+#[test]
+fn type_aliases() {
+	let t = |body: &str, aliases: &str| {
+		let source = format!("function f() {{ {body} }}\n{aliases}");
+		declarations(Box::leak(source.into_boxed_str()))
+	};
+
+	assert_eq!(
+		t("var m: Mat2 = [[1.0, 0.0], [0.0, 1.0]]", "type Mat2 = [[f32; 2]; 2]").unwrap(),
+		[Type::F32.as_array(2).as_array(2)]
+	);
+	assert_eq!(t("var a: Pair<u8> = [1, 2]", "type Pair<T> = [T; 2]").unwrap(), [Type::U8.as_array(2)]);
+	assert_eq!(t("var a: Bytes = [1, 2]", "type Bytes = Pair<u8>\ntype Pair<T> = [T; 2]").unwrap(), [Type::U8.as_array(2)]);
+	assert_eq!(t("var a: *Pair<_> = &[1, 2]", "type Pair<T> = [T; 2]").unwrap(), [Type::I32.as_array(2).as_pointer()]);
+	assert_eq!(
+		t("var f = function (x: Handle) -> Handle => x", "type Handle = *u8").unwrap(),
+		[FunctionSignature::new(vec![Type::U8.as_pointer()], Type::U8.as_pointer()).into()]
+	);
+
+	assert_eq!(
+		t("var f: Callback<u8> = function (x: u8) {}", "type Callback<T> = function (T) => unit").unwrap(),
+		[FunctionSignature::new(vec![Type::U8], Type::Unit).into()]
+	);
+
+	// the alias is named in the error, as that is what was written
+	assert_eq!(
+		t("var a: Pair<u8> = [1, 2, 3]", "type Pair<T> = [T; 2]").unwrap_err().kind().to_string(),
+		"Expected type Pair<u8> (aka [u8; 2]), given [u8; 3]"
+	);
+	assert!(matches!(t("var a: A = 1", "type A = B\ntype B = A").unwrap_err().kind(), analysis::Error::RecursiveAlias(_)));
+	assert!(matches!(
+		t("var a: Pair<u8, u8> = [1, 2]", "type Pair<T> = [T; 2]").unwrap_err().kind(),
+		analysis::Error::AliasArguments { expected: 1, given: 2, .. }
+	));
+}
+
+#[test]
+fn coerce_types() {
+	assert_eq!(checker::coerce(&Type::I32.as_const(), &Type::I32), Some(Type::I32.as_const()));
+	assert_eq!(checker::coerce(&Type::I32, &Type::I32.as_const()), Some(Type::I32));
+	assert_eq!(
+		checker::coerce(&Type::I32.as_const().as_pointer(), &Type::I32.as_pointer()),
+		Some(Type::I32.as_const().as_pointer())
+	);
+	assert_eq!(checker::coerce(&Type::I32.as_pointer(), &Type::I32.as_const().as_pointer()), None);
+	assert_eq!(checker::coerce(&Type::Infer.as_pointer(), &Type::U8.as_pointer()), Some(Type::U8.as_pointer()));
+	assert_eq!(
+		checker::coerce(&Type::U8.as_slice().as_pointer(), &Type::U8.as_array(3).as_pointer()),
+		Some(Type::U8.as_slice().as_pointer())
+	);
+	assert_eq!(checker::coerce(&Type::U8.as_array(3).as_pointer(), &Type::U8.as_slice().as_pointer()), None);
+}
+
+#[test]
+fn constants() {
+	let t = |body: &str| {
+		let source = format!("function f() {{ {body} }}\nconst N: usize = M * 2\nconst M: usize = 4\nconst SMALL: u8 = 200");
+		declarations(Box::leak(source.into_boxed_str()))
+	};
+	let error = |body: &str| t(body).unwrap_err().kind().to_string();
+
+	assert_eq!(t("let a: *[f32; N * 2] = new [f32; 16]; let b = N").unwrap(), [Type::F32.as_array(16).as_pointer().as_const(), Type::USize.as_const()]);
+	assert_eq!(t("let p = new [i32; M - 1]").unwrap(), [Type::I32.as_array(3).as_pointer().as_const()]);
+	assert_eq!(t("let n: usize = 3; let p = new [i32; n + M]").unwrap(), [Type::USize.as_const(), Type::I32.as_slice().as_pointer().as_const()]);
+	assert_eq!(error("var n: usize = 3; let p: *[i32; 3] = new [i32; n]"), "Expected type const *[i32; 3], given *[i32]");
+	assert_eq!(error("let p = new [i32; M - N]"), "Constant expression overflows type usize");
+	assert_eq!(error("let a: [i32; N / (M - 4)] = 0"), "Division by zero in a constant expression");
+	assert_eq!(error("let a: [i32; M - N] = 0"), "Constant expression overflows type usize");
+	assert!(matches!(t("N = 2").unwrap_err().kind(), analysis::Error::NotAssignable(_)));
+
+	let source = "function f() {\n\tlet n = 2\n\tlet a: [i32; N + n] = 0\n}\nconst N: usize = 1";
+	let analysis::Error::At { error, trace } = declarations(source).unwrap_err() else { panic!("Expected a location") };
+	assert!(matches!(*error, analysis::Error::NotConstant));
+	assert_eq!((trace.begin_pos.row, trace.begin_pos.column), (2, 19));
+
+	let constant = |source: &'static str| declarations(source).unwrap_err().kind().to_string();
+	assert_eq!(constant("function f() {}\nconst X: u8 = 200 + 100"), "Constant expression overflows type u8");
+	assert_eq!(constant("function f() {}\nconst X: i32 = Y\nconst Y: i32 = X + 1"), "Constant 'X' refers to itself");
+	assert_eq!(constant("function f() -> i32 => 1\nconst X: i32 = f()"), analysis::Error::NotConstant.to_string());
+	assert!(matches!(declarations("function f() {}\nconst X: i32 = 1.5").unwrap_err().kind(), analysis::Error::TypeMismatch { .. }));
+}
+
+#[test]
+fn const_functions() {
+	let t = |body: &str| {
+		let source = format!(r"function f() {{ {body} }}
+		const function fib(n: u64) -> u64 => if n < 2 {{ n }} else {{ fib(n - 1) + fib(n - 2) }}
+		const function clamp(x: i32, low: i32) -> i32 {{
+			var y = x
+			if y < low {{
+				y = low
+			}}
+			y
+		}}
+		const function square(n: u64) -> u64 => n * n
+		const function forever(n: u64) -> u64 => forever(n + 1)
+		const function spin(n: u64) -> u64 => if n == 0 {{ 0 }} else {{ spin(n - 1) + spin(n - 1) }}
+		const FIBS: u64 = fib(20)");
+		declarations(Box::leak(source.into_boxed_str()))
+	};
+	let error = |body: &str| t(body).unwrap_err().kind().to_string();
+
+	assert_eq!(t("let a: *[u8; fib(10)] = new [u8; 55]").unwrap(), [Type::U8.as_array(55).as_pointer().as_const()]);
+	assert_eq!(t("let a: *[i32; clamp(-4, 3) + FIBS - 6765] = new [i32; 3]").unwrap(), [Type::I32.as_array(3).as_pointer().as_const()]);
+	assert_eq!(t("let n = fib(5)").unwrap(), [Type::U64.as_const()]);
+	assert_eq!(error("let a: [u8; forever(0)] = 0"), "Constant evaluation went more than 128 const function calls deep");
+	assert_eq!(error("let a: [u8; spin(40)] = 0"), "Constant evaluation did not finish within 1000000 steps");
+	assert_eq!(error("let a: [u8; square(1 << 40)] = 0"), "Constant expression overflows type u64");
+
+	let refused = |source: &'static str| declarations(source).unwrap_err().kind().to_string();
+	assert_eq!(refused("function f() {}\nconst function g(p: *i32) -> i32 => *p"), "Const functions cannot use pointers");
+	assert_eq!(refused("function f() -> i32 => 1\nconst function g() -> *i32 => new i32(1)"), "Const functions cannot allocate memory");
+	assert_eq!(refused("function f() -> i32 => 1\nconst function g() -> i32 => f()"), "Const functions cannot call functions that are not const");
+	assert_eq!(refused("function f() -> i32 => 1\nconst function g() => f"), "Const functions cannot use globals that are not constant");
+	assert_eq!(refused("function f() {}\nconst function g() -> i32 { let a = [1, 2]; 0 }"), "Const functions cannot create arrays or tuples");
+	assert_eq!(refused("function f() {}\nconst function g(a: [i32; 2]) -> i32 => a[0]"), "Const functions cannot index arrays");
+	assert_eq!(refused("function f() {}\nconst function g(t: (i32, i32)) -> i32 => t.0"), "Const functions cannot access fields");
+	assert_eq!(refused("function f() {}\nconst function g((a, b): (i32, i32)) -> i32 => a"), "Const functions cannot destructure values");
+	assert_eq!(refused("function f() -> i32 => 1\nconst X: i32 = f()"), analysis::Error::NotConstant.to_string());
+}
+
+#[test]
+fn tests() {
+	let error = |source: &'static str| declarations(source).unwrap_err().kind().to_string();
+
+	assert!(declarations("function f() {}\ntest \"f runs\" { f(); assert(2 > 1) }").is_ok());
+	assert!(matches!(declarations("function f() { assert(1) }").unwrap_err().kind(), analysis::Error::TypeMismatch { .. }));
+	assert_eq!(error("function f() {}\n@test\nfunction g(x: i32) {}"), "Test 'g' cannot take arguments or type parameters");
+	assert_eq!(error("function f() {}\n@test\nfunction g<T>() {}"), "Test 'g' cannot take arguments or type parameters");
+	assert_eq!(error("function f() {}\nconst function g() -> i32 { assert(1 > 2); 0 }\nconst X: i32 = g()"), "Assertion failed while evaluating a constant");
+	assert_eq!(error("function f() {}\ntest \"same\" {}\ntest \"same\" { assert(1 > 2) }"), "'same' is defined more than once");
+	assert_eq!(error("function f() {}\nfunction f() {}"), "'f' is defined more than once");
+}
+
+#[test]
+fn error_location() {
+	const SOURCE: &str = "function f() {\n\tvar a: i32 = 2\n\tvar b = a + (no_cap)\n}";
+
+	let analysis::Error::At { error, trace } = declarations(SOURCE).unwrap_err() else { panic!("Expected a location") };
+	assert!(matches!(*error, analysis::Error::TypeMismatch { .. }));
+	assert_eq!((trace.begin_pos.row, trace.begin_pos.column), (2, 14));
+	assert_eq!((trace.end_pos.row, trace.end_pos.column), (2, 22));
+}
+
+#[test]
+fn attribute_registry() {
+	let statements = parser::parse(r#"
+		@inline
+		function square(x: i32) -> i32 => x * x
+
+		@deprecated("use square") @export
+		function old_square(x: i32) -> i32 => x * x
+
+		@extern("C")
+		function puts(s: *char) {}
+
+		function plain() {}
+
+		@packed
+		type Header = (u8, u32)
+
+		@deprecated("use Header")
+		const SIZE: usize = 5
+	"#.into()).unwrap().into_result().unwrap();
+
+	let registry = Registry::collect(&statements);
+
+	assert!(registry.has(&"square".into(), "inline"));
+	assert!(!registry.has(&"square".into(), "export"));
+	assert_eq!(registry.get(&"old_square".into()), [Attribute::Deprecated("use square".into()), Attribute::Export]);
+	assert_eq!(registry.deprecated(&"old_square".into()), Some("use square"));
+	assert_eq!(registry.deprecated(&"square".into()), None);
+	assert_eq!(registry.extern_abi(&"puts".into()), Some("C"));
+	assert_eq!(registry.get(&"plain".into()), []);
+	assert_eq!(registry.with("export"), [&Identifier::from("old_square")]);
+	assert!(registry.has(&"Header".into(), "packed"));
+	assert_eq!(registry.deprecated(&"SIZE".into()), Some("use Header"));
+}
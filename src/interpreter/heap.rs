@@ -0,0 +1,114 @@
+use std::fmt;
+use std::fmt::Formatter;
+use crate::interpreter::{Error, Result};
+use crate::interpreter::value::{Pointer, Value};
+use crate::parser::ast::variable::Identifier;
+use crate::parser::token::Trace;
+
+/// Where an allocation came from, used to point at the culprit when memory is misused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+	/// Allocated with 'new', the only kind of memory that can be deleted
+	Heap(Trace),
+	/// Local variable, freed when its scope ends
+	Stack(Identifier),
+	/// Data of a string literal, lives for the whole program
+	Static,
+}
+
+impl fmt::Display for Origin {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Heap(trace) => write!(f, "memory allocated at {trace}"),
+			Self::Stack(ident) => write!(f, "variable '{ident}'"),
+			Self::Static => f.write_str("static memory"),
+		}
+	}
+}
+
+#[derive(Debug)]
+struct Allocation {
+	/// None once the allocation has been freed, freed allocations are kept around so that any
+	/// later use of them can be reported
+	value: Option<Value>,
+	origin: Origin,
+}
+
+/// Every piece of memory the interpreter has handed out, both stack and heap
+#[derive(Debug, Default)]
+pub struct Heap {
+	allocations: Vec<Allocation>,
+}
+
+impl Heap {
+	pub fn allocate(&mut self, value: Value, origin: Origin) -> usize {
+		self.allocations.push(Allocation {
+			value: Some(value),
+			origin,
+		});
+		self.allocations.len() - 1
+	}
+
+	/// Frees memory allocated with 'new'
+	pub fn delete(&mut self, pointer: &Pointer) -> Result {
+		let allocation = self.allocation(pointer.allocation)?;
+
+		match &allocation.origin {
+			Origin::Heap(trace) if allocation.value.is_none() => Error::DoubleFree(trace.clone()).into(),
+			Origin::Heap(_) if pointer.path.is_empty() => {
+				allocation.value = None;
+				Ok(())
+			}
+			origin => Error::InvalidDelete(origin.clone()).into(),
+		}
+	}
+
+	/// Frees a local variable once it goes out of scope
+	pub fn free_stack(&mut self, allocation: usize) {
+		if let Ok(allocation) = self.allocation(allocation) {
+			allocation.value = None;
+		}
+	}
+
+	pub fn load(&mut self, pointer: &Pointer) -> Result<Value> {
+		self.resolve(pointer).cloned()
+	}
+
+	pub fn store(&mut self, pointer: &Pointer, value: Value) -> Result {
+		*self.resolve(pointer)? = value;
+		Ok(())
+	}
+
+	/// Heap allocations that were never deleted
+	pub fn leaks(&self) -> Vec<Trace> {
+		self.allocations.iter()
+			.filter_map(|allocation| match (&allocation.origin, &allocation.value) {
+				(Origin::Heap(trace), Some(_)) => Some(trace.clone()),
+				_ => None
+			})
+			.collect()
+	}
+
+	fn allocation(&mut self, allocation: usize) -> Result<&mut Allocation> {
+		self.allocations.get_mut(allocation).ok_or(Error::InvalidPointer)
+	}
+
+	fn resolve(&mut self, pointer: &Pointer) -> Result<&mut Value> {
+		let allocation = self.allocation(pointer.allocation)?;
+
+		let origin = allocation.origin.clone();
+		let mut value = allocation.value.as_mut().ok_or(Error::UseAfterFree(origin))?;
+
+		for index in &pointer.path {
+			value = match value {
+				Value::Array(elements) | Value::Tuple(elements) => {
+					let length = elements.len();
+					elements.get_mut(*index).ok_or(Error::IndexOutOfBounds { index: *index, length })?
+				}
+				_ => return Error::InvalidPointer.into()
+			};
+		}
+
+		Ok(value)
+	}
+}
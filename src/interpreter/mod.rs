@@ -0,0 +1,760 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::interpreter::heap::{Heap, Origin};
+use crate::interpreter::value::{Binding, Closure, Pointer, Value};
+use crate::parser::ast::{Expression, operator, Spanned, Statement, TopLevelStatement};
+use crate::parser::ast::function::{CaptureMode, Function};
+use crate::parser::ast::pattern::Pattern;
+use crate::parser::ast::variable::{Identifier, Type};
+use crate::parser::token::{Literal, Located, Trace};
+
+pub mod value;
+pub mod heap;
+
+#[cfg(test)]
+mod test;
+
+/// Most function calls that can be in progress at once, deeper recursion is reported instead of
+/// overflowing the stack of the interpreter itself
+const RECURSION_LIMIT: usize = 128;
+
+pub type Result<T = ()> = std::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	#[error("Could not find a 'main' function to run")]
+	NoMain,
+
+	#[error("Could not find variable '{0}'")]
+	UnknownVariable(Identifier),
+
+	#[error("Expected a value of type {expected}, given {given}")]
+	UnexpectedValue {
+		expected: &'static str,
+		given: &'static str,
+	},
+
+	#[error("Division by zero")]
+	DivisionByZero,
+
+	#[error("Division overflows {0}, which cannot hold its minimum divided by -1")]
+	DivisionOverflow(Type),
+
+	#[error("Index {index} is out of bounds for an array of length {length}")]
+	IndexOutOfBounds {
+		index: usize,
+		length: usize,
+	},
+
+	#[error("Cannot cast a value of type {given} to {to}")]
+	InvalidCast {
+		given: &'static str,
+		to: Type,
+	},
+
+	#[error("Expression cannot be evaluated")]
+	Unsupported,
+
+	#[error("Pointer does not point to valid memory")]
+	InvalidPointer,
+
+	#[error("Use of {0} after it was freed")]
+	UseAfterFree(Origin),
+
+	#[error("Double free of memory allocated at {0}")]
+	DoubleFree(Trace),
+
+	#[error("Cannot delete {0}, only the start of memory allocated with 'new' can be deleted")]
+	InvalidDelete(Origin),
+
+	#[error("Assertion failed")]
+	AssertionFailed,
+
+	#[error("Went more than {0} function calls deep")]
+	RecursionLimit(usize),
+
+	#[error("{} allocation(s) were never deleted, allocated at: {}", .0.len(), list_traces(.0))]
+	MemoryLeak(Vec<Trace>),
+
+	#[error("{trace}: {error}")]
+	At {
+		error: Box<Self>,
+		trace: Trace,
+	},
+}
+
+/// Errors are located at the expression being evaluated when they happened
+impl Located for Error {
+	fn located(self, trace: Trace) -> Self {
+		Self::At { error: Box::new(self), trace }
+	}
+
+	fn location(&self) -> Option<(&Self, &Trace)> {
+		match self {
+			Self::At { error, trace } => Some((error, trace)),
+			_ => None,
+		}
+	}
+}
+
+fn list_traces(traces: &[Trace]) -> String {
+	traces.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
+impl<T> From<Error> for Result<T> {
+	fn from(value: Error) -> Self {
+		Err(value)
+	}
+}
+
+/// Reasons evaluation can stop early, errors as well as control flow
+#[derive(Debug)]
+enum Unwind {
+	Return(Value),
+	Error(Error),
+}
+
+impl From<Error> for Unwind {
+	fn from(value: Error) -> Self {
+		Self::Error(value)
+	}
+}
+
+impl Unwind {
+	fn at(self, trace: &Trace) -> Self {
+		match self {
+			Self::Error(error) => Self::Error(error.at(trace)),
+			unwind @ Self::Return(_) => unwind,
+		}
+	}
+}
+
+type Eval<T = Value> = std::result::Result<T, Unwind>;
+
+#[derive(Debug, Default)]
+struct Scope {
+	names: HashMap<Identifier, usize>,
+	/// Allocations freed when the scope ends, captured references are named but not owned
+	owned: Vec<usize>,
+}
+
+#[derive(Debug, Default)]
+pub struct Interpreter {
+	heap: Heap,
+	globals: HashMap<Identifier, usize>,
+	/// Scopes of every function call, innermost call last
+	frames: Vec<Vec<Scope>>,
+}
+
+/// Runs the 'main' function of a type checked program, reporting any memory that was never deleted
+/// once it finishes
+pub fn run(statements: &[Spanned<TopLevelStatement>]) -> Result<Value> {
+	let mut interpreter = Interpreter::load(statements)?;
+	let main = interpreter.lookup(&"main".into()).map_err(|_| Error::NoMain)?;
+	interpreter.run_global(main)
+}
+
+/// Runs a single test function of a type checked program with memory of its own, so that tests
+/// cannot affect each other. Like a program, a test that never deletes some memory fails
+pub fn run_test(statements: &[Spanned<TopLevelStatement>], test: &Identifier) -> Result {
+	let mut interpreter = Interpreter::load(statements)?;
+	let test = interpreter.lookup(test)?;
+	interpreter.run_global(test).map(|_| ())
+}
+
+impl Interpreter {
+	/// Interpreter with the globals of a program allocated, without running anything
+	fn load(statements: &[Spanned<TopLevelStatement>]) -> Result<Self> {
+		let mut interpreter = Self::default();
+
+		for statement in statements {
+			let globals = match &statement.node {
+				TopLevelStatement::Function { ident, function, .. } => vec![(ident, function_value(function))],
+				TopLevelStatement::GlobalVariable { global, .. } => vec![(global.get_name(), Value::zeroed(global.get_type()))],
+				// left as the literal they evaluate to by the checker
+				TopLevelStatement::Const { constant, value, .. } => match interpreter.expression(value) {
+					Ok(value) | Err(Unwind::Return(value)) => vec![(constant.get_name(), value)],
+					Err(Unwind::Error(error)) => return Err(error),
+				},
+				TopLevelStatement::Impl { methods, .. } =>
+					methods.iter().map(|x| (&x.ident, function_value(&x.function))).collect(),
+				TopLevelStatement::TypeAlias { .. } | TopLevelStatement::Interface { .. }
+				| TopLevelStatement::Import(_) | TopLevelStatement::Use(_) => continue,
+			};
+
+			for (ident, value) in globals {
+				let allocation = interpreter.heap.allocate(value, Origin::Static);
+				interpreter.globals.insert(ident.clone(), allocation);
+			}
+		}
+
+		Ok(interpreter)
+	}
+
+	/// Calls the function stored in a global without any arguments
+	fn run_global(&mut self, global: usize) -> Result<Value> {
+		let function = self.heap.load(&Pointer::new(global))?;
+		let value = self.call(&function, vec![])?;
+
+		let leaks = self.heap.leaks();
+		if !leaks.is_empty() {
+			return Error::MemoryLeak(leaks).into();
+		}
+
+		Ok(value)
+	}
+
+	fn scopes(&mut self) -> &mut Vec<Scope> {
+		if self.frames.is_empty() {
+			self.frames.push(vec![]);
+		}
+		self.frames.last_mut().expect("Frame was just pushed")
+	}
+
+	fn push_scope(&mut self) {
+		self.scopes().push(Scope::default());
+	}
+
+	fn pop_scope(&mut self) {
+		if let Some(scope) = self.scopes().pop() {
+			for allocation in scope.owned {
+				self.heap.free_stack(allocation);
+			}
+		}
+	}
+
+	fn declare(&mut self, ident: &Identifier, value: Value) {
+		let allocation = self.heap.allocate(value, Origin::Stack(ident.clone()));
+
+		if let Some(scope) = self.scopes().last_mut() {
+			scope.names.insert(ident.clone(), allocation);
+			scope.owned.push(allocation);
+		}
+	}
+
+	fn lookup(&self, ident: &Identifier) -> Result<usize> {
+		self.frames.last()
+			.and_then(|scopes| scopes.iter().rev().find_map(|scope| scope.names.get(ident)))
+			.or_else(|| self.globals.get(ident))
+			.copied()
+			.ok_or_else(|| Error::UnknownVariable(ident.clone()))
+	}
+
+	fn call(&mut self, function: &Value, arguments: Vec<Value>) -> Result<Value> {
+		let Value::Function(closure) = function else {
+			return unexpected("function", function).into();
+		};
+
+		if self.frames.len() > RECURSION_LIMIT {
+			return Error::RecursionLimit(RECURSION_LIMIT).into();
+		}
+
+		self.frames.push(vec![Scope::default()]);
+
+		for (arg, value) in closure.function.signature.get_args().iter().zip(arguments) {
+			self.declare(arg.get_name(), value);
+		}
+
+		for (ident, binding) in &closure.bindings {
+			match binding {
+				Binding::Value(value) => self.declare(ident, value.clone()),
+				Binding::Reference(allocation) => {
+					if let Some(scope) = self.scopes().last_mut() {
+						scope.names.insert(ident.clone(), *allocation);
+					}
+				}
+			}
+		}
+
+		let result = self.expression(&closure.function.body);
+
+		while !self.scopes().is_empty() {
+			self.pop_scope();
+		}
+		self.frames.pop();
+
+		match result {
+			Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+			Err(Unwind::Error(error)) => Err(error),
+		}
+	}
+
+	fn statement(&mut self, statement: &Spanned<Statement>) -> Eval {
+		self.statement_node(&statement.node).map_err(|unwind| unwind.at(&statement.trace))
+	}
+
+	fn statement_node(&mut self, statement: &Statement) -> Eval {
+		match statement {
+			Statement::Expression(expr) => self.expression(expr),
+			Statement::Declaration { var, initialisation } => {
+				let value = match initialisation {
+					Some(expr) => self.expression(expr)?,
+					None => Value::zeroed(var.get_type()),
+				};
+				self.declare(var.get_name(), value);
+				Ok(Value::Unit)
+			}
+			Statement::Destructure { pattern, initialisation, otherwise } => {
+				let value = self.expression(initialisation)?;
+
+				if !matches(&pattern.node, &value) {
+					// the 'else' never finishes, which the checker made sure of
+					if let Some(otherwise) = otherwise {
+						self.expression(otherwise)?;
+					}
+					return Err(Error::Unsupported.into());
+				}
+
+				self.bind(&pattern.node, value);
+				Ok(Value::Unit)
+			}
+			Statement::Return(expr) => Err(Unwind::Return(self.expression(expr)?)),
+			Statement::Delete(expr) => {
+				// deleting null does nothing
+				let value = self.expression(expr)?;
+				if value == Value::Null {
+					return Ok(Value::Unit);
+				}
+
+				let pointer = pointer(value)?;
+				self.heap.delete(&pointer)?;
+				Ok(Value::Unit)
+			}
+			Statement::Assert(condition) => {
+				if !boolean(&self.expression(condition)?)? {
+					return Err(Error::AssertionFailed.into());
+				}
+				Ok(Value::Unit)
+			}
+			// removed by the checker
+			Statement::Defer(_) | Statement::Error => Err(Error::Unsupported.into()),
+		}
+	}
+
+	/// Declares the variables of a pattern the value matches
+	fn bind(&mut self, pattern: &Pattern, value: Value) {
+		match (pattern, value) {
+			(Pattern::Binding(var), value) => self.declare(var.get_name(), value),
+			(Pattern::Tuple(elements) | Pattern::Array { elements, .. }, Value::Tuple(values) | Value::Array(values)) =>
+				for (element, value) in elements.iter().zip(values) {
+					self.bind(element, value);
+				},
+			_ => {}
+		}
+	}
+
+	fn expression(&mut self, expr: &Spanned<Expression>) -> Eval {
+		self.expression_node(&expr.node, &expr.trace).map_err(|unwind| unwind.at(&expr.trace))
+	}
+
+	fn expression_node(&mut self, expr: &Expression, trace: &Trace) -> Eval {
+		match expr {
+			Expression::Literal(Literal::String(string)) => {
+				let bytes = string.bytes().map(|x| Value::Integer(x.into())).collect();
+				Ok(Value::Pointer(Pointer::new(self.heap.allocate(Value::Array(bytes), Origin::Static))))
+			}
+			Expression::Literal(literal) => Ok(literal_value(literal)),
+			Expression::ObjectReference(ident) => {
+				let allocation = self.lookup(ident)?;
+				Ok(self.heap.load(&Pointer::new(allocation))?)
+			}
+			Expression::Binary { lhs, operator: operator::Binary::Assignment, rhs, .. } => {
+				let place = self.place(lhs)?;
+				let value = self.expression(rhs)?;
+				self.heap.store(&place, value)?;
+				Ok(Value::Unit)
+			}
+			Expression::Binary { lhs, operator: operator::Binary::Dot, rhs, .. } => match &rhs.node {
+				Expression::Literal(Literal::Integer(field)) => self.field(lhs, *field),
+				// otherwise the checker only lets through the length of an array or slice
+				_ => self.length(lhs),
+			},
+			Expression::Binary { lhs, operator: operator::Binary::And, rhs, .. } =>
+				Ok(Value::Bool(boolean(&self.expression(lhs)?)? && boolean(&self.expression(rhs)?)?)),
+			Expression::Binary { lhs, operator: operator::Binary::Or, rhs, .. } =>
+				Ok(Value::Bool(boolean(&self.expression(lhs)?)? || boolean(&self.expression(rhs)?)?)),
+			Expression::Binary { lhs, operator, rhs, ty } => {
+				let lhs = self.expression(lhs)?;
+				let rhs = self.expression(rhs)?;
+				Ok(binary(*operator, lhs, rhs, ty)?)
+			}
+			Expression::Unary { operator: operator::Unary::Reference, expr } => Ok(Value::Pointer(self.place(expr)?)),
+			Expression::Unary { operator: operator::Unary::Dereference, expr } => {
+				let pointer = pointer(self.expression(expr)?)?;
+				Ok(self.heap.load(&pointer)?)
+			}
+			Expression::Unary { operator, expr } => Ok(match (operator, self.expression(expr)?) {
+				(operator::Unary::Negate, Value::Integer(x)) => Value::Integer(x.wrapping_neg()),
+				(operator::Unary::Negate, Value::Float(x)) => Value::Float(-x),
+				(operator::Unary::Not, Value::Integer(x)) => Value::Integer(!x),
+				(operator::Unary::Not, Value::Bool(x)) => Value::Bool(!x),
+				(_, value) => return Err(unexpected("number", &value).into()),
+			}),
+			Expression::Cast { from, to } => Ok(cast(self.expression(from)?, to)?),
+			Expression::FunctionCall { function, arguments } => self.function_call(function, arguments),
+			Expression::Dyn { data, vtable, .. } => self.object(data, vtable),
+			Expression::ArrayAccess { expr: array, index } => {
+				if Self::is_place(array) {
+					let element = self.place(expr)?;
+					return Ok(self.heap.load(&element)?);
+				}
+
+				let array = self.expression(array)?;
+				let index = self.index(index)?;
+				match array {
+					Value::Array(elements) => {
+						let length = elements.len();
+						Ok(elements.into_iter().nth(index).ok_or(Error::IndexOutOfBounds { index, length })?)
+					}
+					value => Ok(self.heap.load(&pointer(value)?.index(index))?),
+				}
+			}
+			Expression::Array(elements) =>
+				Ok(Value::Array(elements.iter().map(|x| self.expression(x)).collect::<Eval<_>>()?)),
+			Expression::Tuple(elements) =>
+				Ok(Value::Tuple(elements.iter().map(|x| self.expression(x)).collect::<Eval<_>>()?)),
+			Expression::New { ty, arguments } => {
+				let value = match arguments.first() {
+					Some(expr) => self.expression(expr)?,
+					None => Value::zeroed(ty),
+				};
+				Ok(Value::Pointer(Pointer::new(self.heap.allocate(value, Origin::Heap(trace.clone())))))
+			}
+			Expression::NewArray { ty, length } => {
+				let length = self.index(length)?;
+				let value = Value::Array(vec![Value::zeroed(ty); length]);
+				Ok(Value::Pointer(Pointer::new(self.heap.allocate(value, Origin::Heap(trace.clone())))))
+			}
+			Expression::Lambda(function) => self.lambda(function),
+			Expression::If { condition, then, otherwise } => {
+				if boolean(&self.expression(condition)?)? {
+					self.expression(then)
+				} else {
+					otherwise.as_ref().map_or(Ok(Value::Unit), |x| self.expression(x))
+				}
+			}
+			Expression::Scope(body) => self.scope(body),
+			// methods of interface objects are only ever called, see [`Interpreter::function_call`]
+			Expression::DynMethod { .. } | Expression::Error => Err(Error::Unsupported.into()),
+		}
+	}
+
+	fn scope(&mut self, body: &[Spanned<Statement>]) -> Eval {
+		self.push_scope();
+
+		let mut value = Ok(Value::Unit);
+		for statement in body {
+			value = self.statement(statement);
+			if value.is_err() { break; }
+		}
+
+		self.pop_scope();
+		value
+	}
+
+	fn lambda(&mut self, function: &Function) -> Eval {
+		let mut bindings = vec![];
+
+		for capture in &function.captures {
+			let allocation = self.lookup(&capture.ident)?;
+			bindings.push((capture.ident.clone(), match capture.mode {
+				CaptureMode::Value => Binding::Value(self.heap.load(&Pointer::new(allocation))?),
+				CaptureMode::Reference => Binding::Reference(allocation),
+			}));
+		}
+
+		Ok(Value::Function(Rc::new(Closure {
+			function: function.clone(),
+			bindings,
+		})))
+	}
+
+	/// Calls a function, where calls of methods are calls of functions by now other than those of
+	/// interface objects
+	fn function_call(&mut self, function: &Spanned<Expression>, arguments: &[Spanned<Expression>]) -> Eval {
+		let (function, receiver) = if let Expression::DynMethod { object, method, slot } = &function.node {
+			let (function, receiver) = self.dyn_method(object, method, *slot)?;
+			(function, Some(receiver))
+		} else {
+			(self.expression(function)?, None)
+		};
+
+		let arguments = receiver.map(Ok).into_iter()
+			.chain(arguments.iter().map(|x| self.expression(x)))
+			.collect::<Eval<Vec<_>>>()?;
+		Ok(self.call(&function, arguments)?)
+	}
+
+	/// Interface object of a pointer, with the functions of its vtable
+	fn object(&mut self, data: &Spanned<Expression>, vtable: &[Identifier]) -> Eval {
+		let data = pointer(self.expression(data)?)?;
+		let vtable = vtable.iter()
+			.map(|function| {
+				let allocation = *self.globals.get(function).ok_or_else(|| Error::UnknownVariable(function.clone()))?;
+				self.heap.load(&Pointer::new(allocation))
+			})
+			.collect::<Result<Vec<_>>>()?;
+
+		Ok(Value::Dyn { data, vtable: Rc::new(vtable) })
+	}
+
+	/// Function implementing a method of an interface object, along with the 'self' to call it with,
+	/// which is the object's pointer or what it points to depending on the function
+	fn dyn_method(&mut self, object: &Spanned<Expression>, method: &Identifier, slot: usize) -> Eval<(Value, Value)> {
+		let object = self.expression(object)?;
+		let Value::Dyn { data, vtable } = &object else {
+			return Err(unexpected("interface object", &object).into());
+		};
+
+		let Some(function) = vtable.get(slot) else {
+			return Err(Error::UnknownVariable(method.clone()).into());
+		};
+
+		let by_pointer = match function {
+			Value::Function(closure) => closure.function.signature.get_args().first()
+				.is_some_and(|x| matches!(x.get_type().value_under_cost(), Type::Pointer(_))),
+			_ => false,
+		};
+
+		let receiver = if by_pointer { Value::Pointer(data.clone()) } else { self.heap.load(data)? };
+		Ok((function.clone(), receiver))
+	}
+
+	/// Element of a tuple, eg. `t.0`
+	fn field(&mut self, tuple: &Spanned<Expression>, field: i64) -> Eval {
+		let index = usize::try_from(field).map_err(|_| Error::Unsupported)?;
+
+		if Self::is_place(tuple) {
+			let element = self.place(tuple)?.index(index);
+			return Ok(self.heap.load(&element)?);
+		}
+
+		match self.expression(tuple)? {
+			Value::Tuple(mut elements) if index < elements.len() => Ok(elements.swap_remove(index)),
+			value => Err(unexpected("tuple", &value).into()),
+		}
+	}
+
+	/// Length of an array, or of the array a pointer points to
+	fn length(&mut self, expr: &Spanned<Expression>) -> Eval {
+		let length = match self.expression(expr)? {
+			Value::Array(elements) => elements.len(),
+			Value::Pointer(pointer) => match self.heap.load(&pointer)? {
+				Value::Array(elements) => elements.len(),
+				value => return Err(unexpected("array", &value).into()),
+			},
+			value => return Err(unexpected("array", &value).into()),
+		};
+
+		Ok(Value::Integer(i64::try_from(length).map_err(|_| Error::Unsupported)?))
+	}
+
+	fn index(&mut self, expr: &Spanned<Expression>) -> Eval<usize> {
+		let index = integer(&self.expression(expr)?)?;
+		usize::try_from(index).map_err(|_| Error::IndexOutOfBounds { index: usize::MAX, length: 0 }.into())
+	}
+
+	fn is_place(expr: &Expression) -> bool {
+		match expr {
+			Expression::Binary { lhs, operator: operator::Binary::Dot, rhs, .. } =>
+				matches!(rhs.node, Expression::Literal(Literal::Integer(_))) && Self::is_place(lhs),
+			expr => matches!(expr, Expression::ObjectReference(_) | Expression::ArrayAccess { .. } | Expression::Unary {
+				operator: operator::Unary::Dereference, ..
+			}),
+		}
+	}
+
+	/// Finds the location an expression refers to, for expressions that can be assigned to
+	fn place(&mut self, expr: &Expression) -> Eval<Pointer> {
+		match expr {
+			Expression::ObjectReference(ident) => Ok(Pointer::new(self.lookup(ident)?)),
+			Expression::Unary { operator: operator::Unary::Dereference, expr } => Ok(pointer(self.expression(expr)?)?),
+			Expression::ArrayAccess { expr, index } => {
+				// indexing through a pointer to an array indexes the array it points to
+				let array = if Self::is_place(expr) {
+					let place = self.place(expr)?;
+					match self.heap.load(&place)? {
+						Value::Pointer(array) => array,
+						_ => place,
+					}
+				} else {
+					pointer(self.expression(expr)?)?
+				};
+
+				let index = self.index(index)?;
+				Ok(array.index(index))
+			}
+			Expression::Binary { lhs, operator: operator::Binary::Dot, rhs, .. } => match rhs.node {
+				Expression::Literal(Literal::Integer(field)) =>
+					Ok(self.place(lhs)?.index(usize::try_from(field).map_err(|_| Error::Unsupported)?)),
+				_ => Err(Error::Unsupported.into()),
+			},
+			_ => Err(Error::Unsupported.into()),
+		}
+	}
+}
+
+/// Value of a top level function, which captures nothing
+fn function_value(function: &Function) -> Value {
+	Value::Function(Rc::new(Closure {
+		function: function.clone(),
+		bindings: vec![],
+	}))
+}
+
+const fn unexpected(expected: &'static str, given: &Value) -> Error {
+	Error::UnexpectedValue { expected, given: given.type_name() }
+}
+
+const fn literal_value(literal: &Literal) -> Value {
+	match literal {
+		Literal::Integer(x) => Value::Integer(*x),
+		Literal::Float(x) => Value::Float(*x),
+		Literal::Bool(x) => Value::Bool(*x),
+		Literal::Character(x) => Value::Char(*x),
+		Literal::String(_) | Literal::Unit => Value::Unit,
+		Literal::Null => Value::Null,
+	}
+}
+
+/// Whether a value can be taken apart by a pattern
+fn matches(pattern: &Pattern, value: &Value) -> bool {
+	match (pattern, value) {
+		(Pattern::Binding(_) | Pattern::Wildcard, _) => true,
+		(Pattern::Literal(literal), value) => literal_value(literal) == *value,
+		(Pattern::Tuple(elements), Value::Tuple(values)) =>
+			elements.len() == values.len() && elements.iter().zip(values).all(|(x, value)| matches(x, value)),
+		(Pattern::Array { elements, rest }, Value::Array(values)) =>
+			(if *rest { values.len() >= elements.len() } else { values.len() == elements.len() })
+				&& elements.iter().zip(values).all(|(x, value)| matches(x, value)),
+		_ => false,
+	}
+}
+
+fn integer(value: &Value) -> Result<i64> {
+	if let Value::Integer(x) = value { Ok(*x) } else { unexpected("integer", value).into() }
+}
+
+fn boolean(value: &Value) -> Result<bool> {
+	if let Value::Bool(x) = value { Ok(*x) } else { unexpected("bool", value).into() }
+}
+
+fn pointer(value: Value) -> Result<Pointer> {
+	if let Value::Pointer(x) = value { Ok(x) } else { unexpected("pointer", &value).into() }
+}
+
+/// Applies a binary operator to two evaluated operands of type `ty`
+fn binary(operator: operator::Binary, lhs: Value, rhs: Value, ty: &Type) -> Result<Value> {
+	use operator::Binary as B;
+
+	Ok(match (lhs, rhs) {
+		(Value::Integer(a), Value::Integer(b)) => integer_binary(operator, a, b, ty)?,
+		(Value::Float(a), Value::Float(b)) => match operator {
+			B::Add => Value::Float(a + b),
+			B::Minus => Value::Float(a - b),
+			B::Multiply => Value::Float(a * b),
+			B::Divide => Value::Float(a / b),
+			B::Mod => Value::Float(a % b),
+			operator => Value::Bool(compare(operator, &a, &b)?),
+		},
+		(Value::Bool(a), Value::Bool(b)) => match operator {
+			B::BitAnd => Value::Bool(a & b),
+			B::BitOr => Value::Bool(a | b),
+			B::BitXor => Value::Bool(a ^ b),
+			operator => Value::Bool(compare(operator, &a, &b)?),
+		},
+		(Value::Char(a), Value::Char(b)) => Value::Bool(compare(operator, &a, &b)?),
+		(a, b) => match operator {
+			B::Equals => Value::Bool(a == b),
+			B::NotEquals => Value::Bool(a != b),
+			_ => return unexpected("number", &a).into(),
+		},
+	})
+}
+
+/// Integers are stored as i64, so results wrap to the width of `ty` and 64 bit unsigned
+/// integers, which are kept as their bit pattern, are compared and divided as u64
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_wrap)]
+fn integer_binary(operator: operator::Binary, a: i64, b: i64, ty: &Type) -> Result<Value> {
+	use operator::Binary as B;
+
+	let unsigned = matches!(ty, Type::U64 | Type::USize);
+	let shift = u32::try_from(b).unwrap_or(u32::MAX);
+
+	let result = match operator {
+		B::Add => a.wrapping_add(b),
+		B::Minus => a.wrapping_sub(b),
+		B::Multiply => a.wrapping_mul(b),
+		B::Divide | B::Mod if b == 0 => return Error::DivisionByZero.into(),
+		B::Divide if unsigned => (a as u64 / b as u64) as i64,
+		B::Mod if unsigned => (a as u64 % b as u64) as i64,
+		B::Divide | B::Mod => {
+			// only the minimum of a signed type divided by -1 leaves its range
+			let quotient = a.checked_div(b).filter(|x| wrap(*x, ty) == *x).ok_or_else(|| Error::DivisionOverflow(ty.clone()))?;
+			if operator == B::Divide { quotient } else { a - quotient * b }
+		}
+		B::ShiftLeft => a.wrapping_shl(shift),
+		B::ShiftRight if unsigned => (a as u64).wrapping_shr(shift) as i64,
+		B::ShiftRight => a.wrapping_shr(shift),
+		B::BitAnd => a & b,
+		B::BitOr => a | b,
+		B::BitXor => a ^ b,
+		operator if unsigned => return Ok(Value::Bool(compare(operator, &(a as u64), &(b as u64))?)),
+		operator => return Ok(Value::Bool(compare(operator, &a, &b)?)),
+	};
+
+	Ok(Value::Integer(wrap(result, ty)))
+}
+
+/// Truncates an integer to the width of `ty`, leaving 64 bit integers as they are
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn wrap(x: i64, ty: &Type) -> i64 {
+	match ty.value_under_cost() {
+		Type::I32 => i64::from(x as i32),
+		Type::I8 => i64::from(x as i8),
+		Type::U32 => i64::from(x as u32),
+		Type::U8 => i64::from(x as u8),
+		_ => x,
+	}
+}
+
+fn compare<T: PartialOrd>(operator: operator::Binary, a: &T, b: &T) -> Result<bool> {
+	use operator::Binary as B;
+
+	Ok(match operator {
+		B::Equals => a == b,
+		B::NotEquals => a != b,
+		B::Less => a < b,
+		B::LessOrEquals => a <= b,
+		B::Greater => a > b,
+		B::GreaterOrEquals => a >= b,
+		_ => return Error::Unsupported.into(),
+	})
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+fn cast(value: Value, to: &Type) -> Result<Value> {
+	let to = to.value_under_cost();
+
+	// integers are stored as i64, so casting between them truncates to the width of the type
+	let truncate = |x: i64| wrap(x, &to);
+
+	Ok(match (value, &to) {
+		(Value::Integer(x), ty) if ty.is_integer() => Value::Integer(truncate(x)),
+		(Value::Integer(x), ty) if ty.is_float() => Value::Float(x as f64),
+		(Value::Float(x), ty) if ty.is_integer() => Value::Integer(truncate(x as i64)),
+		(Value::Float(x), ty) if ty.is_float() => Value::Float(x),
+		(Value::Bool(x), ty) if ty.is_integer() => Value::Integer(x.into()),
+		(Value::Char(x), ty) if ty.is_integer() => Value::Integer(truncate(u32::from(x).into())),
+		(Value::Integer(x), Type::Char) => Value::Char(
+			u32::try_from(x).ok().and_then(char::from_u32).ok_or_else(|| Error::InvalidCast { given: "integer", to: to.clone() })?
+		),
+		(value @ (Value::Pointer(_) | Value::Null), Type::Nullable(_))
+		| (value @ Value::Pointer(_), Type::Pointer(_))
+		| (value @ Value::Bool(_), Type::Bool)
+		| (value @ Value::Char(_), Type::Char)
+		| (value @ Value::Function(_), Type::Function(_))
+		| (value @ Value::Dyn { .. }, Type::Dyn(_)) => value,
+		(value, _) => return Error::InvalidCast { given: value.type_name(), to }.into(),
+	})
+}
@@ -0,0 +1,466 @@
+#![allow(clippy::unwrap_used, clippy::needless_raw_string_hashes, clippy::needless_pass_by_value)]
+
+use test_case::test_case;
+use crate::{analysis, interpreter, parser};
+use crate::interpreter::heap::Origin;
+use crate::interpreter::value::Value;
+use crate::parser::token::Located;
+
+fn run(source: &'static str) -> interpreter::Result<Value> {
+	let mut statements = parser::parse(source.into()).unwrap().into_result().unwrap();
+	analysis::capture::analyse(&mut statements);
+	analysis::checker::check(&mut statements).unwrap();
+	interpreter::run(&statements)
+}
+
+#[test_case("function main() -> i32 => 4 + 2 * 3", Value::Integer(10); "Arithmetic")]
+#[test_case("function main() -> i32 => 7 % 4", Value::Integer(3); "Modulo")]
+#[test_case("function main() -> f64 => 1.5 * 2.0", Value::Float(3.0); "Float arithmetic")]
+#[test_case("function main() -> bool => 2 < 3 and no_cap", Value::Bool(true); "Comparison")]
+#[test_case("function main() -> i32 { var a = 2; a = a * 5; return a }", Value::Integer(10); "Assignment")]
+#[test_case("function main() -> i32 { let a = [1, 2, 3]; a[2] }", Value::Integer(3); "Array access")]
+#[test_case("function main() -> i32 { var a = [1, 2, 3]; a[1] = 5; a[1] }", Value::Integer(5); "Array assignment")]
+#[test_case("function main() -> i32 { var a = 2; set(&a, 4); a } function set(p: *i32, v: i32) { *p = v }", Value::Integer(4); "Pointer write")]
+#[test_case("function main() -> i32 => double(4) function double(x: i32) -> i32 => x * 2", Value::Integer(8); "Function call")]
+#[test_case("function main() -> i32 => apply(function (x: i32) => x + 1, 4) function apply(f: function (i32) => i32, x: i32) -> i32 => f(x)", Value::Integer(5); "Callback parameter")]
+#[test_case("function main() -> u8 { let x: u8 = 255; x + 1 }", Value::Integer(0); "u8 overflow")]
+#[test_case("function main() -> i32 => 2147483647 + 1", Value::Integer(-2_147_483_648); "i32 overflow")]
+#[test_case("function main() -> bool { let x: usize = 0; x - 1 > x }", Value::Bool(true); "usize overflow")]
+fn evaluate(source: &'static str, expected: Value) {
+	assert_eq!(run(source).unwrap(), expected);
+}
+
+#[test]
+fn closures() {
+	assert_eq!(run(r#"function main() -> i32 {
+		let a = 3
+		let triple = function (x: i32) => x * a
+		triple(2)
+	}"#).unwrap(), Value::Integer(6));
+
+	assert_eq!(run(r#"function main() -> i32 {
+		var count = 0
+		let increment = function () { count = count + 1 }
+		increment()
+		increment()
+		count
+	}"#).unwrap(), Value::Integer(2));
+
+	// callbacks can be passed around under an alias of their type
+	assert_eq!(run(r#"function main() -> i32 {
+		var total = 0
+		both(1, 2, function (x: i32) { total = total + x })
+		total
+	}
+
+	type Callback<T> = function (T) => unit
+	function both(a: i32, b: i32, callback: Callback<i32>) {
+		callback(a)
+		callback(b)
+	}"#).unwrap(), Value::Integer(3));
+}
+
+#[test]
+fn heap_allocation() {
+	assert_eq!(run(r#"function main() -> i32 {
+		let p = new i32(5)
+		increment(p)
+		let value = read(p)
+		delete p
+		value
+	}
+
+	function increment(p: *i32) { *p = *p + 1 }
+	function read(p: *i32) -> i32 => *p"#).unwrap(), Value::Integer(6));
+
+	assert_eq!(run(r#"function main() -> i32 {
+		let array = new [i32; 3]
+		array[1] = 7
+		let value = array[1] + array[0]
+		delete array
+		value
+	}"#).unwrap(), Value::Integer(7));
+
+	assert_eq!(run(r#"function main() -> usize {
+		let cells = new [u8; count(2, 3)]
+		cells[5] = 1
+		let length = cells.len
+		delete cells
+		length
+	}
+
+	function count(width: usize, height: usize) -> usize => width * height"#).unwrap(), Value::Integer(6));
+}
+
+#[test]
+fn double_free() {
+	const SOURCE: &str = "function main() { let p = new i32(); delete p; delete p }";
+	let column = |pattern: &str| SOURCE.rfind(pattern).unwrap() + 1;
+
+	let interpreter::Error::At { error, trace } = run(SOURCE).unwrap_err() else { panic!("Expected a location") };
+	assert_eq!(trace.begin_pos.column, column("delete p"));
+	assert!(matches!(*error, interpreter::Error::DoubleFree(trace) if trace.begin_pos.column == column("new")));
+}
+
+#[test]
+fn use_after_free() {
+	let error = run("function main() -> i32 { let p = new i32(2); delete p; read(p) } function read(p: *i32) -> i32 => *p")
+		.unwrap_err();
+	assert!(matches!(error.kind(), interpreter::Error::UseAfterFree(Origin::Heap(_))));
+
+	let error = run(r#"function main() -> i32 {
+		var p = new i32()
+		{
+			var a = 2
+			p = &a
+		}
+		read(p)
+	}
+
+	function read(p: *i32) -> i32 => *p"#).unwrap_err();
+	assert!(matches!(error.kind(), interpreter::Error::UseAfterFree(Origin::Stack(ident)) if ident.0 == "a"));
+}
+
+#[test]
+fn invalid_delete() {
+	let error = run("function main() { var a = 2; delete &a }").unwrap_err();
+	assert!(matches!(error.kind(), interpreter::Error::InvalidDelete(Origin::Stack(_))));
+}
+
+#[test]
+fn memory_leak() {
+	let error = run("function main() { let a = new i32(1); let b = new [u8; 4]; delete a }").unwrap_err();
+	assert!(matches!(error.kind(), interpreter::Error::MemoryLeak(traces) if traces.len() == 1));
+}
+
+#[test]
+fn division_by_zero() {
+	let error = run("function main() -> i32 => 4 / 0").unwrap_err();
+	assert!(matches!(error.kind(), interpreter::Error::DivisionByZero));
+
+	let error = run("function main() -> i32 { let x: i32 = -2147483647 - 1; x / -1 }").unwrap_err();
+	assert!(matches!(error.kind(), interpreter::Error::DivisionOverflow(parser::ast::variable::Type::I32)));
+}
+
+#[test]
+fn slices() {
+	assert_eq!(run(r#"function main() -> i32 {
+		var a = [4, 5, 6]
+		set(&a, 2, 7)
+		a[0] + a[2] + last(&a)
+	}
+
+	function set(s: *[i32], i: usize, v: i32) { s[i] = v }
+	function last(s: *[i32]) -> i32 => s[s.len - 1]"#).unwrap(), Value::Integer(18));
+
+	let error = run(r#"function main() -> i32 {
+		let a = new [i32; 2]
+		let value = get(a, 2)
+		delete a
+		value
+	}
+
+	function get(s: *[i32], i: usize) -> i32 => s[i]"#).unwrap_err();
+	assert!(matches!(error.kind(), interpreter::Error::IndexOutOfBounds { index: 2, length: 2 }));
+}
+
+#[test]
+fn tuples() {
+	assert_eq!(run(r#"function main() -> i32 {
+		let (q, r) = divmod(17, 5)
+		var t = ((q, 0), r)
+		t.0.1 = 10
+		t.0.0 * t.0.1 + t.1
+	}
+
+	function divmod(a: i32, b: i32) -> (i32, i32) => (a / b, a % b)"#).unwrap(), Value::Integer(32));
+}
+
+#[test]
+fn patterns() {
+	assert_eq!(run(r#"function main() -> i32 {
+		let [first, _, ..] = [3, 4, 5, 6]
+		var ((a, _), [b, c]) = ((1, 'x'), [first, 7])
+		a = a + 1
+		a * 100 + b * 10 + c
+	}"#).unwrap(), Value::Integer(237));
+
+	// the 'else' runs when the value does not match, with deferred code before it returns
+	assert_eq!(run(r#"function main() -> i32 {
+		var log = 0
+		let a = second(&log, (0, 4))
+		let b = second(&log, (1, 4))
+		a * 100 + b * 10 + log
+	}
+
+	function second(log: *i32, pair: (i32, i32)) -> i32 {
+		defer *log = *log + 1
+		let (0, x) = pair else { return 9 }
+		x
+	}"#).unwrap(), Value::Integer(492));
+
+	assert_eq!(run(r#"function main() -> i32 => dot((1, 2), [3, 4, 5])
+
+	function dot((x, y): (i32, i32), [a, b, ..]: [i32; 3]) -> i32 => x * a + y * b"#).unwrap(), Value::Integer(11));
+}
+
+#[test]
+fn nullable_pointers() {
+	assert_eq!(run(r#"function main() -> i32 {
+		var a = 4
+		var p: ?*i32 = null
+		let before = read(p)
+		p = &a
+		delete null
+		before + read(p)
+	}
+
+	function read(p: ?*i32) -> i32 {
+		if p == null {
+			return -1
+		}
+		*p
+	}"#).unwrap(), Value::Integer(3));
+}
+
+#[test]
+fn methods() {
+	// the receiver has its address taken or is dereferenced to fit the method's 'self'
+	assert_eq!(run(r#"function main() -> i32 {
+		var counter = Counter::new(5)
+		counter.add(2)
+		let p = &counter
+		p.add(3)
+		p.get() * 100 + counter.get()
+	}
+
+	type Counter = (i32, i32)
+
+	impl Counter {
+		function new(start: i32) -> Counter => (start, 0)
+		function add(self: *Counter, amount: i32) { (*self).0 = (*self).0 + amount; (*self).1 = (*self).1 + 1 }
+		function get(self: Counter) -> i32 => self.0 + self.1
+	}"#).unwrap(), Value::Integer(1212));
+}
+
+#[test]
+fn nominal_methods() {
+	// types of the same layout each have their own methods, and convert to their layout as it is
+	assert_eq!(run(r#"function main() -> i32 {
+		let m: Meters = (2,)
+		let f: Feet = (3,)
+		let plain: (i32,) = f
+		m.show() * 100 + f.show() * 10 + plain.0
+	}
+
+	type Meters = (i32,)
+	type Feet = (i32,)
+
+	impl Meters {
+		function show(self) -> i32 => self.0
+	}
+
+	impl Feet {
+		function show(self) -> i32 => self.0 * 2
+	}"#).unwrap(), Value::Integer(263));
+}
+
+#[test]
+fn generics() {
+	// each call is checked and run with the methods of the type it was called with
+	assert_eq!(run(r#"function main() -> f64 {
+		let circles = [Circle::new(1.0), Circle::new(2.0)]
+		let rectangles: [Rectangle; 1] = [(2.0, 3.0)]
+		total(&circles, 2) + total(&rectangles, 1) * 100.0 + first(&rectangles).1 * 1000.0
+	}
+
+	function total<T: Shape>(shapes: *const [T], count: usize) -> f64 {
+		if count == 0 {
+			return 0.0
+		}
+		shapes[count - 1].area() + total(shapes, count - 1)
+	}
+
+	function first<T>(items: *const [T]) -> T => items[0]
+
+	type Circle = (f64,)
+	type Rectangle = (f64, f64)
+
+	interface Shape {
+		function area(self) -> f64
+	}
+
+	impl Circle {
+		function new(radius: f64) -> Circle => (radius,)
+	}
+
+	impl Shape for Circle {
+		function area(self) -> f64 => self.0 * self.0 * 3.0
+	}
+
+	impl Shape for Rectangle {
+		function area(self: Self) -> f64 => self.0 * self.1
+	}"#).unwrap(), Value::Float(3615.0));
+}
+
+#[test]
+fn interface_objects() {
+	// objects of different types share an array, each calling its own methods through its vtable
+	assert_eq!(run(r#"function main() -> f64 {
+		var c: Circle = (1.0,)
+		let r: Rectangle = (2.0, 3.0)
+		let shapes: [dyn Shape; 2] = [&c, &r]
+		shapes[0].grow(2.0)
+		total(&shapes, 1) + total(&shapes, 0) * 100.0
+	}
+
+	function total(shapes: *const [dyn Shape], i: usize) -> f64 => shapes[i].area()
+
+	type Circle = (f64,)
+	type Rectangle = (f64, f64)
+
+	interface Shape {
+		function area(self) -> f64
+		function grow(self: *Self, by: f64)
+	}
+
+	impl Shape for Circle {
+		function area(self) -> f64 => self.0 * self.0 * 3.0
+		function grow(self: *Circle, by: f64) { (*self).0 = (*self).0 * by }
+	}
+
+	impl Shape for Rectangle {
+		function area(self) -> f64 => self.0 * self.1
+		function grow(self: *Rectangle, by: f64) { (*self).0 = (*self).0 * by }
+	}"#).unwrap(), Value::Float(1206.0));
+}
+
+#[test]
+fn operator_overloading() {
+	// operators of a type other than numbers call the functions of the interfaces it implements
+	assert_eq!(run(r#"function main() -> f32 {
+		let a: Vec2 = (1.0, 2.0)
+		let b: Vec2 = (3.0, 4.0)
+		var c = a + b * b - -a
+		c[1] = c[0] + c[1]
+		if c != (11.0, 31.0) {
+			return 0.0
+		}
+		c[0] * 100.0 + c[1]
+	}
+
+	type Vec2 = (f32, f32)
+
+	impl Add for Vec2 {
+		function add(self, other: Self) -> Self => (self.0 + other.0, self.1 + other.1)
+	}
+
+	impl Subtract for Vec2 {
+		function subtract(self, other: Self) -> Self => (self.0 - other.0, self.1 - other.1)
+	}
+
+	impl Multiply for Vec2 {
+		function multiply(self, other: Self) -> Self => (self.0 * other.0, self.1 * other.1)
+	}
+
+	impl Negate for Vec2 {
+		function negate(self) -> Self => (-self.0, -self.1)
+	}
+
+	impl Equals for Vec2 {
+		function equals(self, other: Self) -> bool => self.0 == other.0 and self.1 == other.1
+	}
+
+	impl Index for Vec2 {
+		function index(self: *Self, i: usize) -> *f32 => if i == 0 { &(*self).0 } else { &(*self).1 }
+	}"#).unwrap(), Value::Float(1131.0));
+}
+
+#[test]
+fn constants() {
+	// constants are used as values and as the lengths of arrays
+	assert_eq!(run(r#"function main() -> i32 {
+		let cells: *[i32; SIZE * SIZE] = new [i32; 16]
+		let length = cells.len
+		delete cells
+		if length != SIZE * 4 {
+			return 0
+		}
+		SCALE * 100 + MASK
+	}
+
+	const SIZE: usize = 2 * 2
+	const SCALE: i32 = -(1 << 3)
+	const MASK: i32 = not 0 xor 7"#).unwrap(), Value::Integer(-808));
+}
+
+#[test]
+fn const_functions() {
+	// const functions run while compiling for constants and array lengths, and as usual otherwise
+	assert_eq!(run(r#"function main() -> u64 {
+		let table: *[u64; fib(6)] = new [u64; 8]
+		let length = table.len
+		delete table
+		if length != 8 {
+			return 0
+		}
+		FIB_20 * 10 + fib(4)
+	}
+
+	const FIB_20: u64 = fib(20)
+
+	const function fib(n: u64) -> u64 {
+		if n < 2 {
+			return n
+		}
+		fib(n - 1) + fib(n - 2)
+	}"#).unwrap(), Value::Integer(67_653));
+}
+
+#[test]
+fn defer() {
+	// deferred code runs last in first out, after the value being returned is found
+	assert_eq!(run(r#"function main() -> i32 {
+		var log = 0
+		let late = run(&log, cap)
+		let early = run(&log, no_cap)
+		late * 1000 + early * 100 + log
+	}
+
+	function run(log: *i32, early: bool) -> i32 {
+		*log = 0
+		defer *log = *log * 10 + 1
+		defer *log = *log * 10 + 2
+		if early {
+			return *log + 3
+		}
+		5
+	}"#).unwrap(), Value::Integer(5321));
+
+	assert_eq!(run(r#"function main() -> i32 {
+		let a = f(cap)
+		a + f(no_cap)
+	}
+
+	function f(early: bool) -> i32 {
+		let p = new i32(2)
+		defer { delete p }
+		{
+			defer *p = *p + 1
+			if early { return *p }
+		}
+		*p * 10
+	}"#).unwrap(), Value::Integer(32));
+
+	// a deferred 'delete' frees the allocation before an early return, so nothing leaks
+	assert_eq!(run(r#"function main() -> i32 => f(cap) + f(no_cap)
+
+	function f(early: bool) -> i32 {
+		let p = new i32(4)
+		defer delete p
+		if early {
+			return *p
+		}
+		*p * 2
+	}"#).unwrap(), Value::Integer(12));
+}
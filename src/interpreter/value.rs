@@ -0,0 +1,92 @@
+use std::rc::Rc;
+use crate::parser::ast::function::Function;
+use crate::parser::ast::variable::{Identifier, Type};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+	Unit,
+	Bool(bool),
+	Char(char),
+	Integer(i64),
+	Float(f64),
+	Pointer(Pointer),
+	/// Nullable pointer that points to nothing
+	Null,
+	Array(Vec<Self>),
+	Tuple(Vec<Self>),
+	Function(Rc<Closure>),
+	/// Interface object, a pointer along with the methods implementing the interface for what it
+	/// points to, in the order the interface declares them
+	Dyn {
+		data: Pointer,
+		vtable: Rc<Vec<Self>>,
+	},
+}
+
+/// Location of a value, the path indexes into arrays and tuples nested inside of the allocation. Pointers to
+/// slices are no different, their length is that of the array they point to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pointer {
+	pub allocation: usize,
+	pub path: Vec<usize>,
+}
+
+impl Pointer {
+	pub const fn new(allocation: usize) -> Self {
+		Self { allocation, path: vec![] }
+	}
+
+	pub fn index(&self, index: usize) -> Self {
+		let mut path = self.path.clone();
+		path.push(index);
+		Self { allocation: self.allocation, path }
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Binding {
+	/// Copy of the captured variable, given a fresh allocation on every call
+	Value(Value),
+	/// Allocation of the captured variable itself
+	Reference(usize),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Closure {
+	pub function: Function,
+	pub bindings: Vec<(Identifier, Binding)>,
+}
+
+impl Value {
+	/// Value a variable of the given type holds before it is assigned to
+	pub fn zeroed(ty: &Type) -> Self {
+		match ty {
+			Type::Unit | Type::Infer | Type::Custom { .. } | Type::Function(_) | Type::Pointer(_) | Type::Slice(_) | Type::Dyn(_) =>
+				Self::Unit,
+			Type::Nullable(_) => Self::Null,
+			Type::Bool => Self::Bool(false),
+			Type::Char => Self::Char('\0'),
+			Type::F32 | Type::F64 => Self::Float(0.0),
+			Type::Const(ty) => Self::zeroed(ty),
+			Type::Array { ty, length } => Self::Array(vec![Self::zeroed(ty); *length]),
+			Type::Tuple(elements) => Self::Tuple(elements.iter().map(Self::zeroed).collect()),
+			_ => Self::Integer(0),
+		}
+	}
+
+	pub const fn type_name(&self) -> &'static str {
+		match self {
+			Self::Unit => "unit",
+			Self::Bool(_) => "bool",
+			Self::Char(_) => "char",
+			Self::Integer(_) => "integer",
+			Self::Float(_) => "float",
+			Self::Pointer(_) => "pointer",
+			Self::Null => "null",
+			Self::Array(_) => "array",
+			Self::Tuple(_) => "tuple",
+			Self::Function(_) => "function",
+			Self::Dyn { .. } => "interface object",
+		}
+	}
+}
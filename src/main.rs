@@ -4,43 +4,126 @@
 
 
 mod parser;
+mod analysis;
 mod interpreter;
+mod module;
+mod runner;
 
-use std::path::{PathBuf};
-use clap::Parser;
+use std::path::{Path, PathBuf};
+use clap::{Parser, Subcommand, ValueEnum};
+use crate::parser::ast::{Spanned, TopLevelStatement};
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-	path: PathBuf,
+	#[command(subcommand)]
+	command: Option<Command>,
+
+	/// Source file to run, or a directory to compile every source file within, running its 'main.pp'
+	#[arg(required = true)]
+	path: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+	/// Runs the test blocks and '@test' functions of a program, each on its own
+	Test {
+		/// Only runs the tests whose name contains this
+		filter: Option<String>,
+
+		/// Source file or directory to find the tests in
+		#[arg(long, default_value = ".")]
+		path: PathBuf,
+
+		#[arg(long, value_enum, default_value_t = Format::Human)]
+		format: Format,
+	},
+}
+
+/// How the results of the tests are written
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+enum Format {
+	Human,
+	Json,
 }
 
 #[derive(Debug, thiserror::Error)]
 enum ArgumentError {
 	#[error("Could not find file to read")]
 	FileNotFound,
+}
 
-	#[error("Could not open file to read")]
-	CouldNotOpenFile,
+#[derive(Debug, thiserror::Error)]
+enum CompileError {
+	#[error("Found {0} error(s) while parsing")]
+	Parsing(usize),
+}
+
+#[derive(Debug, thiserror::Error)]
+enum TestError {
+	#[error("{0} test(s) failed")]
+	Failed(usize),
 }
 
 fn main() -> anyhow::Result<()> {
 	let args = Args::parse();
 
+	if let Some(Command::Test { filter, path, format }) = args.command {
+		let (statements, _) = compile(&path)?;
+		let report = runner::run(&statements, filter.as_deref());
 
-	let a: Vec<_> = vec![10];
+		match format {
+			Format::Human => println!("{}", report.human()),
+			Format::Json => println!("{}", report.json()),
+		}
 
-	if !args.path.exists() {
-		Err(ArgumentError::FileNotFound)?;
+		if report.failed() > 0 {
+			Err(TestError::Failed(report.failed()))?;
+		}
+		return Ok(());
 	}
 
-	println!("Parsing file {:#?}", args.path.as_path());
+	let Some(path) = args.path else {
+		Err(ArgumentError::FileNotFound)?
+	};
 
-	if let Ok(contents) = std::fs::read_to_string(args.path) {
-		parser::parse(contents)?;
-	} else {
-		Err(ArgumentError::CouldNotOpenFile)?;
+	println!("Parsing file {:#?}", path.as_path());
+
+	let (statements, runnable) = compile(&path)?;
+
+	if runnable {
+		interpreter::run(&statements)?;
 	}
 
 	Ok(())
-}
\ No newline at end of file
+}
+
+/// Loads and checks the program at a path, along with whether it has a 'main.pp' to run. A
+/// directory without one is only checked
+fn compile(path: &Path) -> anyhow::Result<(Vec<Spanned<TopLevelStatement>>, bool)> {
+	if !path.exists() {
+		Err(ArgumentError::FileNotFound)?;
+	}
+
+	let program = if path.is_dir() {
+		module::Program::load_directory(path)?
+	} else {
+		module::Program::load_file(path)?
+	};
+
+	for diagnostic in &program.diagnostics {
+		eprintln!("{diagnostic}");
+	}
+
+	if !program.diagnostics.is_empty() {
+		Err(CompileError::Parsing(program.diagnostics.len()))?;
+	}
+
+	let runnable = program.entry.is_some();
+	let mut statements = module::resolve::resolve(program)?;
+
+	analysis::capture::analyse(&mut statements);
+	analysis::checker::check(&mut statements)?;
+
+	Ok((statements, runnable))
+}
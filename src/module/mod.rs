@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use crate::parser;
+use crate::parser::{Diagnostic, LexerError};
+use crate::parser::ast::{Spanned, TopLevelStatement};
+use crate::parser::ast::variable::Identifier;
+use crate::parser::token::{Located, Trace};
+
+pub mod resolve;
+
+#[cfg(test)]
+mod test;
+
+pub type Result<T = ()> = std::result::Result<T, Error>;
+
+/// Extension of source files, directories are searched for files ending in it
+pub const EXTENSION: &str = "pp";
+
+/// Name of the file that is run when a whole directory is compiled
+pub const ENTRY_FILE: &str = "main.pp";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	#[error("Could not read '{}': {error}", .path.display())]
+	Io {
+		path: PathBuf,
+		#[source]
+		error: std::io::Error,
+	},
+
+	#[error("{path}: {error}")]
+	Lexer {
+		path: String,
+		#[source]
+		error: LexerError,
+	},
+
+	#[error("Import cycle {}", .0.join(" -> "))]
+	ImportCycle(Vec<String>),
+
+	#[error("Imports of '{first}' and '{second}' would both be named '{alias}'")]
+	DuplicateImport {
+		alias: String,
+		first: String,
+		second: String,
+	},
+
+	#[error("Could not find an imported module named '{0}'")]
+	UnknownModule(Identifier),
+
+	#[error("Module '{module}' has no item named '{item}'")]
+	UnknownItem {
+		module: String,
+		item: Identifier,
+	},
+
+	#[error("'{item}' is private, declared at {declaration}")]
+	Private {
+		item: Identifier,
+		declaration: Trace,
+	},
+
+	#[error("{trace}: {error}")]
+	At {
+		error: Box<Self>,
+		trace: Trace,
+	},
+}
+
+impl Located for Error {
+	fn located(self, trace: Trace) -> Self {
+		Self::At { error: Box::new(self), trace }
+	}
+
+	fn location(&self) -> Option<(&Self, &Trace)> {
+		match self {
+			Self::At { error, trace } => Some((error, trace)),
+			_ => None,
+		}
+	}
+}
+
+impl<T> From<Error> for Result<T> {
+	fn from(value: Error) -> Self {
+		Err(value)
+	}
+}
+
+/// A single source file, its top level names live in their own namespace named by the id
+#[derive(Debug)]
+pub struct Module {
+	/// Path of the file relative to the root with `::` between directories, eg. `shapes::circle`,
+	/// or its canonical path for files outside of the root. Empty for the entry file, whose names
+	/// are left as they are so 'main' can be found
+	pub id: String,
+	pub path: PathBuf,
+	pub statements: Vec<Spanned<TopLevelStatement>>,
+	/// Modules imported by this one, by the name they are referred to with
+	pub imports: HashMap<String, usize>,
+}
+
+/// Every module reachable from the files that were loaded, each after the modules it imports
+#[derive(Debug, Default)]
+pub struct Program {
+	pub modules: Vec<Module>,
+	/// Index of the module that is run, if there is one
+	pub entry: Option<usize>,
+	pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Program {
+	/// Loads a file and everything it imports, with imports resolved relative to the file importing them
+	pub fn load_file(path: &Path) -> Result<Self> {
+		let path = canonicalize(path)?;
+		let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+		let mut loader = Loader::new(root);
+		let entry = loader.load(&path, true)?;
+		loader.program.entry = Some(entry);
+
+		Ok(loader.program)
+	}
+
+	/// Loads every source file within a directory and its subdirectories, with the 'main.pp'
+	/// at the top as the entry if there is one
+	pub fn load_directory(path: &Path) -> Result<Self> {
+		let root = canonicalize(path)?;
+		let mut loader = Loader::new(root.clone());
+
+		let entry = root.join(ENTRY_FILE);
+		if entry.is_file() {
+			let entry = loader.load(&entry, true)?;
+			loader.program.entry = Some(entry);
+		}
+
+		for file in source_files(&root)? {
+			loader.load(&file, false)?;
+		}
+
+		Ok(loader.program)
+	}
+}
+
+#[derive(Debug)]
+struct Loader {
+	root: PathBuf,
+	program: Program,
+	/// Modules that have been loaded, by their canonical path
+	loaded: HashMap<PathBuf, usize>,
+	/// Files currently being loaded, each importing the next
+	stack: Vec<PathBuf>,
+}
+
+impl Loader {
+	fn new(root: PathBuf) -> Self {
+		Self {
+			root,
+			program: Program::default(),
+			loaded: HashMap::new(),
+			stack: vec![],
+		}
+	}
+
+	fn load(&mut self, path: &Path, entry: bool) -> Result<usize> {
+		let path = canonicalize(path)?;
+
+		if let Some(start) = self.stack.iter().position(|x| x == &path) {
+			let cycle = self.stack[start..].iter()
+				.chain([&path])
+				.map(|x| self.display(x))
+				.collect();
+			return Error::ImportCycle(cycle).into();
+		}
+
+		if let Some(&index) = self.loaded.get(&path) {
+			return Ok(index);
+		}
+
+		let contents = fs::read_to_string(&path).map_err(|error| Error::Io { path: path.clone(), error })?;
+		let parsed = parser::parse_file(contents, self.display(&path))
+			.map_err(|error| Error::Lexer { path: self.display(&path), error })?;
+		self.program.diagnostics.extend(parsed.diagnostics);
+
+		let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+		let mut imports = HashMap::new();
+		// path each alias was imported from, as two files of the same name cannot both be referred to
+		let mut imported = HashMap::<String, &String>::new();
+
+		self.stack.push(path.clone());
+		for statement in &parsed.statements {
+			if let TopLevelStatement::Import(import) = &statement.node {
+				let index = self.load(&directory.join(import), false).map_err(|x| x.at(&statement.trace))?;
+				let alias = alias(import);
+
+				if imports.get(&alias).is_some_and(|&first| first != index) {
+					let error = Error::DuplicateImport { first: imported[&alias].clone(), second: import.clone(), alias };
+					return error.at(&statement.trace).into();
+				}
+
+				imported.insert(alias.clone(), import);
+				imports.insert(alias, index);
+			}
+		}
+		self.stack.pop();
+
+		let id = if entry { String::new() } else { self.id(&path) };
+
+		self.program.modules.push(Module {
+			id,
+			path: path.clone(),
+			statements: parsed.statements,
+			imports,
+		});
+
+		let index = self.program.modules.len() - 1;
+		self.loaded.insert(path, index);
+		Ok(index)
+	}
+
+	/// Path of a file relative to the root when it is inside of it, so traces stay short
+	fn display(&self, path: &Path) -> String {
+		path.strip_prefix(&self.root).unwrap_or(path).display().to_string()
+	}
+
+	/// Namespace of a file, its path relative to the root or its whole canonical path for files
+	/// outside of the root, so no two files share one
+	fn id(&self, path: &Path) -> String {
+		let relative = path.strip_prefix(&self.root).unwrap_or(path);
+
+		relative.with_extension("")
+			.components()
+			.filter_map(|x| match x {
+				Component::Normal(name) => Some(name.to_string_lossy()),
+				_ => None,
+			})
+			.collect::<Vec<_>>()
+			.join("::")
+	}
+}
+
+/// Name an imported module is referred to by, the name of its file without the extension
+fn alias(import: &str) -> String {
+	Path::new(import).file_stem()
+		.map(|x| x.to_string_lossy().into_owned())
+		.unwrap_or_default()
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf> {
+	path.canonicalize().map_err(|error| Error::Io { path: path.to_path_buf(), error })
+}
+
+/// Every source file under a directory, in a stable order
+fn source_files(directory: &Path) -> Result<Vec<PathBuf>> {
+	let entries = fs::read_dir(directory).map_err(|error| Error::Io { path: directory.to_path_buf(), error })?;
+
+	let mut paths = entries
+		.map(|x| x.map(|x| x.path()))
+		.collect::<std::io::Result<Vec<_>>>()
+		.map_err(|error| Error::Io { path: directory.to_path_buf(), error })?;
+	paths.sort();
+
+	let mut files = vec![];
+	for path in paths {
+		if path.is_dir() {
+			files.extend(source_files(&path)?);
+		} else if path.extension().is_some_and(|x| x == EXTENSION) {
+			files.push(path);
+		}
+	}
+
+	Ok(files)
+}
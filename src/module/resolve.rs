@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use crate::module::{Error, Program, Result};
+use crate::parser::ast::{Expression, operator, Spanned, Statement, TopLevelStatement, Visibility};
+use crate::parser::token::{Located, Trace};
+use crate::parser::ast::function::{Function, FunctionSignature};
+use crate::parser::ast::variable::{Identifier, Length, Type, Variable};
+
+/// Type an impl block or interface refers to itself with
+const SELF: &str = "Self";
+
+/// Top level declaration of a module
+#[derive(Debug)]
+struct Item {
+	visibility: Visibility,
+	trace: Trace,
+}
+
+/// Top level names of every module, indexed the same as [`Program::modules`]
+#[derive(Debug)]
+struct Namespaces {
+	ids: Vec<String>,
+	items: Vec<HashMap<Identifier, Item>>,
+	imports: Vec<HashMap<String, usize>>,
+}
+
+impl Namespaces {
+	/// Name a top level statement of a module is known by once every module is merged together
+	fn mangle(&self, module: usize, item: &Identifier) -> Identifier {
+		let id = &self.ids[module];
+		if id.is_empty() { item.clone() } else { format!("{id}::{item}").into() }
+	}
+
+	/// Looks up a path such as `geometry::area` or `geometry::Point::new` from within a module
+	fn qualified(&self, module: usize, path: &Identifier) -> Result<Identifier> {
+		let Some((alias, item)) = path.0.split_once("::") else {
+			return Error::UnknownModule(path.clone()).into();
+		};
+
+		let Some(&imported) = self.imports[module].get(alias) else {
+			return Error::UnknownModule(alias.to_string().into()).into();
+		};
+
+		let item = Identifier(item.to_string());
+		match self.items[imported].get(&item) {
+			None => return Error::UnknownItem { module: alias.to_string(), item }.into(),
+			Some(Item { visibility: Visibility::Private, trace }) =>
+				return Error::Private { item, declaration: trace.clone() }.into(),
+			Some(_) => {}
+		}
+
+		Ok(self.mangle(imported, &item))
+	}
+}
+
+/// Merges the modules of a program into one list of statements, giving every top level name a
+/// prefix of the module it belongs to and pointing references at the names they resolve to
+pub fn resolve(program: Program) -> Result<Vec<Spanned<TopLevelStatement>>> {
+	let namespaces = Namespaces {
+		ids: program.modules.iter().map(|x| x.id.clone()).collect(),
+		items: program.modules.iter()
+			.map(|module| module.statements.iter().flat_map(items).collect())
+			.collect(),
+		imports: program.modules.iter().map(|x| x.imports.clone()).collect(),
+	};
+
+	let mut merged = vec![];
+
+	for (index, module) in program.modules.into_iter().enumerate() {
+		let mut resolver = Resolver {
+			namespaces: &namespaces,
+			module: index,
+			uses: HashMap::new(),
+			scopes: vec![],
+			parameters: vec![],
+		};
+
+		for statement in &module.statements {
+			if let TopLevelStatement::Use(path) = &statement.node {
+				let target = namespaces.qualified(index, path).map_err(|x| x.at(&statement.trace))?;
+				let name = path.0.rsplit_once("::").map_or(path.0.as_str(), |(_, item)| item);
+				resolver.uses.insert(name.to_string().into(), target);
+			}
+		}
+
+		for mut statement in module.statements {
+			let trace = statement.trace.clone();
+
+			match &mut statement.node {
+				TopLevelStatement::Function { ident, generics, function, .. } => {
+					*ident = namespaces.mangle(index, ident);
+					for generic in generics.iter_mut() {
+						for bound in &mut generic.bounds {
+							*bound = resolver.top_level(bound).map_err(|x| x.at(&trace))?.unwrap_or_else(|| bound.clone());
+						}
+					}
+
+					resolver.parameters = generics.iter().map(|x| x.ident.clone()).collect();
+					resolver.function(function).map_err(|x| x.at(&trace))?;
+					resolver.parameters.clear();
+				}
+				TopLevelStatement::GlobalVariable { global, .. } => {
+					let ty = resolver.ty(global.get_type(), &[]).map_err(|x| x.at(&trace))?;
+					*global = Variable::new(namespaces.mangle(index, global.get_name()), ty);
+				}
+				TopLevelStatement::Const { constant, value, .. } => {
+					let ty = resolver.ty(constant.get_type(), &[]).map_err(|x| x.at(&trace))?;
+					*constant = Variable::new(namespaces.mangle(index, constant.get_name()), ty);
+					resolver.expression(value)?;
+				}
+				TopLevelStatement::TypeAlias { ident, parameters, ty, .. } => {
+					*ident = namespaces.mangle(index, ident);
+					*ty = resolver.ty(ty, parameters).map_err(|x| x.at(&trace))?;
+				}
+				TopLevelStatement::Impl { ty, interface, methods } => {
+					if let Some(interface) = interface {
+						*interface = resolver.top_level(interface).map_err(|x| x.at(&trace))?.unwrap_or_else(|| interface.clone());
+					}
+
+					resolver.parameters = vec![SELF.into()];
+					for method in methods {
+						method.ident = namespaces.mangle(index, &method.ident);
+						resolver.function(&mut method.function).map_err(|x| x.at(&method.trace))?;
+					}
+					resolver.parameters.clear();
+
+					*ty = resolver.ty(ty, &[]).map_err(|x| x.at(&trace))?;
+				}
+				TopLevelStatement::Interface { ident, methods, .. } => {
+					*ident = namespaces.mangle(index, ident);
+					for (_, signature) in methods {
+						let Type::Function(ty) = resolver.ty(&signature.as_type(), &[SELF.into()]).map_err(|x| x.at(&trace))? else {
+							unreachable!("function types resolve to function types");
+						};
+						*signature = *ty;
+					}
+				}
+				// fully handled by now, so are left out of the merged program
+				TopLevelStatement::Import(_) | TopLevelStatement::Use(_) => continue,
+			}
+			merged.push(statement);
+		}
+	}
+
+	Ok(merged)
+}
+
+/// Names a top level statement declares, which is one for every statement but impl blocks
+fn items(statement: &Spanned<TopLevelStatement>) -> Vec<(Identifier, Item)> {
+	let (ident, visibility) = match &statement.node {
+		TopLevelStatement::Function { ident, visibility, .. }
+		| TopLevelStatement::TypeAlias { ident, visibility, .. }
+		| TopLevelStatement::Interface { ident, visibility, .. } => (ident, *visibility),
+		TopLevelStatement::GlobalVariable { global: variable, visibility, .. }
+		| TopLevelStatement::Const { constant: variable, visibility, .. } => (variable.get_name(), *visibility),
+		TopLevelStatement::Impl { methods, .. } => return methods.iter()
+			.map(|x| (x.ident.clone(), Item { visibility: x.visibility, trace: x.trace.clone() }))
+			.collect(),
+		TopLevelStatement::Import(_) | TopLevelStatement::Use(_) => return vec![],
+	};
+
+	vec![(ident.clone(), Item { visibility, trace: statement.trace.clone() })]
+}
+
+
+#[derive(Debug)]
+struct Resolver<'a> {
+	namespaces: &'a Namespaces,
+	module: usize,
+	/// Names brought into the module with 'use', along with what they refer to
+	uses: HashMap<Identifier, Identifier>,
+	scopes: Vec<Vec<Identifier>>,
+	/// Type parameters of the generic function being resolved, or 'Self' within impl blocks,
+	/// which are left alone as they do not name top level types
+	parameters: Vec<Identifier>,
+}
+
+impl Resolver<'_> {
+	fn function(&mut self, function: &mut Function) -> Result {
+		let args = function.signature.get_args().iter()
+			.map(|x| Ok(Variable::new(x.get_name().clone(), self.ty(x.get_type(), &self.parameters)?)))
+			.collect::<Result<Vec<_>>>()?;
+		function.signature = FunctionSignature::new_named(args, self.ty(function.signature.get_return_type(), &self.parameters)?);
+
+		self.scopes.push(function.signature.get_args().iter().map(|x| x.get_name().clone()).collect());
+		let result = self.expression(&mut function.body);
+		self.scopes.pop();
+		result
+	}
+
+	fn reference(&self, ident: &Identifier) -> Result<Option<Identifier>> {
+		// locals shadow every top level name
+		if self.scopes.iter().flatten().any(|x| x == ident) {
+			return Ok(None);
+		}
+
+		self.top_level(ident)
+	}
+
+	/// Points the names of aliases within a type at the aliases they resolve to, 'parameters'
+	/// being the parameters of the generic alias the type belongs to, which are left alone
+	fn ty(&self, ty: &Type, parameters: &[Identifier]) -> Result<Type> {
+		Ok(match ty {
+			Type::Custom { name, template_args } => {
+				let template_args = template_args.iter()
+					.map(|x| self.ty(x, parameters))
+					.collect::<Result<Vec<_>>>()?;
+
+				let name = if parameters.contains(name) {
+					name.clone()
+				} else {
+					self.top_level(name)?.unwrap_or_else(|| name.clone())
+				};
+
+				Type::template(name, template_args)
+			}
+			Type::Pointer(ty) => self.ty(ty, parameters)?.as_pointer(),
+			Type::Nullable(ty) => self.ty(ty, parameters)?.as_nullable(),
+			Type::Const(ty) => self.ty(ty, parameters)?.as_const(),
+			Type::Array { ty, length } => self.ty(ty, parameters)?.as_array(*length),
+			Type::ConstArray { ty, length } => Type::ConstArray {
+				ty: Box::new(self.ty(ty, parameters)?),
+				length: Length(Box::new(self.constant(&length.0)?)),
+			},
+			Type::Slice(ty) => self.ty(ty, parameters)?.as_slice(),
+			Type::Dyn(interface) => Type::Dyn(self.top_level(interface)?.unwrap_or_else(|| interface.clone())),
+			Type::Tuple(elements) => Type::Tuple(elements.iter().map(|x| self.ty(x, parameters)).collect::<Result<_>>()?),
+			Type::Function(signature) => {
+				let args = signature.get_args().iter()
+					.map(|x| Ok(Variable::new(x.get_name().clone(), self.ty(x.get_type(), parameters)?)))
+					.collect::<Result<Vec<_>>>()?;
+
+				FunctionSignature::new_named(args, self.ty(signature.get_return_type(), parameters)?).into()
+			}
+			ty => ty.clone(),
+		})
+	}
+
+	/// Resolves a constant expression within a type, which can only refer to top level names
+	fn constant(&self, expr: &Spanned<Expression>) -> Result<Spanned<Expression>> {
+		let mut resolver = Resolver {
+			namespaces: self.namespaces,
+			module: self.module,
+			uses: self.uses.clone(),
+			scopes: vec![],
+			parameters: self.parameters.clone(),
+		};
+
+		let mut expr = expr.clone();
+		resolver.expression(&mut expr)?;
+		Ok(expr)
+	}
+
+	/// Name a reference to a top level statement resolves to, ignoring local variables
+	fn top_level(&self, ident: &Identifier) -> Result<Option<Identifier>> {
+		// functions of impl blocks are paths themselves, eg. `Point::new`
+		if ident.0.contains("::") && !self.namespaces.items[self.module].contains_key(ident) {
+			return self.namespaces.qualified(self.module, ident).map(Some);
+		}
+
+		if let Some(target) = self.uses.get(ident) {
+			return Ok(Some(target.clone()));
+		}
+
+		if self.namespaces.items[self.module].contains_key(ident) {
+			return Ok(Some(self.namespaces.mangle(self.module, ident)));
+		}
+
+		// left for the checker to report
+		Ok(None)
+	}
+
+	fn statement(&mut self, statement: &mut Spanned<Statement>) -> Result {
+		match &mut statement.node {
+			Statement::Expression(expr) | Statement::Return(expr) | Statement::Delete(expr) | Statement::Defer(expr)
+			| Statement::Assert(expr) =>
+				self.expression(expr),
+			Statement::Declaration { var, initialisation } => {
+				if let Some(initialisation) = initialisation {
+					self.expression(initialisation)?;
+				}
+				*var = Variable::new(var.get_name().clone(), self.ty(var.get_type(), &self.parameters)?);
+				if let Some(scope) = self.scopes.last_mut() {
+					scope.push(var.get_name().clone());
+				}
+				Ok(())
+			}
+			Statement::Destructure { pattern, initialisation, otherwise } => {
+				self.expression(initialisation)?;
+				// the else runs before the variables of the pattern exist
+				if let Some(otherwise) = otherwise {
+					self.expression(otherwise)?;
+				}
+				for var in pattern.node.bindings_mut() {
+					*var = Variable::new(var.get_name().clone(), self.ty(var.get_type(), &self.parameters)?);
+					if let Some(scope) = self.scopes.last_mut() {
+						scope.push(var.get_name().clone());
+					}
+				}
+				Ok(())
+			}
+			Statement::Error => Ok(()),
+		}
+	}
+
+	fn expression(&mut self, expr: &mut Spanned<Expression>) -> Result {
+		let trace = expr.trace.clone();
+
+		match &mut expr.node {
+			// the right of a '.' names a field rather than a variable
+			Expression::Binary { lhs, operator: operator::Binary::Dot, .. } => self.expression(lhs),
+			Expression::Binary { lhs, rhs, .. } | Expression::ArrayAccess { expr: lhs, index: rhs } => {
+				self.expression(lhs)?;
+				self.expression(rhs)
+			}
+			// only made by the checker, after every name is resolved
+			Expression::Unary { expr, .. } | Expression::Dyn { data: expr, .. } | Expression::DynMethod { object: expr, .. } =>
+				self.expression(expr),
+			Expression::Cast { from: expr, to: ty } | Expression::NewArray { length: expr, ty } => {
+				*ty = self.ty(ty, &self.parameters).map_err(|x| x.at(&trace))?;
+				self.expression(expr)
+			}
+			Expression::FunctionCall { function, arguments } => {
+				self.expression(function)?;
+				arguments.iter_mut().try_for_each(|x| self.expression(x))
+			}
+			Expression::New { ty, arguments } => {
+				*ty = self.ty(ty, &self.parameters).map_err(|x| x.at(&trace))?;
+				arguments.iter_mut().try_for_each(|x| self.expression(x))
+			}
+			Expression::Array(elements) | Expression::Tuple(elements) => elements.iter_mut().try_for_each(|x| self.expression(x)),
+			Expression::ObjectReference(ident) => {
+				if let Some(resolved) = self.reference(ident).map_err(|x| x.at(&trace))? {
+					*ident = resolved;
+				}
+				Ok(())
+			}
+			Expression::If { condition, then, otherwise } => {
+				self.expression(condition)?;
+				self.expression(then)?;
+				otherwise.as_deref_mut().map_or(Ok(()), |x| self.expression(x))
+			}
+			Expression::Literal(_) | Expression::Error => Ok(()),
+			Expression::Lambda(function) => self.function(function),
+			Expression::Scope(body) => {
+				self.scopes.push(vec![]);
+				let result = body.iter_mut().try_for_each(|x| self.statement(x));
+				self.scopes.pop();
+				result
+			}
+		}
+	}
+}
@@ -0,0 +1,304 @@
+#![allow(clippy::unwrap_used)]
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use crate::{analysis, interpreter};
+use crate::interpreter::value::Value;
+use crate::module::{Error, Program, Result};
+use crate::module::resolve::resolve;
+use crate::parser::token::Located;
+
+/// Directory a project was written to, removed again once the test is done with it
+struct Project(PathBuf);
+
+impl Deref for Project {
+	type Target = Path;
+
+	fn deref(&self) -> &Path {
+		&self.0
+	}
+}
+
+impl Drop for Project {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.0);
+	}
+}
+
+/// Writes the files of a project into a fresh directory
+fn project(name: &str, files: &[(&str, &str)]) -> Project {
+	let root = std::env::temp_dir().join(format!("pplang-{name}-{}", std::process::id()));
+	let _ = fs::remove_dir_all(&root);
+
+	for (path, contents) in files {
+		let path = root.join(path);
+		fs::create_dir_all(path.parent().unwrap()).unwrap();
+		fs::write(path, contents).unwrap();
+	}
+
+	Project(root)
+}
+
+fn run(program: Result<Program>) -> Result<Value> {
+	let program = program?;
+	assert!(program.diagnostics.is_empty(), "{:?}", program.diagnostics);
+
+	let mut statements = resolve(program)?;
+	analysis::capture::analyse(&mut statements);
+	analysis::checker::check(&mut statements).unwrap();
+	Ok(interpreter::run(&statements).unwrap())
+}
+
+fn run_file(root: &Path) -> Result<Value> {
+	run(Program::load_file(&root.join("main.pp")))
+}
+
+#[test]
+fn qualified_reference() {
+	let root = project("qualified", &[
+		("main.pp", "import \"geometry.pp\"\nfunction main() -> i32 => geometry::area(2, 3)"),
+		("geometry.pp", "pub function area(w: i32, h: i32) -> i32 => w * h"),
+	]);
+
+	assert_eq!(run_file(&root).unwrap(), Value::Integer(6));
+}
+
+#[test]
+fn use_brings_into_scope() {
+	let root = project("use", &[
+		("main.pp", "import \"geometry.pp\"\nuse geometry::area\nfunction main() -> i32 => area(4, 2)"),
+		("geometry.pp", "pub function area(w: i32, h: i32) -> i32 => w * h"),
+	]);
+
+	assert_eq!(run_file(&root).unwrap(), Value::Integer(8));
+}
+
+#[test]
+fn separate_namespaces() {
+	// both modules have their own 'helper', and each only sees its own
+	let root = project("namespaces", &[
+		("main.pp", "import \"a.pp\"\nfunction helper() -> i32 => 1\nfunction main() -> i32 => helper() * 10 + a::call()"),
+		("a.pp", "function helper() -> i32 => 2\npub function call() -> i32 => helper()"),
+	]);
+
+	assert_eq!(run_file(&root).unwrap(), Value::Integer(12));
+}
+
+#[test]
+fn locals_shadow_items() {
+	let root = project("shadow", &[
+		("main.pp", "function value() -> i32 => 1\nfunction main() -> i32 { let value = 5; value }"),
+	]);
+
+	assert_eq!(run_file(&root).unwrap(), Value::Integer(5));
+}
+
+#[test]
+fn relative_to_importer() {
+	let root = project("relative", &[
+		("main.pp", "import \"shapes/square.pp\"\nfunction main() -> i32 => square::area(3)"),
+		("shapes/square.pp", "import \"../util.pp\"\npub function area(x: i32) -> i32 => util::mul(x, x)"),
+		("util.pp", "pub function mul(a: i32, b: i32) -> i32 => a * b"),
+	]);
+
+	assert_eq!(run_file(&root).unwrap(), Value::Integer(9));
+}
+
+#[test]
+fn shared_import_loaded_once() {
+	let root = project("shared", &[
+		("main.pp", "import \"a.pp\"\nimport \"b.pp\"\nfunction main() -> i32 => a::one() + b::two()"),
+		("a.pp", "import \"util.pp\"\npub function one() -> i32 => util::id(1)"),
+		("b.pp", "import \"util.pp\"\npub function two() -> i32 => util::id(2)"),
+		("util.pp", "pub function id(x: i32) -> i32 => x"),
+	]);
+
+	let program = Program::load_file(&root.join("main.pp")).unwrap();
+	assert_eq!(program.modules.len(), 4);
+	assert_eq!(run(Ok(program)).unwrap(), Value::Integer(3));
+}
+
+#[test]
+fn import_cycle() {
+	let root = project("cycle", &[
+		("main.pp", "import \"a.pp\"\nfunction main() -> i32 => 0"),
+		("a.pp", "import \"b.pp\""),
+		("b.pp", "\nimport \"a.pp\""),
+	]);
+
+	let error = Program::load_file(&root.join("main.pp")).unwrap_err();
+	assert!(matches!(error.kind(), Error::ImportCycle(cycle) if cycle == &["a.pp", "b.pp", "a.pp"]));
+
+	// located at the import that closes the cycle
+	let Error::At { trace, .. } = error else { panic!("Expected a location") };
+	assert_eq!(trace.source_file, "b.pp");
+	assert_eq!(trace.begin_pos.row, 1);
+}
+
+#[test]
+fn unknown_names() {
+	let root = project("unknown", &[
+		("main.pp", "import \"geometry.pp\"\nfunction main() -> i32 => geometry::volume(1)"),
+		("geometry.pp", "pub function area(w: i32, h: i32) -> i32 => w * h"),
+	]);
+	assert!(matches!(run_file(&root).unwrap_err().kind(), Error::UnknownItem { item, .. } if item.0 == "volume"));
+
+	let root = project("unknown_module", &[
+		("main.pp", "use shapes::area\nfunction main() -> i32 => 0"),
+	]);
+	assert!(matches!(run_file(&root).unwrap_err().kind(), Error::UnknownModule(module) if module.0 == "shapes"));
+
+	let root = project("missing_file", &[
+		("main.pp", "import \"missing.pp\"\nfunction main() -> i32 => 0"),
+	]);
+	assert!(matches!(run_file(&root).unwrap_err().kind(), Error::Io { .. }));
+}
+
+#[test]
+fn duplicate_import() {
+	let root = project("duplicate_import", &[
+		("main.pp", "import \"a/util.pp\"\nimport \"b/util.pp\"\nfunction main() -> i32 => util::value()"),
+		("a/util.pp", "pub function value() -> i32 => 1"),
+		("b/util.pp", "pub function value() -> i32 => 2"),
+	]);
+
+	let error = run_file(&root).unwrap_err();
+	assert_eq!(error.to_string(), "main.pp:2:1: Imports of 'a/util.pp' and 'b/util.pp' would both be named 'util'");
+
+	// the same file imported twice is still the one module
+	let root = project("repeated_import", &[
+		("main.pp", "import \"a/util.pp\"\nimport \"./a/util.pp\"\nfunction main() -> i32 => util::value()"),
+		("a/util.pp", "pub function value() -> i32 => 1"),
+	]);
+	assert_eq!(run_file(&root).unwrap(), Value::Integer(1));
+}
+
+#[test]
+fn outside_root() {
+	// both files named 'util' are outside of the root, and keep namespaces of their own
+	let root = project("outside_root", &[
+		("app/main.pp", "import \"../a/util.pp\"\nimport \"../b/other.pp\"\nfunction main() -> i32 => util::value() * 10 + other::value()"),
+		("a/util.pp", "pub function value() -> i32 => 1"),
+		("b/other.pp", "import \"util.pp\"\npub function value() -> i32 => util::value()"),
+		("b/util.pp", "pub function value() -> i32 => 2"),
+	]);
+
+	let program = Program::load_file(&root.join("app/main.pp")).unwrap();
+	let canonical = root.canonicalize().unwrap();
+	let id = |path: &str| canonical.join(path).components()
+		.filter_map(|x| match x {
+			std::path::Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+			_ => None,
+		})
+		.collect::<Vec<_>>()
+		.join("::");
+
+	let ids: Vec<_> = program.modules.iter().map(|x| x.id.clone()).collect();
+	assert_eq!(ids, [id("a/util"), id("b/util"), id("b/other"), String::new()]);
+	assert_eq!(run(Ok(program)).unwrap(), Value::Integer(12));
+}
+
+#[test]
+fn directory() {
+	let root = project("directory", &[
+		("main.pp", "import \"shapes/circle.pp\"\nfunction main() -> i32 => circle::radius()"),
+		("shapes/circle.pp", "pub function radius() -> i32 => 7"),
+		("unused.pp", "function value() -> i32 => 1"),
+	]);
+
+	let program = Program::load_directory(&root).unwrap();
+	let mut ids: Vec<_> = program.modules.iter().map(|x| x.id.as_str()).collect();
+	ids.sort_unstable();
+	assert_eq!(ids, ["", "shapes::circle", "unused"]);
+
+	assert_eq!(run(Ok(program)).unwrap(), Value::Integer(7));
+}
+
+#[test]
+fn private_items() {
+	let root = project("private", &[
+		("main.pp", "import \"a.pp\"\nfunction main() -> i32 => a::helper()"),
+		("a.pp", "pub function call() -> i32 => helper()\nfunction helper() -> i32 => 2"),
+	]);
+
+	let error = run_file(&root).unwrap_err();
+	assert_eq!(error.to_string(), "main.pp:2:27: 'helper' is private, declared at a.pp:2:1");
+
+	let root = project("private_use", &[
+		("main.pp", "import \"a.pp\"\nuse a::helper\nfunction main() -> i32 => a::call()"),
+		("a.pp", "pub function call() -> i32 => helper()\nfunction helper() -> i32 => 2"),
+	]);
+	assert!(matches!(run_file(&root).unwrap_err().kind(), Error::Private { item, .. } if item.0 == "helper"));
+}
+
+#[test]
+fn type_alias_from_module() {
+	let root = project("alias", &[
+		("main.pp", "import \"geometry.pp\"\nfunction main() -> i32 { var v: geometry::Vec2 = [3, 4]; geometry::dot(&v, &v) }"),
+		("geometry.pp", "pub type Vec2 = [i32; 2]\npub function dot(a: *Vec2, b: *Vec2) -> i32 => a[0] * b[0] + a[1] * b[1]"),
+	]);
+
+	assert_eq!(run_file(&root).unwrap(), Value::Integer(25));
+}
+
+#[test]
+fn methods_from_module() {
+	let root = project("methods", &[
+		("main.pp", "import \"geometry.pp\"\nfunction main() -> i32 { let v = geometry::Vec2::new(3, 4); v.dot(&v) }"),
+		("geometry.pp", r"pub type Vec2 = (i32, i32)
+
+impl Vec2 {
+	pub function new(x: i32, y: i32) -> Vec2 => (x, y)
+	pub function dot(self: *const Vec2, other: *const Vec2) -> i32 => self.x() * other.x() + (*self).1 * (*other).1
+	function x(self: Vec2) -> i32 => self.0
+}"),
+	]);
+
+	assert_eq!(run_file(&root).unwrap(), Value::Integer(25));
+
+	let root = project("private_method", &[
+		("main.pp", "import \"geometry.pp\"\nfunction main() -> i32 => geometry::Vec2::zero()"),
+		("geometry.pp", "pub type Vec2 = (i32, i32)\nimpl Vec2 { function zero() -> i32 => 0 }"),
+	]);
+	assert!(matches!(run_file(&root).unwrap_err().kind(), Error::Private { item, .. } if item.0 == "Vec2::zero"));
+}
+
+#[test]
+fn interface_from_module() {
+	// the generic function is instantiated with a type and impl of the importing module
+	let root = project("interface", &[
+		("main.pp", r#"import "geometry.pp"
+type Square = (i32,)
+impl geometry::Shape for Square {
+	function area(self) -> i32 => self.0 * self.0
+}
+function main() -> i32 { let s: [Square; 1] = [(3,)]; geometry::first_area(&s) }"#),
+		("geometry.pp", r"pub interface Shape {
+	function area(self) -> i32
+}
+pub function first_area<T: Shape>(shapes: *const [T]) -> i32 => shapes[0].area()"),
+	]);
+
+	assert_eq!(run_file(&root).unwrap(), Value::Integer(9));
+}
+
+#[test]
+fn constant_from_module() {
+	// lengths of array types resolve the constants they use like any other expression
+	let root = project("constant", &[
+		("main.pp", r#"import "grid.pp"
+use grid::WIDTH
+function main() -> usize {
+	let cells: *[u8; grid::HEIGHT * WIDTH] = new [u8; 6]
+	let length = cells.len
+	delete cells
+	length + grid::area()
+}"#),
+		("grid.pp", r"pub const WIDTH: usize = 3
+pub const HEIGHT: usize = 2
+pub function area() -> usize => WIDTH * HEIGHT"),
+	]);
+
+	assert_eq!(run_file(&root).unwrap(), Value::Integer(12));
+}
@@ -0,0 +1,71 @@
+use std::fmt;
+use std::fmt::Formatter;
+
+/// Annotation written in front of a declaration, eg. `@inline` or `@deprecated("use foo")`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Attribute {
+	Inline,
+	/// Function run by the test runner rather than the program
+	Test,
+	/// Using the declaration is discouraged, with a message saying what to use instead
+	Deprecated(String),
+	/// Function implemented outside of the program, using the calling convention named, eg. "C"
+	Extern(String),
+	/// Function visible to code outside of the program
+	Export,
+	/// Tuple laid out without padding between its elements, written on a type alias of one
+	Packed,
+}
+
+/// Kind of declaration an attribute is written on
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Target {
+	Function,
+	/// Type alias of a tuple, the closest there is to a struct
+	Tuple,
+	Variable,
+	TypeAlias,
+	Import,
+	Use,
+	Impl,
+	Interface,
+}
+
+impl Attribute {
+	/// Name the attribute is written with, without the '@'
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::Inline => "inline",
+			Self::Test => "test",
+			Self::Deprecated(_) => "deprecated",
+			Self::Extern(_) => "extern",
+			Self::Export => "export",
+			Self::Packed => "packed",
+		}
+	}
+
+	pub const fn applies_to(&self, target: Target) -> bool {
+		match self {
+			Self::Inline | Self::Test | Self::Extern(_) => matches!(target, Target::Function),
+			Self::Export => matches!(target, Target::Function | Target::Variable),
+			// anything that is used by name can be deprecated
+			Self::Deprecated(_) => !matches!(target, Target::Import | Target::Use | Target::Impl),
+			Self::Packed => matches!(target, Target::Tuple),
+		}
+	}
+}
+
+impl fmt::Display for Target {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Function => "a function",
+			Self::Tuple => "a tuple type alias",
+			Self::Variable => "a variable",
+			Self::TypeAlias => "a type alias",
+			Self::Import => "an import",
+			Self::Use => "a 'use'",
+			Self::Impl => "an impl block",
+			Self::Interface => "an interface",
+		})
+	}
+}
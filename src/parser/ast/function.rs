@@ -1,7 +1,7 @@
 use std::fmt::{Display, Formatter, Write};
 use std::hash::{Hash, Hasher};
-use crate::parser::ast::{Expression, Statement};
-use crate::parser::ast::variable::{Type, Variable};
+use crate::parser::ast::{Expression, Spanned, Statement};
+use crate::parser::ast::variable::{Identifier, Type, Variable};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[allow(clippy::module_name_repetitions)]
@@ -32,6 +32,19 @@ impl FunctionSignature {
 	pub fn as_type(&self) -> Type {
 		self.clone().into()
 	}
+
+	/// Type of a function with this signature, argument names are not part of the type
+	pub fn as_unnamed_type(&self) -> Type {
+		Self::new(self.args.iter().map(|x| x.get_type().clone()).collect(), self.returns.clone()).into()
+	}
+
+	pub fn get_args(&self) -> &[Variable] {
+		&self.args
+	}
+
+	pub const fn get_return_type(&self) -> &Type {
+		&self.returns
+	}
 }
 
 impl From<FunctionSignature> for Type {
@@ -43,14 +56,12 @@ impl From<FunctionSignature> for Type {
 impl Display for FunctionSignature {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
 		f.write_str("function (")?;
-		let args = &self.args;
-		if !args.is_empty() {
-			f.write_fmt(format_args!("{}", args[0].get_type()))?;
-		}
 
-		let args = &self.args[1..];
-		for arg in args {
-			f.write_fmt(format_args!(", {}", arg.get_type()))?;
+		for (i, arg) in self.args.iter().enumerate() {
+			if i > 0 {
+				f.write_str(", ")?;
+			}
+			f.write_fmt(format_args!("{}", arg.get_type()))?;
 		}
 
 		f.write_fmt(format_args!(") => {}", self.returns))?;
@@ -62,5 +73,48 @@ impl Display for FunctionSignature {
 #[derive(Debug, PartialEq, Clone)]
 pub struct Function {
 	pub signature: FunctionSignature,
-	pub body: Box<Expression>,
+	pub body: Box<Spanned<Expression>>,
+	/// Variables from enclosing scopes used by this function, always empty for top level functions
+	/// and only filled in for lambdas once capture analysis has been run
+	pub captures: Vec<Capture>,
+}
+
+impl Function {
+	pub const fn new(signature: FunctionSignature, body: Box<Spanned<Expression>>) -> Self {
+		Self { signature, body, captures: vec![] }
+	}
+
+	/// Type of the function as a value, argument names are not part of the type
+	pub fn as_type(&self) -> Type {
+		self.signature.as_unnamed_type()
+	}
+}
+
+/// Type parameter of a generic function along with the interfaces its type has to implement,
+/// eg. `T: Shape`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generic {
+	pub ident: Identifier,
+	pub bounds: Vec<Identifier>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CaptureMode {
+	/// Variable is copied into the lambda when it is created
+	Value,
+	/// Variable is shared with the enclosing scope, needed when the lambda writes to it or takes
+	/// its address
+	Reference,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Capture {
+	pub ident: Identifier,
+	pub mode: CaptureMode,
+}
+
+impl Capture {
+	pub fn new(ident: impl Into<Identifier>, mode: CaptureMode) -> Self {
+		Self { ident: ident.into(), mode }
+	}
 }
\ No newline at end of file
@@ -1,36 +1,188 @@
-use crate::parser::ast::function::{Function, FunctionSignature};
+use crate::parser::ast::attribute::Attribute;
+use crate::parser::ast::function::{Function, FunctionSignature, Generic};
+use crate::parser::ast::pattern::Pattern;
 use crate::parser::ast::variable::{Identifier, Type, Variable};
-use crate::parser::token::Literal;
+use std::ops::{Deref, DerefMut};
+use crate::parser::token::{Literal, Trace};
 
 pub mod operator;
 pub mod variable;
 pub mod function;
+pub mod attribute;
+pub mod pattern;
 #[cfg(test)]
 mod test;
 
+/// AST node along with the part of the source it was parsed from. Nodes are compared by their
+/// contents alone, so trees parsed from differently laid out sources are equal
+#[derive(Debug, Clone)]
+pub struct Spanned<T> {
+	pub node: T,
+	pub trace: Trace,
+}
+
+impl<T> Spanned<T> {
+	pub const fn new(node: T, trace: Trace) -> Self {
+		Self { node, trace }
+	}
+}
+
+impl<T: PartialEq> PartialEq for Spanned<T> {
+	fn eq(&self, other: &Self) -> bool {
+		self.node == other.node
+	}
+}
+
+impl<T> Deref for Spanned<T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.node
+	}
+}
+
+impl<T> DerefMut for Spanned<T> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.node
+	}
+}
+
+/// Nodes built outside of the parser have no source, so are given an empty trace
+impl From<TopLevelStatement> for Spanned<TopLevelStatement> {
+	fn from(value: TopLevelStatement) -> Self {
+		Self::new(value, Trace::default())
+	}
+}
+
+impl From<Pattern> for Spanned<Pattern> {
+	fn from(value: Pattern) -> Self {
+		Self::new(value, Trace::default())
+	}
+}
+
+impl<T: Into<Statement>> From<T> for Spanned<Statement> {
+	fn from(value: T) -> Self {
+		Self::new(value.into(), Trace::default())
+	}
+}
+
+impl<T: Into<Expression>> From<T> for Spanned<Expression> {
+	fn from(value: T) -> Self {
+		Self::new(value.into(), Trace::default())
+	}
+}
+
+/// Whether a top level declaration can be used from other modules, written as 'pub' when it can
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Visibility {
+	#[default]
+	Private,
+	Public,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum TopLevelStatement {
+	/// Function declared at the top level, generic functions are checked once for every set of
+	/// types they are called with, eg. `function total<T: Shape>(items: *[T]) -> f64`
 	Function {
 		ident: Identifier,
+		generics: Vec<Generic>,
 		function: Function,
+		attributes: Vec<Spanned<Attribute>>,
+		visibility: Visibility,
+		/// Whether the function is declared `const function`, which lets it be called wherever a
+		/// constant expression is asked for
+		constant: bool,
 	},
 	GlobalVariable {
-		global: Variable
+		global: Variable,
+		attributes: Vec<Spanned<Attribute>>,
+		visibility: Visibility,
+	},
+	/// Value known while compiling, eg. `const N: usize = 4 * 4`. Constants are used as values and
+	/// wherever a constant expression is asked for, such as the length of an array type
+	Const {
+		constant: Variable,
+		value: Spanned<Expression>,
+		attributes: Vec<Spanned<Attribute>>,
+		visibility: Visibility,
+	},
+	/// Another name for a type, eg. `type Mat4 = [[f32; 4]; 4]`. Generic aliases name their
+	/// parameters, which are replaced by the template arguments the alias is used with
+	TypeAlias {
+		ident: Identifier,
+		parameters: Vec<Identifier>,
+		ty: Type,
+		attributes: Vec<Spanned<Attribute>>,
+		visibility: Visibility,
+	},
+	/// Makes the top level statements of another file available through its name, eg.
+	/// `import "geometry.pp"` allows `geometry::area`. The path is relative to the importing file
+	Import(String),
+	/// Brings something from an imported module into scope under its own name, eg. `use geometry::area`
+	Use(Identifier),
+	/// Functions belonging to a type, eg. `impl Point { function length(self: *const Point) -> f32 => .. }`.
+	/// Functions whose first argument is named 'self' are methods called as `p.length()`, every
+	/// function can be called through the type, eg. `Point::new(1.0, 2.0)`. Written as
+	/// `impl Shape for Circle` when the functions are those of an interface
+	Impl {
+		ty: Type,
+		interface: Option<Identifier>,
+		methods: Vec<Spanned<Method>>,
+	},
+	/// Methods a type has to implement to be used where the interface is asked for, eg.
+	/// `interface Shape { function area(self) -> f64 }`. Within it 'Self' is the implementing type
+	Interface {
+		ident: Identifier,
+		methods: Vec<(Identifier, FunctionSignature)>,
+		attributes: Vec<Spanned<Attribute>>,
+		visibility: Visibility,
 	},
 }
 
+/// Function declared within an 'impl' block, named after the type it belongs to, eg. `Point::new`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Method {
+	pub ident: Identifier,
+	pub function: Function,
+	pub visibility: Visibility,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Statement {
-	Expression(Expression),
+	Expression(Spanned<Expression>),
 	Declaration {
 		var: Variable,
-		initialisation: Option<Expression>,
+		initialisation: Option<Spanned<Expression>>,
+	},
+	/// Declares the variables of a pattern, eg. `let (q, r) = divmod(a, b)`. A refutable pattern
+	/// has an 'else' that never finishes, which runs when the value does not match, eg.
+	/// `let (0, x) = pair else { return }`
+	Destructure {
+		pattern: Spanned<Pattern>,
+		initialisation: Spanned<Expression>,
+		otherwise: Option<Box<Spanned<Expression>>>,
 	},
-	Return(Expression),
+	Return(Spanned<Expression>),
+	/// Frees memory allocated with 'new'
+	Delete(Spanned<Expression>),
+	/// Runs an expression when the scope it is in is left, after any code deferred later in the
+	/// scope. Removed once the scope is type checked, by copying it to wherever the scope is left
+	Defer(Spanned<Expression>),
+	/// Stops a test with a failure when the condition does not hold, eg. `assert(len == 3)`
+	Assert(Spanned<Expression>),
+	/// Statement that failed to parse, the error is reported as a diagnostic
+	Error,
 }
 
 impl From<Expression> for Statement {
 	fn from(value: Expression) -> Self {
+		Self::Expression(value.into())
+	}
+}
+
+impl From<Spanned<Expression>> for Statement {
+	fn from(value: Spanned<Expression>) -> Self {
 		Self::Expression(value)
 	}
 }
@@ -38,30 +190,72 @@ impl From<Expression> for Statement {
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
 	Binary {
-		lhs: Box<Expression>,
+		lhs: Box<Spanned<Self>>,
 		operator: operator::Binary,
-		rhs: Box<Expression>,
+		rhs: Box<Spanned<Self>>,
+		/// Type of the operands, which integer arithmetic wraps to the width of. Left to be
+		/// inferred by the parser and filled in by the checker
+		ty: Type,
 	},
 	Unary {
 		operator: operator::Unary,
-		expr: Box<Expression>,
+		expr: Box<Spanned<Self>>,
 	},
 	Cast {
-		from: Box<Expression>,
+		from: Box<Spanned<Self>>,
 		to: Type,
 	},
 	FunctionCall {
-		function: Box<Expression>,
-		arguments: Vec<Expression>,
+		function: Box<Spanned<Self>>,
+		arguments: Vec<Spanned<Self>>,
 	},
 	ObjectReference(Identifier),
 	ArrayAccess {
-		expr: Box<Expression>,
-		index: Box<Expression>,
+		expr: Box<Spanned<Self>>,
+		index: Box<Spanned<Self>>,
 	},
 	Literal(Literal),
+	/// Array literal, eg. '[1, 2, 3]'
+	Array(Vec<Spanned<Self>>),
+	/// Tuple of two or more values, or a single value followed by a comma, eg. '(1, 2.0)' or '(1,)'
+	Tuple(Vec<Spanned<Self>>),
+	/// Heap allocation of a single value, eg. 'new i32(5)', evaluates to a pointer to the value
+	New {
+		ty: Type,
+		arguments: Vec<Spanned<Self>>,
+	},
+	/// Heap allocation of an array, eg. 'new [i32; 10]', evaluates to a pointer to the array. One
+	/// whose length is not constant, eg. 'new [i32; n]', evaluates to a pointer to a slice
+	NewArray {
+		ty: Type,
+		length: Box<Spanned<Self>>,
+	},
 	Lambda(Function),
-	Scope(Vec<Statement>),
+	Scope(Vec<Spanned<Statement>>),
+	/// Evaluates to the first branch when the condition holds and to the second otherwise. Both
+	/// branches are scopes, except for an 'else if' whose second branch is the inner 'if'
+	If {
+		condition: Box<Spanned<Self>>,
+		then: Box<Spanned<Self>>,
+		otherwise: Option<Box<Spanned<Self>>>,
+	},
+	/// Interface object made from a pointer, eg. the value of `&circle` used as a 'dyn Shape'.
+	/// Never parsed, the checker inserts it along with the vtable, which is the functions
+	/// implementing the interface for the type pointed to, in the order the interface declares them
+	Dyn {
+		data: Box<Spanned<Self>>,
+		interface: Identifier,
+		vtable: Vec<Identifier>,
+	},
+	/// Method of an interface object being called, eg. `shape.area` in `shape.area()`. Never
+	/// parsed, the checker inserts it along with the position of the method in the vtable
+	DynMethod {
+		object: Box<Spanned<Self>>,
+		method: Identifier,
+		slot: usize,
+	},
+	/// Expression that failed to parse, the error is reported as a diagnostic
+	Error,
 }
 
 impl From<Identifier> for Expression {
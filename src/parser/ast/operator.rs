@@ -28,6 +28,44 @@ pub enum Binary {
 	Dot,
 }
 
+/// Function calls and array accesses bind as tightly as '.', so 'a.b(c)' calls 'a.b'
+pub const POSTFIX_PRECEDENCE: u8 = Binary::Dot.precedence();
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Associativity {
+	/// 'a - b - c' is '(a - b) - c'
+	Left,
+	/// 'a = b = c' is 'a = (b = c)'
+	Right,
+}
+
+impl Binary {
+	/// How tightly the operator binds to its operands, higher binds tighter
+	pub const fn precedence(self) -> u8 {
+		match self {
+			Self::Assignment => 1,
+			Self::Or => 2,
+			Self::And => 3,
+			Self::BitOr => 4,
+			Self::BitXor => 5,
+			Self::BitAnd => 6,
+			Self::Equals | Self::NotEquals => 7,
+			Self::Less | Self::LessOrEquals | Self::Greater | Self::GreaterOrEquals => 8,
+			Self::ShiftLeft | Self::ShiftRight => 9,
+			Self::Add | Self::Minus => 10,
+			Self::Multiply | Self::Divide | Self::Mod => 11,
+			Self::Dot => 13,
+		}
+	}
+
+	pub const fn associativity(self) -> Associativity {
+		match self {
+			Self::Assignment => Associativity::Right,
+			_ => Associativity::Left,
+		}
+	}
+}
+
 impl TryFrom<Operator> for Binary {
 	type Error = ();
 
@@ -64,6 +102,16 @@ pub enum Unary {
 	Dereference,
 }
 
+impl Unary {
+	/// Binds tighter than every binary operator apart from '.', so '-a + b' is '(-a) + b' while
+	/// '*p.x' is '*(p.x)'
+	pub const fn precedence(self) -> u8 {
+		match self {
+			Self::Negate | Self::Not | Self::Reference | Self::Dereference => 12,
+		}
+	}
+}
+
 impl TryFrom<Operator> for Unary {
 	type Error = ();
 
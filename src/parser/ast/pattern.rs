@@ -0,0 +1,52 @@
+use crate::parser::ast::Spanned;
+use crate::parser::ast::variable::Variable;
+use crate::parser::token::Literal;
+
+/// Shape a value is taken apart into, binding names to its parts, eg. `(q, [first, ..])`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+	/// Binds the whole value to a variable, whose type is inferred when not written, eg. `x: i32`
+	Binding(Variable),
+	/// Matches any value without binding it, `_`
+	Wildcard,
+	/// Only matches a value equal to the literal, eg. `0`
+	Literal(Literal),
+	/// Tuple with a pattern for each of its elements, eg. `(q, r)`
+	Tuple(Vec<Spanned<Self>>),
+	/// Array whose first elements match the patterns. Unless the rest is left out with `..` the
+	/// array has as many elements as there are patterns, eg. `[first, second, ..]`
+	Array {
+		elements: Vec<Spanned<Self>>,
+		rest: bool,
+	},
+}
+
+impl Pattern {
+	/// Variables the pattern binds, in the order they are written
+	pub fn bindings(&self) -> Vec<&Variable> {
+		match self {
+			Self::Binding(var) => vec![var],
+			Self::Tuple(elements) | Self::Array { elements, .. } => elements.iter().flat_map(|x| x.bindings()).collect(),
+			Self::Wildcard | Self::Literal(_) => vec![],
+		}
+	}
+
+	pub fn bindings_mut(&mut self) -> Vec<&mut Variable> {
+		match self {
+			Self::Binding(var) => vec![var],
+			Self::Tuple(elements) | Self::Array { elements, .. } =>
+				elements.iter_mut().flat_map(|x| x.node.bindings_mut()).collect(),
+			Self::Wildcard | Self::Literal(_) => vec![],
+		}
+	}
+
+	/// Whether a value of the type the pattern is checked against might not match it, such
+	/// patterns can only declare variables along with an 'else' for when they do not
+	pub fn is_refutable(&self) -> bool {
+		match self {
+			Self::Literal(_) => true,
+			Self::Tuple(elements) | Self::Array { elements, .. } => elements.iter().any(|x| x.is_refutable()),
+			Self::Binding(_) | Self::Wildcard => false,
+		}
+	}
+}
@@ -19,6 +19,7 @@ use crate::parser::ast::function::FunctionSignature;
 #[test_case(Type::template("BruhMoment", vec ! [Type::I64, Type::F32.as_pointer()]), "BruhMoment<i64, *f32>")]
 #[test_case(Type::U8.as_const(), "const u8")]
 #[test_case(Type::U8.as_const().as_pointer(), "*const u8")]
+#[test_case(FunctionSignature::new(vec ! [], Type::Unit).into(), "function () => unit")]
 #[test_case(FunctionSignature::new(vec ! [Type::F32], Type::Unit).into(), "function (f32) => unit")]
 #[test_case(FunctionSignature::new(vec ! [Type::F32, Type::U8], Type::Unit).into(), "function (f32, u8) => unit")]
 #[test_case(FunctionSignature::new(vec ! [Type::F32], Type::Unit).as_type().as_pointer().as_const(), "const *function (f32) => unit")]
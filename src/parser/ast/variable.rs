@@ -1,11 +1,18 @@
 use std::fmt;
 use std::fmt::Formatter;
-use crate::parser::ast::Expression;
+use std::hash::{Hash, Hasher};
+use crate::parser::ast::{Expression, Spanned};
 use crate::parser::ast::function::FunctionSignature;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub struct Identifier(pub String);
 
+impl fmt::Display for Identifier {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
 impl From<String> for Identifier {
 	fn from(value: String) -> Self {
 		Self(value)
@@ -42,19 +49,54 @@ pub enum Type {
 	F32,
 	F64,
 	USize,
+	Bool,
+	Char,
+	/// Placeholder for a type that is left to be inferred, written as '_'. A template argument
+	/// list that is only '<>' is parsed as a single Infer, which stands in for every argument
+	Infer,
 	Function(Box<FunctionSignature>),
 	Pointer(Box<Type>),
+	/// Pointer that may be null, written as '?*T'. It has to be checked against null before it
+	/// can be used as a '*T'
+	Nullable(Box<Self>),
 	Const(Box<Type>),
 	Array {
 		ty: Box<Type>,
 		length: usize,
 	},
+	/// Array whose length is a constant expression rather than a literal, eg. '[f32; N * 2]'. It is
+	/// a [`Type::Array`] once the length is evaluated, which happens as its aliases are expanded
+	ConstArray {
+		ty: Box<Self>,
+		length: Length,
+	},
+	/// Array of unknown length, written as '[T]'. It has no size, so it is only ever used behind a
+	/// pointer, which then also carries the length
+	Slice(Box<Self>),
+	/// Values of different types grouped together, eg. '(i32, f32)'. Elements are accessed by
+	/// their position, eg. 't.0'
+	Tuple(Vec<Self>),
+	/// Pointer to a value of any type implementing an interface, written as 'dyn Shape'. It also
+	/// points at the functions implementing the interface for that type, which its methods call
+	Dyn(Identifier),
 	Custom {
 		name: Identifier,
 		template_args: Vec<Type>,
 	},
 }
 
+/// Constant expression giving the length of a [`Type::ConstArray`]. Lengths are equal when their
+/// expressions are
+#[derive(Debug, Clone, PartialEq)]
+pub struct Length(pub Box<Spanned<Expression>>);
+
+impl Eq for Length {}
+
+/// Expressions cannot be hashed, so every length hashes the same, which equal lengths then do too
+impl Hash for Length {
+	fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
 impl fmt::Display for Type {
 	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
 		f.write_str(self.name().as_str())
@@ -76,10 +118,18 @@ impl Type {
 		}
 	}
 
+	pub fn as_slice(&self) -> Self {
+		Self::Slice(Box::new(self.clone()))
+	}
+
 	pub fn as_pointer(&self) -> Self {
 		Self::Pointer(Box::new(self.clone()))
 	}
 
+	pub fn as_nullable(&self) -> Self {
+		Self::Nullable(Box::new(self.clone()))
+	}
+
 	pub fn as_const(&self) -> Self {
 		match self {
 			Self::Const(ty) => self.clone(),
@@ -92,12 +142,18 @@ impl Type {
 			Self::Unit => Some(0),
 			Self::USize => Some(std::mem::size_of::<usize>()),
 			Self::I64 | Self::U64 | Self::F64 => Some(8),
-			Self::I32 | Self::U32 | Self::F32 => Some(4),
-			Self::U8 | Self::I8 => Some(1),
+			Self::I32 | Self::U32 | Self::F32 | Self::Char => Some(4),
+			Self::U8 | Self::I8 | Self::Bool => Some(1),
 			Self::Const(ty) => ty.size_of(),
 			Self::Array { ty, length } => ty.size_of().map(|x| x * length),
-			Self::Function(_) | Self::Pointer(_) => Self::USize.size_of(),
-			Self::Custom { .. } => None,
+			// pointers to slices also hold the length
+			Self::Pointer(ty) | Self::Nullable(ty) if matches!(ty.value_under_cost(), Self::Slice(_)) =>
+				Self::USize.size_of().map(|x| x * 2),
+			// the data and the functions implementing the interface for it
+			Self::Dyn(_) => Self::USize.size_of().map(|x| x * 2),
+			Self::Function(_) | Self::Pointer(_) | Self::Nullable(_) => Self::USize.size_of(),
+			Self::Tuple(elements) => elements.iter().map(Self::size_of).sum(),
+			Self::Slice(_) | Self::ConstArray { .. } | Self::Custom { .. } | Self::Infer => None,
 		}
 	}
 
@@ -113,12 +169,22 @@ impl Type {
 			Self::U8 => "u8".into(),
 			Self::F32 => "f32".into(),
 			Self::F64 => "f64".into(),
+			Self::Bool => "bool".into(),
+			Self::Char => "char".into(),
+			Self::Infer => "_".into(),
 			Self::Function(signature) => format!("{signature}"),
 			Self::Const(underlying) => format!("const {underlying}"),
 			Self::Pointer(underlying) => format!("*{underlying}"),
+			Self::Nullable(underlying) => format!("?*{underlying}"),
 			Self::Array {
 				ty, length
 			} => format!("[{ty}; {length}]"),
+			Self::ConstArray { ty, .. } => format!("[{ty}; const]"),
+			Self::Slice(ty) => format!("[{ty}]"),
+			Self::Dyn(interface) => format!("dyn {interface}"),
+			// a single element needs the comma to not be read as parenthesis around a type
+			Self::Tuple(elements) if elements.len() == 1 => format!("({},)", elements[0]),
+			Self::Tuple(elements) => format!("({})", elements.iter().map(Self::name).collect::<Vec<_>>().join(", ")),
 			Self::Custom { name, template_args } => {
 				let mut string = name.0.clone();
 
@@ -140,6 +206,31 @@ impl Type {
 		}
 	}
 
+	pub const fn is_integer(&self) -> bool {
+		matches!(self, Self::I64 | Self::I32 | Self::I8 | Self::U64 | Self::U32 | Self::U8 | Self::USize)
+	}
+
+	pub const fn is_float(&self) -> bool {
+		matches!(self, Self::F32 | Self::F64)
+	}
+
+	pub const fn is_numeric(&self) -> bool {
+		self.is_integer() || self.is_float()
+	}
+
+	/// If any part of this type still has to be inferred
+	pub fn contains_infer(&self) -> bool {
+		match self {
+			Self::Infer => true,
+			Self::Pointer(ty) | Self::Nullable(ty) | Self::Const(ty) | Self::Array { ty, .. } | Self::ConstArray { ty, .. }
+			| Self::Slice(ty) => ty.contains_infer(),
+			Self::Custom { template_args: types, .. } | Self::Tuple(types) => types.iter().any(Self::contains_infer),
+			Self::Function(signature) => signature.get_return_type().contains_infer()
+				|| signature.get_args().iter().any(|x| x.get_type().contains_infer()),
+			_ => false
+		}
+	}
+
 	const fn is_pointer_type(&self) -> bool {
 		matches!(self, &Self::Pointer(_))
 	}
@@ -151,7 +242,7 @@ impl Type {
 		}
 	}
 
-	fn value_under_cost(&self) -> Self {
+	pub fn value_under_cost(&self) -> Self {
 		match self {
 			Self::Const(ty) => ty.value_under_cost(),
 			ty => ty.clone()
@@ -172,6 +263,9 @@ impl From<Identifier> for Type {
 			"f64" => Self::F64,
 			"unit" => Self::Unit,
 			"usize" => Self::USize,
+			"bool" => Self::Bool,
+			"char" => Self::Char,
+			"_" => Self::Infer,
 			name => Self::Custom {
 				name: name.to_string().into(),
 				template_args: vec![],
@@ -1,19 +1,34 @@
-use crate::parser::ast::TopLevelStatement;
+use crate::parser::ast::{Spanned, TopLevelStatement};
 use crate::parser::ast::variable::Identifier;
-use crate::parser::{Error};
+use crate::parser::{Diagnostic, Error, Parsed};
 use crate::parser::Error::UnexpectedToken;
-use crate::parser::pass::top_level;
-use crate::parser::token::{Token, TracedToken, TracedTokenList};
+use crate::parser::pass::{attribute, top_level};
+use crate::parser::token::{FilePos, Keyword, Operator, Parenthetical, Token, Trace, TracedToken, TracedTokenList};
 use crate::parser::Result;
 
 #[derive(Debug)]
 pub struct TokenStream {
 	tokens: TracedTokenList,
 	pos: usize,
+	diagnostics: Vec<Diagnostic>,
+}
+
+/// Position in a [`TokenStream`] to return to if a speculative parse does not work out
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+	pos: usize,
+	diagnostics: usize,
 }
 
 pub const TOP_LEVEL_PASSES: &[PassAttempt<TopLevelStatement>] = &[
 	top_level::function_pass,
+	top_level::test_pass,
+	top_level::type_alias_pass,
+	top_level::const_pass,
+	top_level::impl_pass,
+	top_level::interface_pass,
+	top_level::import_pass,
+	top_level::use_pass,
 ];
 
 pub type Pass<T = ()> = fn(ctx: &mut TokenStream) -> Result<T>;
@@ -21,9 +36,25 @@ pub type PassAttempt<T> = Pass<Option<T>>;
 
 impl From<TracedTokenList> for TokenStream {
 	fn from(tokens: TracedTokenList) -> Self {
+		let mut tokens = tokens;
+
+		// the stream always ends with an EOF so there is a token to look at at every position
+		if tokens.last().map(|x| &x.token) != Some(&Token::EOF) {
+			let end = tokens.last().map_or_else(FilePos::beginning, |x| x.trace.end_pos);
+			tokens.push(TracedToken {
+				token: Token::EOF,
+				trace: Trace {
+					source_file: tokens.last().map(|x| x.trace.source_file.clone()).unwrap_or_default(),
+					begin_pos: end,
+					end_pos: end,
+				},
+			});
+		}
+
 		Self {
 			tokens,
 			pos: 0,
+			diagnostics: vec![],
 		}
 	}
 }
@@ -38,25 +69,110 @@ impl TokenStream {
 		Ok(None)
 	}
 
-	pub fn parse(tokens: TracedTokenList) -> Result<Vec<TopLevelStatement>> {
-		let mut parser = Self {
-			tokens,
-			pos: 0,
-		};
-
-		let statements = {
-			let mut statements = vec![];
-			while let Some(statement) = parser.use_passes(TOP_LEVEL_PASSES)? {
-				statements.push(statement);
-			};
-			statements
-		};
-
-		for pass in TOP_LEVEL_PASSES {
-			pass(&mut parser)?;
+	pub fn parse(tokens: TracedTokenList) -> Parsed {
+		let mut parser = Self::from(tokens);
+		let mut statements = vec![];
+
+		while !parser.is_eof() {
+			// top level statements are separated by newlines, which the lexer turns into ';'
+			if parser.try_consume(Operator::SemiColon).is_some() { continue; }
+
+			let begin = parser.pos;
+			let begin_trace = parser.curr_trace().clone();
+
+			let attributes = attribute::consume_attributes(&mut parser);
+			let public = top_level::consume_visibility(&mut parser);
+
+			match parser.use_passes(TOP_LEVEL_PASSES) {
+				Ok(Some(mut statement)) => {
+					attribute::attach(&mut parser, &mut statement, attributes);
+					top_level::apply_visibility(&mut parser, &mut statement, public);
+					statements.push(Spanned::new(statement, parser.trace_from(&begin_trace)));
+				}
+				Ok(None) => {
+					parser.report(Error::ExpectedEndOfFile(parser.curr_token().clone()));
+					parser.synchronize_top_level();
+				}
+				Err(error) => {
+					parser.report(error);
+					parser.synchronize_top_level();
+				}
+			}
+
+			// always make progress so a token nothing can parse does not stall the parser
+			if parser.pos == begin {
+				parser.next();
+			}
+		}
+
+		Parsed {
+			statements,
+			diagnostics: parser.diagnostics,
+		}
+	}
+
+	/// Records an error at the current token, for the caller to then resynchronize and carry on
+	pub fn report(&mut self, error: Error) {
+		let trace = self.curr_trace().clone();
+		self.report_at(error, trace);
+	}
+
+	/// Records an error at an earlier part of the source, for errors only found once it was parsed
+	pub fn report_at(&mut self, error: Error, trace: Trace) {
+		self.diagnostics.push(Diagnostic { error, trace });
+	}
+
+	/// Skips tokens until one that 'stop' accepts, the end of the file, or a closing parenthetical
+	/// belonging to an enclosing construct. Nested parentheticals are skipped over as a whole
+	pub fn skip_until(&mut self, stop: impl Fn(&Token) -> bool) {
+		let mut depth = 0usize;
+
+		while !self.is_eof() {
+			match self.curr_token() {
+				Token::Parenthetical(
+					Parenthetical::NormalClose | Parenthetical::BracketClose | Parenthetical::CurlyClose
+				) if depth == 0 => return,
+				token if depth == 0 && stop(token) => return,
+				Token::Parenthetical(
+					Parenthetical::NormalOpen | Parenthetical::BracketOpen | Parenthetical::CurlyOpen
+				) => depth += 1,
+				Token::Parenthetical(_) => depth -= 1,
+				_ => {}
+			}
+			self.next();
 		}
+	}
 
-		Ok(statements)
+	/// Skips the rest of a statement that failed to parse, up to a ';' or the start of the next
+	/// statement
+	pub fn synchronize_statement(&mut self) {
+		self.skip_until(|token| matches!(token,
+			Token::Operator(Operator::SemiColon) |
+			Token::Keyword(Keyword::Let | Keyword::Var | Keyword::Return | Keyword::Delete)
+		));
+		self.try_consume(Operator::SemiColon);
+	}
+
+	/// Skips the rest of a top level statement that failed to parse, up to the next function, test,
+	/// type alias, constant, impl block or interface, or the attributes and 'pub' in front of one
+	pub fn synchronize_top_level(&mut self) {
+		let stop = |token: &Token| matches!(token,
+			Token::Keyword(Keyword::Function | Keyword::Type | Keyword::Const | Keyword::Impl | Keyword::Interface | Keyword::Pub) | Token::Operator(Operator::At)
+		) || top_level::is_test(token);
+
+		loop {
+			self.skip_until(stop);
+
+			// closing parentheticals without an opener at the top level belong to nothing
+			if self.is_eof() || stop(self.curr_token()) {
+				return;
+			}
+			self.next();
+		}
+	}
+
+	pub const fn position(&self) -> usize {
+		self.pos
 	}
 
 	pub fn is_eof(&self) -> bool {
@@ -64,7 +180,45 @@ impl TokenStream {
 	}
 
 	pub fn curr_token(&self) -> &Token {
-		&self.tokens[self.pos].token
+		self.peek(0)
+	}
+
+	/// Token 'n' tokens after the current one, the EOF at the end of the stream is given for
+	/// anything past it
+	pub fn peek(&self, n: usize) -> &Token {
+		&self.traced(self.pos + n).token
+	}
+
+	pub fn curr_trace(&self) -> &Trace {
+		&self.traced(self.pos).trace
+	}
+
+	fn traced(&self, pos: usize) -> &TracedToken {
+		&self.tokens[pos.min(self.tokens.len() - 1)]
+	}
+
+	/// Saves the current position, for a parse that might have to be undone with [`Self::rewind`]
+	pub const fn checkpoint(&self) -> Checkpoint {
+		Checkpoint {
+			pos: self.pos,
+			diagnostics: self.diagnostics.len(),
+		}
+	}
+
+	/// Returns to a checkpoint, dropping any diagnostics reported since it was made
+	pub fn rewind(&mut self, checkpoint: Checkpoint) {
+		self.pos = checkpoint.pos;
+		self.diagnostics.truncate(checkpoint.diagnostics);
+	}
+
+	/// Trace spanning from the beginning of 'begin' up to the end of the last consumed token
+	pub fn trace_from(&self, begin: &Trace) -> Trace {
+		let end = &self.traced(self.pos.saturating_sub(1)).trace;
+		Trace {
+			source_file: begin.source_file.clone(),
+			begin_pos: begin.begin_pos,
+			end_pos: end.end_pos,
+		}
 	}
 
 	pub fn next(&mut self) -> &Token {
@@ -77,6 +231,14 @@ impl TokenStream {
 	}
 	pub fn not_curr(&self, token: impl Into<Token>) -> bool { !self.is_curr(token) }
 
+	/// Skips the ';' the lexer ends a line with when the next line opens a body, so a body can
+	/// start on a line of its own, eg. `function f() -> i32` followed by a line with '{'
+	pub fn skip_line_break_before_body(&mut self) {
+		if self.is_curr(Operator::SemiColon) && self.peek(1) == &Token::Parenthetical(Parenthetical::CurlyOpen) {
+			self.next();
+		}
+	}
+
 	pub fn try_consume(&mut self, token: impl Into<Token>) -> Option<Token> {
 		if self.is_curr(token) {
 			let tok = Some(self.curr_token().clone());
@@ -93,15 +255,37 @@ impl TokenStream {
 		curr
 	}
 
+	/// Takes the '>' closing a template argument list. A '>>' is split in two, so that nested
+	/// lists such as `Vec<Vec<i32>>` can be closed together
+	pub fn consume_template_close(&mut self) -> Result<()> {
+		if !self.is_curr(Operator::ShiftRight) {
+			return self.consume(Operator::Greater).map(|_| ());
+		}
+
+		let pos = self.pos;
+		if let Some(token) = self.tokens.get_mut(pos) {
+			token.token = Operator::Greater.into();
+			token.trace.begin_pos.column += 1;
+		}
+		Ok(())
+	}
+
+	/// Takes the current token if it is the expected one, otherwise errors without moving on
 	pub fn consume(&mut self, token: impl Into<Token>) -> Result<Token> {
 		let expected = token.into();
-		let given = self.take_curr();
-		if given == expected { Ok(given) } else { Err(Error::ExpectedToken { expected, given }) }
+		if self.is_curr(expected.clone()) {
+			Ok(self.take_curr())
+		} else {
+			Err(Error::ExpectedToken { expected, given: self.curr_token().clone() })
+		}
 	}
 
 	pub fn consume_identifier(&mut self) -> Result<Identifier> {
-		match self.take_curr() {
-			Token::Identifier(ident) => Ok(ident),
+		match self.curr_token().clone() {
+			Token::Identifier(ident) => {
+				self.next();
+				Ok(ident)
+			}
 			token => Err(Error::ExpectedToken {
 				expected: Token::Identifier("".into()),
 				given: token,
@@ -14,15 +14,22 @@ struct Lexer {
 	contents: String,
 	tokens: TracedTokenList,
 	last_token_position: FilePos,
+	/// Parentheticals that have been opened but not yet closed, innermost last
+	open: Vec<Parenthetical>,
+	/// File the contents were read from, recorded in the trace of every token
+	source_file: String,
 }
 
 impl Lexer {
-	fn new(contents: String) -> Self {
+	const fn new(contents: String, source_file: String) -> Self {
 		Self {
 			pos: 0,
 			tokens: vec![],
 			contents,
-			last_token_position: FilePos::beginning(),
+			// columns are counted from 1, see Lexer::position
+			last_token_position: FilePos { row: 0, column: 1 },
+			open: vec![],
+			source_file,
 		}
 	}
 
@@ -47,10 +54,10 @@ impl Lexer {
 		self.curr().unwrap_or(' ')
 	}
 
-	fn push_token(&mut self, token: impl Into<Token>) {
+	fn position(&self) -> FilePos {
 		let contents_to_now = &self.contents[0..self.pos.min(self.contents.len())];
 
-		let end_pos = FilePos {
+		FilePos {
 			row: contents_to_now.chars().filter(|x| *x == '\n').count(),
 			column: contents_to_now.chars().fold(1, |x, c| {
 				match c {
@@ -58,12 +65,16 @@ impl Lexer {
 					_ => x + 1
 				}
 			}),
-		};
+		}
+	}
+
+	fn push_token(&mut self, token: impl Into<Token>) {
+		let end_pos = self.position();
 
 		self.tokens.push(TracedToken {
 			token: token.into(),
 			trace: Trace {
-				source_file: "".to_string(),
+				source_file: self.source_file.clone(),
 				begin_pos: self.last_token_position,
 				end_pos,
 			},
@@ -79,6 +90,7 @@ impl Lexer {
 		while !self.is_eof() {
 			self.read_token()?;
 		}
+		self.push_token(Token::EOF);
 
 		Ok(self.tokens)
 	}
@@ -113,6 +125,11 @@ impl Lexer {
 	fn whitespace(&mut self) -> bool {
 		self.curr().map_or(false, |curr| if curr.is_whitespace() {
 			self.advance();
+			if curr == '\n' && self.newline_ends_statement() {
+				self.push_token(Operator::SemiColon);
+			}
+			// the next token begins after the whitespace
+			self.last_token_position = self.position();
 			true
 		} else {
 			false
@@ -120,18 +137,39 @@ impl Lexer {
 		)
 	}
 
+	/// Automatic semicolon insertion, a newline ends the statement when the line ends with
+	/// something that can end an expression and the newline is not inside of '()' or '[]'.
+	/// Lines that are followed by a closing parenthetical or the end of the file are left alone
+	/// as they are already terminated
+	fn newline_ends_statement(&self) -> bool {
+		let ends_expression = self.tokens.last().is_some_and(|x| matches!(x.token,
+			Token::Identifier(_) |
+			Token::Literal(_) |
+			Token::Parenthetical(Parenthetical::NormalClose | Parenthetical::BracketClose | Parenthetical::CurlyClose)
+		));
+
+		let in_statement_list = matches!(self.open.last(), None | Some(Parenthetical::CurlyOpen));
+
+		let next = self.contents.chars().skip(self.pos).find(|x| !x.is_whitespace());
+		let is_terminated = matches!(next, None | Some(')' | ']' | '}'));
+
+		ends_expression && in_statement_list && !is_terminated
+	}
+
 	fn number_literal(&mut self) -> bool {
 		if !self.curr_or_whitespace().is_ascii_digit() {
 			return false;
 		}
 
 		let mut num: String = self.curr_or_whitespace().to_string();
+		// numbers after a '.' are tuple fields, so 't.0.1' is read as two fields rather than a float
+		let is_field = self.tokens.last().is_some_and(|x| x.token == Token::Operator(Operator::Dot));
 		let mut has_decimal = false;
 
 		loop {
 			let curr = self.advance_or_whitespace();
 
-			if !(curr == '_' || (!has_decimal && curr == '.') || curr.is_ascii_digit()) {
+			if !(curr == '_' || (!is_field && !has_decimal && curr == '.') || curr.is_ascii_digit()) {
 				break;
 			}
 
@@ -233,6 +271,7 @@ impl Lexer {
 
 			"no_cap" | "true" => Literal::Bool(true).into(),
 			"cap" | "false" => Literal::Bool(false).into(),
+			"null" => Literal::Null.into(),
 
 			// if not a special character, try to find a keyword, if all else fails
 			// add a new identifier token
@@ -244,7 +283,7 @@ impl Lexer {
 	}
 
 	fn parenthetical(&mut self) -> bool {
-		self.push_token(match self.curr_or_whitespace() {
+		let parenthetical = match self.curr_or_whitespace() {
 			'(' => Parenthetical::NormalOpen,
 			')' => Parenthetical::NormalClose,
 			'[' => Parenthetical::BracketOpen,
@@ -252,13 +291,21 @@ impl Lexer {
 			'{' => Parenthetical::CurlyOpen,
 			'}' => Parenthetical::CurlyClose,
 			_ => return false
-		});
+		};
+
+		if parenthetical.is_opener() {
+			self.open.push(parenthetical);
+		} else {
+			self.open.pop();
+		}
+
 		self.advance();
+		self.push_token(parenthetical);
 		true
 	}
 
 	fn operator_simple(&mut self) -> bool {
-		self.push_token(match self.curr_or_whitespace() {
+		let operator = match self.curr_or_whitespace() {
 			'+' => Operator::Add,
 			'-' => Operator::Minus,
 			'*' => Operator::Star,
@@ -272,9 +319,12 @@ impl Lexer {
 			'<' => Operator::Less,
 			'=' => Operator::Assignment,
 			'&' => Operator::Reference,
+			'@' => Operator::At,
+			'?' => Operator::Question,
 			_ => return false
-		});
+		};
 		self.advance();
+		self.push_token(operator);
 		true
 	}
 
@@ -283,25 +333,32 @@ impl Lexer {
 
 		if slice.len() != 2 { return false; }
 
-		self.push_token(match slice {
+		let operator = match slice {
 			"==" => Operator::Equals,
 			"!=" => Operator::NotEquals,
 			">=" => Operator::GreaterOrEquals,
 			"<=" => Operator::LessOrEquals,
 			"->" => Operator::ThinArrow,
 			"=>" => Operator::Arrow,
+			"::" => Operator::PathSeparator,
 			"<<" => Operator::ShiftLeft,
 			">>" => Operator::ShiftRight,
 			_ => return false
-		});
+		};
 
 		self.advance();
 		self.advance();
+		self.push_token(operator);
 
 		true
 	}
 }
 
 pub fn tokenize(contents: String) -> Result<TracedTokenList> {
-	Lexer::new(contents).tokenize()
+	tokenize_file(contents, String::new())
+}
+
+/// Tokenizes the contents of a file, with the traces of the tokens pointing into that file
+pub fn tokenize_file(contents: String, source_file: String) -> Result<TracedTokenList> {
+	Lexer::new(contents, source_file).tokenize()
 }
\ No newline at end of file
@@ -1,5 +1,9 @@
-use crate::parser::ast::TopLevelStatement;
-use crate::parser::token::{Token, TracedToken, TracedTokenList};
+use crate::parser::ast::{Spanned, TopLevelStatement};
+use crate::parser::ast::attribute::Target;
+use crate::parser::ast::variable::{Identifier, Type};
+use std::fmt;
+use std::fmt::Formatter;
+use crate::parser::token::{Token, Trace, TracedToken, TracedTokenList};
 
 pub mod token;
 pub mod ast;
@@ -28,8 +32,36 @@ pub enum Error {
 	#[error("Unexpected token {0:#?}")]
 	UnexpectedToken(Token),
 
+	#[error("Expected end of file, given {0:?}")]
+	ExpectedEndOfFile(Token),
+
 	#[error("Cannot have an array of size {0}")]
 	InvalidArraySize(i64),
+
+	#[error("Variable '{0}' needs either a type annotation or an initial value")]
+	UntypedUninitialisedVariable(Identifier),
+
+	#[error("Unknown attribute '@{0}'")]
+	UnknownAttribute(Identifier),
+
+	#[error("Attribute '@{attribute}' takes {expected} argument(s), given {given}")]
+	AttributeArguments {
+		attribute: Identifier,
+		expected: usize,
+		given: usize,
+	},
+
+	#[error("Methods can only be implemented for named types, given {0}")]
+	ImplTarget(Type),
+
+	#[error("'pub' cannot be used on {0}")]
+	MisplacedVisibility(Target),
+
+	#[error("Attribute '@{attribute}' cannot be used on {target}")]
+	MisplacedAttribute {
+		attribute: &'static str,
+		target: Target,
+	},
 }
 
 impl Error {
@@ -70,6 +102,40 @@ pub enum LexerError {
 	LongCharacterLiteral(String),
 }
 
-pub fn parse(contents: String) -> Result<Vec<TopLevelStatement>> {
-	context::TokenStream::parse(lexer::tokenize(contents)?)
+/// Error found while parsing along with where it was found
+#[derive(Debug)]
+pub struct Diagnostic {
+	pub error: Error,
+	pub trace: Trace,
+}
+
+impl fmt::Display for Diagnostic {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{}: {}", self.trace, self.error)
+	}
+}
+
+/// Result of parsing a file, the parser recovers from errors so the statements are still
+/// available when there are diagnostics, with the parts that failed replaced by error nodes
+#[derive(Debug)]
+pub struct Parsed {
+	pub statements: Vec<Spanned<TopLevelStatement>>,
+	pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Parsed {
+	/// Statements of the file, only if it parsed without any errors
+	pub fn into_result(self) -> std::result::Result<Vec<Spanned<TopLevelStatement>>, Vec<Diagnostic>> {
+		if self.diagnostics.is_empty() { Ok(self.statements) } else { Err(self.diagnostics) }
+	}
+}
+
+/// Parses a file, only failing outright if it cannot be tokenized
+pub fn parse(contents: String) -> std::result::Result<Parsed, LexerError> {
+	parse_file(contents, String::new())
+}
+
+/// Same as [`parse`], with traces naming the file the contents were read from
+pub fn parse_file(contents: String, source_file: String) -> std::result::Result<Parsed, LexerError> {
+	Ok(context::TokenStream::parse(lexer::tokenize_file(contents, source_file)?))
 }
\ No newline at end of file
@@ -0,0 +1,122 @@
+use crate::parser::ast::attribute::{Attribute, Target};
+use crate::parser::ast::{Spanned, TopLevelStatement};
+use crate::parser::ast::variable::Type;
+use crate::parser::context::TokenStream;
+use crate::parser::{Error, Result};
+use crate::parser::token::{Keyword, Literal, Operator, Parenthetical, Token};
+
+/// Consumes the attributes in front of a top level statement. Attributes that fail to parse or
+/// are not known are reported and left out, so the statement itself can still be parsed
+pub fn consume_attributes(stream: &mut TokenStream) -> Vec<Spanned<Attribute>> {
+	let mut attributes = vec![];
+
+	while stream.is_curr(Operator::At) {
+		let begin = stream.curr_trace().clone();
+
+		match consume_attribute(stream) {
+			Ok(attribute) => attributes.push(Spanned::new(attribute, stream.trace_from(&begin))),
+			Err(error @ (Error::UnknownAttribute(_) | Error::AttributeArguments { .. })) =>
+				stream.report_at(error, stream.trace_from(&begin)),
+			Err(error) => {
+				stream.report(error);
+				skip_attribute(stream);
+			}
+		}
+
+		// attributes are usually on their own line, which ends in an inserted ';'
+		while stream.try_consume(Operator::SemiColon).is_some() {}
+	}
+
+	attributes
+}
+
+/// Skips the rest of an attribute that failed to parse, including the closing parenthesis of
+/// its arguments
+fn skip_attribute(stream: &mut TokenStream) {
+	let stop = |token: &Token| matches!(token,
+		Token::Operator(Operator::SemiColon | Operator::At)
+		| Token::Keyword(Keyword::Function | Keyword::Const | Keyword::Type | Keyword::Interface | Keyword::Pub)
+	);
+
+	loop {
+		stream.skip_until(stop);
+		if stream.is_eof() || stop(stream.curr_token()) {
+			return;
+		}
+		stream.next();
+	}
+}
+
+fn consume_attribute(stream: &mut TokenStream) -> Result<Attribute> {
+	stream.consume(Operator::At)?;
+	let name = stream.consume_identifier()?;
+
+	let mut arguments = vec![];
+	if stream.try_consume(Parenthetical::NormalOpen).is_some() {
+		while !stream.is_curr(Parenthetical::NormalClose) {
+			match stream.curr_token().clone() {
+				Token::Literal(Literal::String(argument)) => arguments.push(argument),
+				token => return Error::expected_token(token, Literal::String(String::new())).into(),
+			}
+			stream.next();
+
+			if stream.try_consume(Operator::Comma).is_none() { break; }
+		}
+		stream.consume(Parenthetical::NormalClose)?;
+	}
+
+	let expected = match name.0.as_str() {
+		"inline" | "test" | "export" | "packed" => 0,
+		"deprecated" | "extern" => 1,
+		_ => return Error::UnknownAttribute(name).into(),
+	};
+
+	if arguments.len() != expected {
+		return Error::AttributeArguments { attribute: name, expected, given: arguments.len() }.into();
+	}
+
+	let mut arguments = arguments.into_iter();
+	let mut argument = || arguments.next().unwrap_or_default();
+
+	Ok(match name.0.as_str() {
+		"inline" => Attribute::Inline,
+		"test" => Attribute::Test,
+		"export" => Attribute::Export,
+		"packed" => Attribute::Packed,
+		"deprecated" => Attribute::Deprecated(argument()),
+		_ => Attribute::Extern(argument()),
+	})
+}
+
+/// Stores the attributes on the statement they were written in front of, reporting any that do
+/// not belong on it
+pub fn attach(stream: &mut TokenStream, statement: &mut TopLevelStatement, attributes: Vec<Spanned<Attribute>>) {
+	let target = match statement {
+		TopLevelStatement::Function { .. } => Target::Function,
+		TopLevelStatement::GlobalVariable { .. } | TopLevelStatement::Const { .. } => Target::Variable,
+		TopLevelStatement::TypeAlias { ty: Type::Tuple(_), .. } => Target::Tuple,
+		TopLevelStatement::TypeAlias { .. } => Target::TypeAlias,
+		TopLevelStatement::Import(_) => Target::Import,
+		TopLevelStatement::Use(_) => Target::Use,
+		TopLevelStatement::Impl { .. } => Target::Impl,
+		TopLevelStatement::Interface { .. } => Target::Interface,
+	};
+
+	let (valid, misplaced): (Vec<_>, Vec<_>) = attributes.into_iter().partition(|x| x.applies_to(target));
+
+	for attribute in misplaced {
+		stream.report_at(Error::MisplacedAttribute { attribute: attribute.name(), target }, attribute.trace);
+	}
+
+	match statement {
+		// test blocks already carry the '@test' they stand for, which stays after those written
+		TopLevelStatement::Function { attributes, .. } => { attributes.splice(0..0, valid); }
+		TopLevelStatement::GlobalVariable { attributes, .. }
+		| TopLevelStatement::Const { attributes, .. }
+		| TopLevelStatement::TypeAlias { attributes, .. }
+		| TopLevelStatement::Interface { attributes, .. } => *attributes = valid,
+		// no attribute applies to these, so every one was reported above
+		TopLevelStatement::Import(_) | TopLevelStatement::Use(_) | TopLevelStatement::Impl { .. } => {}
+	}
+}
+
@@ -1,40 +1,282 @@
-use crate::parser::ast::variable::{Type, Variable};
+use crate::parser::ast::function::{Function, FunctionSignature};
+use crate::parser::ast::{Expression, Spanned, Statement};
+use crate::parser::ast::pattern::Pattern;
+use crate::parser::ast::variable::{Identifier, Length, Type, Variable};
 use crate::parser::context::TokenStream;
 use crate::parser::Error::UnexpectedToken;
+use crate::parser::pass::expression;
 use crate::parser::{Error, Result};
 use crate::parser::token::{Keyword, Literal, Operator, Parenthetical, Token};
 
+/// Consumes an identifier along with any `::` separated parts after it, eg. `geometry::area`,
+/// which are kept together as one identifier
+pub fn consume_path(ctx: &mut TokenStream) -> Result<Identifier> {
+	let mut path = ctx.consume_identifier()?.0;
+
+	while ctx.try_consume(Operator::PathSeparator).is_some() {
+		path.push_str("::");
+		path.push_str(&consume_member_name(ctx)?.0);
+	}
+
+	Ok(path.into())
+}
+
+/// Consumes the name of a function of an impl block, which unlike other names can be 'new', as
+/// it is only ever written after `function` or `::` where it cannot start an allocation
+pub fn consume_member_name(ctx: &mut TokenStream) -> Result<Identifier> {
+	if ctx.try_consume(Keyword::New).is_some() {
+		return Ok("new".into());
+	}
+
+	ctx.consume_identifier()
+}
+
 pub fn consume_variable(ctx: &mut TokenStream) -> Result<Variable> {
 	let ident = ctx.consume_identifier()?;
 	ctx.consume(Operator::Colon)?;
 	Ok(Variable::new(ident, consume_type(ctx)?))
 }
 
+/// Consumes the name of a variable being declared, unlike function arguments the type annotation
+/// is optional and left as [`Type::Infer`] when missing
+pub fn consume_variable_declaration(ctx: &mut TokenStream) -> Result<Variable> {
+	let ident = ctx.consume_identifier()?;
+
+	let ty = if ctx.try_consume(Operator::Colon).is_some() {
+		consume_type(ctx)?
+	} else {
+		Type::Infer
+	};
+
+	Ok(Variable::new(ident, ty))
+}
+
+/// Consumes everything after the 'function' keyword (and name if there is one) of a function,
+/// eg. the argument list, optional return type and either a scope or a '=>' body.
+/// When the return type is not written the function returns `default_returns`
+pub fn consume_function(ctx: &mut TokenStream, default_returns: Type) -> Result<Function> {
+	let (signature, patterns) = consume_parameters(ctx, default_returns)?;
+	ctx.skip_line_break_before_body();
+
+	let begin = ctx.curr_trace().clone();
+
+	let mut body = if let Some(scope) = expression::scope_pass(ctx)? {
+		Spanned::new(scope, ctx.trace_from(&begin))
+	} else {
+		ctx.consume(Operator::Arrow)?;
+		expression::consume(ctx)?
+	};
+
+	// arguments written as patterns are taken apart before the body runs
+	if !patterns.is_empty() {
+		let trace = body.trace.clone();
+		let mut statements: Vec<_> = patterns.into_iter()
+			.map(|(ident, pattern)| {
+				let trace = pattern.trace.clone();
+				Spanned::new(Statement::Destructure {
+					initialisation: Spanned::new(Expression::ObjectReference(ident), trace.clone()),
+					pattern,
+					otherwise: None,
+				}, trace)
+			})
+			.collect();
+		statements.push(Spanned::new(Statement::Expression(body), trace.clone()));
+		body = Spanned::new(Expression::Scope(statements), trace);
+	}
+
+	Ok(Function::new(signature, Box::new(body)))
+}
+
+/// Consumes the argument list and optional return type of a function. An argument written as
+/// just `self` is short for `self: Self`, for methods of impl blocks and interfaces
+pub fn consume_signature(ctx: &mut TokenStream, default_returns: Type) -> Result<FunctionSignature> {
+	consume_parameters(ctx, default_returns).map(|(signature, _)| signature)
+}
+
+/// Arguments written as patterns, along with the names they are passed as
+type PatternArguments = Vec<(Identifier, Spanned<Pattern>)>;
+
+/// Same as [`consume_signature`], where arguments can also be patterns that have their type
+/// written, eg. `(x, y): (f32, f32)`. Such arguments are given a name that no variable can have,
+/// which is returned along with the pattern
+fn consume_parameters(ctx: &mut TokenStream, default_returns: Type) -> Result<(FunctionSignature, PatternArguments)> {
+	ctx.consume(Parenthetical::NormalOpen)?;
+
+	let mut args = vec![];
+	let mut patterns = vec![];
+
+	while !ctx.is_curr(Parenthetical::NormalClose) {
+		if ctx.is_curr(Token::Identifier("self".into())) && !matches!(ctx.peek(1), Token::Operator(Operator::Colon)) {
+			ctx.next();
+			args.push(Variable::new("self", Type::custom("Self")));
+		} else if ctx.is_curr(Parenthetical::NormalOpen) || ctx.is_curr(Parenthetical::BracketOpen) {
+			let pattern = consume_pattern(ctx)?;
+			ctx.consume(Operator::Colon)?;
+
+			let ident = Identifier::from(format!("argument::{}", args.len()));
+			args.push(Variable::new(ident.clone(), consume_type(ctx)?));
+			patterns.push((ident, pattern));
+		} else {
+			args.push(consume_variable(ctx)?);
+		}
+
+		if ctx.is_curr(Operator::Comma) {
+			ctx.next();
+			continue;
+		}
+		break;
+	}
+	ctx.consume(Parenthetical::NormalClose)?;
+
+	let returns = if ctx.is_curr(Operator::ThinArrow) {
+		ctx.next();
+		consume_type(ctx)?
+	} else {
+		default_returns
+	};
+
+	Ok((FunctionSignature::new_named(args, returns), patterns))
+}
+
+/// Consumes a pattern, eg. `(q, [first, _, ..])`, in which names can have their type written as
+/// in a declaration, eg. `(q, r: i32)`
+pub fn consume_pattern(ctx: &mut TokenStream) -> Result<Spanned<Pattern>> {
+	let begin = ctx.curr_trace().clone();
+
+	let pattern = match ctx.curr_token().clone() {
+		Token::Parenthetical(Parenthetical::NormalOpen) => {
+			ctx.next();
+
+			let mut elements = vec![];
+			let mut trailing_comma = false;
+			while !ctx.is_curr(Parenthetical::NormalClose) {
+				elements.push(consume_pattern(ctx)?);
+				trailing_comma = ctx.try_consume(Operator::Comma).is_some();
+				if !trailing_comma { break; }
+			}
+			ctx.consume(Parenthetical::NormalClose)?;
+
+			// as with expressions, a single pattern in parentheses is only grouped
+			if elements.len() == 1 && !trailing_comma {
+				return Ok(elements.remove(0));
+			}
+			Pattern::Tuple(elements)
+		}
+		Token::Parenthetical(Parenthetical::BracketOpen) => {
+			ctx.next();
+
+			let mut elements = vec![];
+			let mut rest = false;
+			while !ctx.is_curr(Parenthetical::BracketClose) {
+				// the rest can only be left out at the end
+				if ctx.try_consume(Operator::Dot).is_some() {
+					ctx.consume(Operator::Dot)?;
+					rest = true;
+					break;
+				}
+
+				elements.push(consume_pattern(ctx)?);
+				if ctx.try_consume(Operator::Comma).is_none() { break; }
+			}
+			ctx.consume(Parenthetical::BracketClose)?;
+
+			Pattern::Array { elements, rest }
+		}
+		Token::Literal(literal) => {
+			ctx.next();
+			Pattern::Literal(literal)
+		}
+		Token::Identifier(ident) if ident.0 == "_" => {
+			ctx.next();
+			Pattern::Wildcard
+		}
+		_ => Pattern::Binding(consume_variable_declaration(ctx)?),
+	};
+
+	Ok(Spanned::new(pattern, ctx.trace_from(&begin)))
+}
+
 pub fn consume_type(ctx: &mut TokenStream) -> Result<Type> {
-	match ctx.take_curr() {
-		Token::Keyword(Keyword::Const) => Ok(consume_type(ctx)?.as_const()),
-		Token::Operator(Operator::Star) => Ok(consume_type(ctx)?.as_pointer()),
+	// tokens are only taken once they are known to start a type, so errors point at the culprit
+	match ctx.curr_token().clone() {
+		Token::Keyword(Keyword::Const) => {
+			ctx.next();
+			Ok(consume_type(ctx)?.as_const())
+		}
+		Token::Operator(Operator::Star) => {
+			ctx.next();
+			Ok(consume_type(ctx)?.as_pointer())
+		}
+		Token::Operator(Operator::Question) => {
+			ctx.next();
+			ctx.consume(Operator::Star)?;
+			Ok(consume_type(ctx)?.as_nullable())
+		}
+		Token::Keyword(Keyword::Dyn) => {
+			ctx.next();
+			Ok(Type::Dyn(consume_path(ctx)?))
+		}
 
 		Token::Parenthetical(Parenthetical::BracketOpen) => {
+			ctx.next();
 			let ty = consume_type(ctx)?;
-			ctx.consume(Operator::SemiColon);
+			if ctx.try_consume(Parenthetical::BracketClose).is_some() {
+				return Ok(ty.as_slice());
+			}
+			ctx.consume(Operator::SemiColon)?;
+
+			// lengths other than a literal are evaluated once the constants they can use are known
+			let length = expression::consume(ctx)?;
+			ctx.consume(Parenthetical::BracketClose)?;
 
-			let length = match ctx.take_curr() {
-				Token::Literal(Literal::Integer(size)) => size,
-				given => return Error::expected_token(given, Literal::Integer(0)).into()
-			};
+			Ok(match length.node {
+				Expression::Literal(Literal::Integer(length)) =>
+					ty.as_array(usize::try_from(length).map_err(|_| Error::InvalidArraySize(length))?),
+				_ => Type::ConstArray { ty: Box::new(ty), length: Length(Box::new(length)) },
+			})
+		}
 
-			let length = usize::try_from(length).map_err(|_| Error::InvalidArraySize(length))?;
+		// a single type in parenthesis is only grouped, a tuple of one is written with a trailing comma
+		Token::Parenthetical(Parenthetical::NormalOpen) => {
+			ctx.next();
 
-			ctx.consume(Parenthetical::BracketClose)?;
-			Ok(Type::Array {
-				ty: Box::new(ty),
-				length,
+			let mut elements = vec![];
+			let mut grouped = true;
+			while !ctx.is_curr(Parenthetical::NormalClose) {
+				elements.push(consume_type(ctx)?);
+				if ctx.try_consume(Operator::Comma).is_none() { break; }
+				grouped = false;
+			}
+
+			ctx.consume(Parenthetical::NormalClose)?;
+
+			Ok(match elements.len() {
+				0 => Type::Unit,
+				1 if grouped => elements.remove(0),
+				_ => Type::Tuple(elements),
 			})
 		}
 
-		Token::Identifier(ident) => {
-			let mut ty = Type::from(ident);
+		// written the same as the signature of a lambda, eg. `function (f32, u8) => unit`
+		Token::Keyword(Keyword::Function) => {
+			ctx.next();
+			ctx.consume(Parenthetical::NormalOpen)?;
+
+			let mut args = vec![];
+			while !ctx.is_curr(Parenthetical::NormalClose) {
+				args.push(consume_type(ctx)?);
+				if ctx.try_consume(Operator::Comma).is_none() { break; }
+			}
+
+			ctx.consume(Parenthetical::NormalClose)?;
+			ctx.consume(Operator::Arrow)?;
+
+			Ok(FunctionSignature::new(args, consume_type(ctx)?).into())
+		}
+
+		Token::Identifier(_) => {
+			// types from other modules are named by their path, eg. `geometry::Mat4`
+			let mut ty = Type::from(consume_path(ctx)?);
 
 			match &mut ty {
 				Type::Custom { name, template_args } => {
@@ -43,10 +285,14 @@ pub fn consume_type(ctx: &mut TokenStream) -> Result<Type> {
 
 						while !ctx.is_curr(Operator::Greater) {
 							template_args.push(consume_type(ctx)?);
-							if ctx.consume(Operator::Comma).is_err() { break; }
+							if ctx.try_consume(Operator::Comma).is_none() { break; }
 						}
 
-						ctx.consume(Operator::Greater)?;
+						ctx.consume_template_close()?;
+
+						if template_args.is_empty() {
+							template_args.push(Type::Infer);
+						}
 					}
 					Ok(ty)
 				}
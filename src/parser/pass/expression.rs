@@ -1,59 +1,93 @@
 use const_panic::fmt::IsLast::No;
 use crate::parser;
-use crate::parser::ast::{Expression, operator};
+use crate::parser::ast::{Expression, operator, Spanned, Statement};
+use crate::parser::ast::operator::Associativity;
+use crate::parser::ast::variable::Type;
 use crate::parser::{context, Error};
 use crate::parser::context::{TokenStream, PassAttempt};
 use crate::parser::Error::UnexpectedToken;
-use crate::parser::pass::{expression, statement};
-use crate::parser::token::{Literal, Operator, Parenthetical, Token};
-
-
-const BINARY_ORDER_OF_OPERATIONS: &[&[operator::Binary]] = {
-	use operator::Binary as B;
-	&[
-		&[B::Dot],
-		&[B::Multiply, B::Divide, B::Mod],
-		&[B::Add, B::Minus],
-		&[B::ShiftLeft, B::ShiftRight],
-		&[B::Less, B::LessOrEquals, B::Greater, B::GreaterOrEquals],
-		&[B::Equals, B::NotEquals],
-		&[B::BitAnd],
-		&[B::BitXor],
-		&[B::BitOr],
-		&[B::And],
-		&[B::Or],
-		&[B::Assignment],
-	]
-};
+use crate::parser::pass::{category, expression, statement};
+use crate::parser::token::{Keyword, Literal, Operator, Parenthetical, Token};
+
 
 const UNARY_PASSES: &[PassAttempt<Expression>] = &[
+	lambda_pass,
+	if_pass,
+	new_pass,
 	identifier_reference_pass,
 	scope_pass,
 	literal_pass,
+	array_pass,
 	unary_pass,
 	parenthesis_pass,
 ];
 
-const POSTFIX_PASS: &[PassAttempt<Expression>] = &[];
-
-type ExpressionResult = parser::Result<Expression>;
+type ExpressionResult = parser::Result<Spanned<Expression>>;
 type ExpressionPassResult = parser::Result<Option<Expression>>;
 
 
 /// Consumes a unary 'atom', eg either an expression that cannot be broken down further -
 /// or a composite such as a function call, scope, or control flow block
 pub fn consume_atom(stream: &mut TokenStream) -> ExpressionResult {
-	stream.use_passes(UNARY_PASSES)?.map_or_else(
+	let begin = stream.curr_trace().clone();
+
+	let expr = stream.use_passes(UNARY_PASSES)?.map_or_else(
 		|| Error::unexpected_token(stream.curr_token().clone()).into(),
 		Ok,
-	)
+	)?;
+
+	Ok(Spanned::new(expr, stream.trace_from(&begin)))
+}
+
+/// Consumes a function call or array access trailing an expression
+fn consume_postfix(stream: &mut TokenStream, expr: Spanned<Expression>) -> ExpressionResult {
+	let begin = expr.trace.clone();
+
+	let postfix = match stream.take_curr() {
+		Token::Parenthetical(Parenthetical::NormalOpen) => Expression::FunctionCall {
+			function: Box::new(expr),
+			arguments: consume_comma_separated(stream, Parenthetical::NormalClose)?,
+		},
+		Token::Parenthetical(Parenthetical::BracketOpen) => {
+			let index = consume(stream)?;
+			stream.consume(Parenthetical::BracketClose)?;
+			Expression::ArrayAccess {
+				expr: Box::new(expr),
+				index: Box::new(index),
+			}
+		}
+		token => return Error::unexpected_token(token).into()
+	};
+
+	Ok(Spanned::new(postfix, stream.trace_from(&begin)))
+}
+
+/// Consumes expressions separated by commas up to and including the closing parenthetical
+fn consume_comma_separated(stream: &mut TokenStream, closing: Parenthetical) -> parser::Result<Vec<Spanned<Expression>>> {
+	let mut expressions = vec![];
+
+	while !stream.is_curr(closing) && !stream.is_eof() {
+		let begin = stream.curr_trace().clone();
+
+		expressions.push(consume(stream).unwrap_or_else(|error| {
+			stream.report(error);
+			stream.skip_until(|token| token == &Token::Operator(Operator::Comma));
+			Spanned::new(Expression::Error, stream.trace_from(&begin))
+		}));
+
+		if stream.try_consume(Operator::Comma).is_none() { break; }
+	}
+	stream.consume(closing)?;
+
+	Ok(expressions)
 }
 
 pub fn unary_pass(stream: &mut TokenStream) -> ExpressionPassResult {
-	Ok(if let Ok(operator) = stream.curr_token().try_into() {
+	Ok(if let Ok(operator) = operator::Unary::try_from(stream.curr_token()) {
+		stream.next();
 		Some(Expression::Unary {
 			operator,
-			expr: Box::new(consume(stream)?),
+			expr: Box::new(consume_binary(stream, operator.precedence())?),
 		})
 	} else {
 		None
@@ -71,6 +105,12 @@ pub fn literal_pass(stream: &mut TokenStream) -> ExpressionPassResult {
 	})
 }
 
+pub fn array_pass(stream: &mut TokenStream) -> ExpressionPassResult {
+	if stream.try_consume(Parenthetical::BracketOpen).is_none() { return Ok(None); }
+
+	Ok(Some(Expression::Array(consume_comma_separated(stream, Parenthetical::BracketClose)?)))
+}
+
 pub fn parenthesis_pass(stream: &mut TokenStream) -> ExpressionPassResult {
 	if stream.try_consume(Parenthetical::NormalOpen).is_none() { return Ok(None); }
 
@@ -78,61 +118,173 @@ pub fn parenthesis_pass(stream: &mut TokenStream) -> ExpressionPassResult {
 		return Ok(Some(Literal::Unit.into()));
 	}
 
+	// the parenthesis are not part of the tree, but are part of the trace of the expression
 	let expr = consume(stream)?;
+
+	// a comma makes it a tuple, even when nothing follows it
+	if stream.try_consume(Operator::Comma).is_some() {
+		let mut elements = vec![expr];
+		elements.extend(consume_comma_separated(stream, Parenthetical::NormalClose)?);
+		return Ok(Some(Expression::Tuple(elements)));
+	}
+
 	stream.consume(Parenthetical::NormalClose)?;
 
-	Ok(Some(expr))
+	Ok(Some(expr.node))
 }
 
+/// Scope of statements, its value is the value of the last statement whether or not it has a
+/// terminator, as otherwise the value of a scope would depend on how its lines are laid out
 pub fn scope_pass(stream: &mut TokenStream) -> ExpressionPassResult {
 	if stream.try_consume(Parenthetical::CurlyOpen).is_none() { return Ok(None); }
 
 	let mut body = vec![];
 	while stream.try_consume(Parenthetical::CurlyClose).is_none() {
-		body.push(statement::consume(stream)?);
+		if stream.is_eof() {
+			return Error::expected_token(Token::EOF, Parenthetical::CurlyClose).into();
+		}
+
+		// empty statement
+		if stream.try_consume(Operator::SemiColon).is_some() { continue; }
+
+		let begin = stream.position();
+		let begin_trace = stream.curr_trace().clone();
+
+		body.push(statement::consume(stream).unwrap_or_else(|error| {
+			stream.report(error);
+			stream.synchronize_statement();
+			Spanned::new(Statement::Error, stream.trace_from(&begin_trace))
+		}));
+
+		// a stray ')' or ']' stops resynchronizing without being consumed
+		if stream.position() == begin {
+			stream.next();
+		}
 	}
 
 	Ok(Expression::Scope(body).into())
 }
 
+/// Conditional, eg. 'if a < b { a } else { b }'
+pub fn if_pass(stream: &mut TokenStream) -> ExpressionPassResult {
+	if stream.try_consume(Keyword::If).is_none() { return Ok(None); }
+
+	let condition = consume(stream)?;
+	stream.skip_line_break_before_body();
+	let then = consume_branch(stream, scope_pass)?;
+
+	// the lexer ends the statement after a '}' at the end of a line, even when 'else' follows
+	if stream.is_curr(Operator::SemiColon) && stream.peek(1) == &Token::Keyword(Keyword::Else) {
+		stream.next();
+	}
+
+	let otherwise = if stream.try_consume(Keyword::Else).is_some() {
+		let pass = if stream.is_curr(Keyword::If) { if_pass } else { scope_pass };
+		Some(Box::new(consume_branch(stream, pass)?))
+	} else {
+		None
+	};
+
+	Ok(Some(Expression::If {
+		condition: Box::new(condition),
+		then: Box::new(then),
+		otherwise,
+	}))
+}
+
+fn consume_branch(stream: &mut TokenStream, pass: PassAttempt<Expression>) -> ExpressionResult {
+	let begin = stream.curr_trace().clone();
+
+	let Some(branch) = pass(stream)? else {
+		return Error::expected_token(stream.curr_token().clone(), Parenthetical::CurlyOpen).into();
+	};
+
+	Ok(Spanned::new(branch, stream.trace_from(&begin)))
+}
+
+/// Anonymous function in expression position, eg. 'function (x: i32) -> i32 => x * 2', unlike
+/// named functions a missing return type is inferred from the body
+pub fn lambda_pass(stream: &mut TokenStream) -> ExpressionPassResult {
+	if stream.try_consume(Keyword::Function).is_none() { return Ok(None); }
+
+	Ok(Some(category::consume_function(stream, Type::Infer)?.into()))
+}
+
+/// Heap allocation, either 'new T(args)' or 'new [T; length]'
+pub fn new_pass(stream: &mut TokenStream) -> ExpressionPassResult {
+	if !stream.is_curr(Keyword::New) { return Ok(None); }
+
+	stream.next();
+
+	if stream.try_consume(Parenthetical::BracketOpen).is_some() {
+		let ty = category::consume_type(stream)?;
+		stream.consume(Operator::SemiColon)?;
+		let length = consume(stream)?;
+		stream.consume(Parenthetical::BracketClose)?;
+
+		return Ok(Some(Expression::NewArray {
+			ty,
+			length: Box::new(length),
+		}));
+	}
+
+	let ty = category::consume_type(stream)?;
+	stream.consume(Parenthetical::NormalOpen)?;
+	let arguments = consume_comma_separated(stream, Parenthetical::NormalClose)?;
+
+	Ok(Some(Expression::New {
+		ty,
+		arguments,
+	}))
+}
+
 pub fn identifier_reference_pass(stream: &mut TokenStream) -> ExpressionPassResult {
 	match stream.curr_token() {
-		Token::Identifier(_) => stream.consume_identifier().map(|x| Some(x.into())),
+		Token::Identifier(_) => category::consume_path(stream).map(|x| Some(x.into())),
 		_ => Ok(None)
 	}
 }
 
-/// Consumes a binary expression from a token screen
-/// (along with a number indicating the order of operation level)
-pub fn consume_binary(stream: &mut TokenStream, operation_level: usize) -> ExpressionResult {
-	if operation_level == 0 {
-		return consume_atom(stream);
-	}
+/// Consumes a binary expression from a token stream, only taking operators that bind at least
+/// as tightly as the given precedence (see [`operator::Binary::precedence`])
+pub fn consume_binary(stream: &mut TokenStream, min_precedence: u8) -> ExpressionResult {
+	let mut lhs = consume_atom(stream)?;
 
-	let operators = BINARY_ORDER_OF_OPERATIONS[operation_level - 1];
+	loop {
+		let is_postfix = stream.is_curr(Parenthetical::NormalOpen) || stream.is_curr(Parenthetical::BracketOpen);
+		if is_postfix && operator::POSTFIX_PRECEDENCE >= min_precedence {
+			lhs = consume_postfix(stream, lhs)?;
+			continue;
+		}
 
-	let mut lhs = consume_binary(stream, operation_level - 1)?;
+		let Token::Operator(op) = stream.curr_token() else { break; };
+		let Ok(op) = operator::Binary::try_from(*op) else { break; };
 
-	while let Token::Operator(op) = stream.curr_token() {
-		if let Ok(op) = (*op).try_into() {
-			if operators.contains(&op) {
-				stream.next();
-				lhs = Expression::Binary {
-					lhs: lhs.into(),
-					operator: op,
-					rhs: consume_binary(stream, operation_level - 1)?.into(),
-				};
-				continue;
-			}
+		if op.precedence() < min_precedence {
+			break;
 		}
-		break;
+		stream.next();
+
+		let rhs_precedence = match op.associativity() {
+			Associativity::Left => op.precedence() + 1,
+			Associativity::Right => op.precedence(),
+		};
+
+		let rhs = consume_binary(stream, rhs_precedence)?;
+		let trace = lhs.trace.to(&rhs.trace);
+
+		lhs = Spanned::new(Expression::Binary {
+			lhs: Box::new(lhs),
+			operator: op,
+			rhs: Box::new(rhs),
+			ty: Type::Infer,
+		}, trace);
 	}
 
 	Ok(lhs)
 }
 
-
 /// Consumes an expression from a token stream
 pub fn consume(stream: &mut TokenStream) -> ExpressionResult {
-	consume_binary(stream, BINARY_ORDER_OF_OPERATIONS.len())
+	consume_binary(stream, 0)
 }
\ No newline at end of file
@@ -1,6 +1,7 @@
 pub mod top_level;
 pub mod category;
 pub mod expression;
+pub mod attribute;
 
 #[cfg(test)]
 mod test;
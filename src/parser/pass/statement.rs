@@ -1,10 +1,10 @@
 use crate::parser;
-use crate::parser::ast::{Statement, TopLevelStatement};
-use crate::parser::ast::variable::Variable;
+use crate::parser::ast::{Expression, Spanned, Statement, TopLevelStatement};
+use crate::parser::ast::variable::{Type, Variable};
 use crate::parser::context::{PassAttempt, TokenStream};
 use crate::parser::Error;
 use crate::parser::pass::{category, expression};
-use crate::parser::token::{Keyword, Operator};
+use crate::parser::token::{Keyword, Operator, Parenthetical};
 
 type PassResult = parser::Result<Option<Statement>>;
 
@@ -12,6 +12,9 @@ const STATEMENT_PASSES: &[PassAttempt<Statement>] = &[
 	let_var_pass,
 	var_pass,
 	return_pass,
+	delete_pass,
+	defer_pass,
+	assert_pass,
 	expression_pass
 ];
 
@@ -20,10 +23,14 @@ fn let_var_pass(stream: &mut TokenStream) -> PassResult {
 		return Ok(None);
 	}
 
-	let var = category::consume_variable(stream)?;
+	if stream.is_curr(Parenthetical::NormalOpen) || stream.is_curr(Parenthetical::BracketOpen) {
+		return consume_destructure(stream, true).map(Some);
+	}
+
+	let var = category::consume_variable_declaration(stream)?;
 	let var = Variable::new(var.get_name().clone(), var.get_type().as_const());
 
-	stream.consume(Operator::Assignment);
+	stream.consume(Operator::Assignment)?;
 
 	let initialisation = Some(expression::consume(stream)?);
 
@@ -36,7 +43,11 @@ fn let_var_pass(stream: &mut TokenStream) -> PassResult {
 fn var_pass(stream: &mut TokenStream) -> PassResult {
 	if stream.try_consume(Keyword::Var).is_none() { return Ok(None); }
 
-	let var = category::consume_variable(stream)?;
+	if stream.is_curr(Parenthetical::NormalOpen) || stream.is_curr(Parenthetical::BracketOpen) {
+		return consume_destructure(stream, false).map(Some);
+	}
+
+	let var = category::consume_variable_declaration(stream)?;
 
 	let initialisation = if stream.try_consume(Operator::Assignment).is_some() {
 		Some(expression::consume(stream)?)
@@ -44,6 +55,10 @@ fn var_pass(stream: &mut TokenStream) -> PassResult {
 		None
 	};
 
+	if initialisation.is_none() && var.get_type() == &Type::Infer {
+		return Error::UntypedUninitialisedVariable(var.get_name().clone()).into();
+	}
+
 
 	Ok(Statement::Declaration {
 		var,
@@ -51,6 +66,32 @@ fn var_pass(stream: &mut TokenStream) -> PassResult {
 	}.into())
 }
 
+/// Consumes the pattern a value is taken apart into, the value itself and the 'else' for when it
+/// does not match if there is one, eg. `(q, r) = divmod(a, b)` or `[0, x, ..] = xs else { return }`
+fn consume_destructure(stream: &mut TokenStream, constant: bool) -> parser::Result<Statement> {
+	let mut pattern = category::consume_pattern(stream)?;
+	if constant {
+		for var in pattern.node.bindings_mut() {
+			*var = Variable::new(var.get_name().clone(), var.get_type().as_const());
+		}
+	}
+
+	stream.consume(Operator::Assignment)?;
+	let initialisation = expression::consume(stream)?;
+
+	let otherwise = if stream.try_consume(Keyword::Else).is_some() {
+		let begin = stream.curr_trace().clone();
+		let Some(scope) = expression::scope_pass(stream)? else {
+			return Error::expected_token(stream.curr_token().clone(), Parenthetical::CurlyOpen).into();
+		};
+		Some(Box::new(Spanned::new(scope, stream.trace_from(&begin))))
+	} else {
+		None
+	};
+
+	Ok(Statement::Destructure { pattern, initialisation, otherwise })
+}
+
 fn return_pass(stream: &mut TokenStream) -> PassResult {
 	if stream.try_consume(Keyword::Return).is_none() {
 		return Ok(None);
@@ -58,13 +99,59 @@ fn return_pass(stream: &mut TokenStream) -> PassResult {
 	Ok(Some(Statement::Return(expression::consume(stream)?)))
 }
 
+fn delete_pass(stream: &mut TokenStream) -> PassResult {
+	if stream.try_consume(Keyword::Delete).is_none() {
+		return Ok(None);
+	}
+	Ok(Some(Statement::Delete(expression::consume(stream)?)))
+}
+
+/// Defers an expression, or a 'delete' which is taken as a scope holding just that statement,
+/// eg. `defer delete p` is the same as `defer { delete p }`
+fn defer_pass(stream: &mut TokenStream) -> PassResult {
+	if stream.try_consume(Keyword::Defer).is_none() {
+		return Ok(None);
+	}
+
+	let begin = stream.curr_trace().clone();
+	let Some(delete) = delete_pass(stream)? else {
+		return Ok(Some(Statement::Defer(expression::consume(stream)?)));
+	};
+
+	let scope = Expression::Scope(vec![Spanned::new(delete, stream.trace_from(&begin))]);
+	Ok(Some(Statement::Defer(Spanned::new(scope, stream.trace_from(&begin)))))
+}
+
+fn assert_pass(stream: &mut TokenStream) -> PassResult {
+	if stream.try_consume(Keyword::Assert).is_none() {
+		return Ok(None);
+	}
+	Ok(Some(Statement::Assert(expression::consume(stream)?)))
+}
+
 fn expression_pass(stream: &mut TokenStream) -> PassResult {
 	Ok(Some(expression::consume(stream)?.into()))
 }
 
-pub fn consume(stream: &mut TokenStream) -> parser::Result<Statement> {
-	stream.use_passes(STATEMENT_PASSES)?.map_or_else(
+/// Consumes a statement and its terminator, which is either a ';' (written or inserted by the
+/// lexer at the end of a line), or the '}' closing the scope or the end of the file, which are
+/// left for the caller to take
+pub fn consume(stream: &mut TokenStream) -> parser::Result<Spanned<Statement>> {
+	let begin = stream.curr_trace().clone();
+
+	let statement = stream.use_passes(STATEMENT_PASSES)?.map_or_else(
 		|| Error::unexpected_token(stream.curr_token().clone()).into(),
 		Ok,
-	)
+	)?;
+	let statement = Spanned::new(statement, stream.trace_from(&begin));
+
+	let terminated = stream.try_consume(Operator::SemiColon).is_some()
+		|| stream.is_curr(Parenthetical::CurlyClose)
+		|| stream.is_eof();
+
+	if !terminated {
+		return Error::expected_token(stream.curr_token().clone(), Operator::SemiColon).into();
+	}
+
+	Ok(statement)
 }
\ No newline at end of file
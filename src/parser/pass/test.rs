@@ -1,13 +1,16 @@
 #![allow(clippy::unwrap_used, clippy::needless_raw_string_hashes)]
 
 use crate::parser;
-use crate::parser::ast::variable::{Identifier, Type, Variable};
+use crate::parser::ast::variable::{Identifier, Length, Type, Variable};
 use crate::parser::lexer;
 use crate::parser::pass::{category, expression, statement};
+use proptest::prelude::*;
 use test_case::{test_case, test_matrix};
-use crate::parser::ast::{Expression, Statement, TopLevelStatement};
-use crate::parser::ast::function::{Function, FunctionSignature};
-use crate::parser::ast::operator::Binary;
+use crate::parser::ast::{Expression, Method, Spanned, Statement, TopLevelStatement, Visibility};
+use crate::parser::ast::attribute::Attribute;
+use crate::parser::ast::function::{Function, FunctionSignature, Generic};
+use crate::parser::ast::operator::{Binary, Unary};
+use crate::parser::ast::pattern::Pattern;
 use crate::parser::context::TokenStream;
 use crate::parser::token::Literal;
 
@@ -28,42 +31,73 @@ use crate::parser::token::Literal;
 #[test_case("***i32", & Type::I32.as_pointer().as_pointer().as_pointer())]
 #[test_case("const *i32", & Type::I32.as_pointer().as_const())]
 #[test_case("* const i32", & Type::I32.as_const().as_pointer())]
+#[test_case("Array<>", & Type::template("Array", vec ! [Type::Infer] ))]
+#[test_case("[_; 3]", & Type::Infer.as_array(3))]
+#[test_case("[f32; N]", & Type::ConstArray { ty: Box::new(Type::F32), length: Length(Box::new("N".into())) })]
+#[test_case("*[i32]", & Type::I32.as_slice().as_pointer())]
+#[test_case("*const [[u8; 2]]", & Type::U8.as_array(2).as_slice().as_const().as_pointer())]
+#[test_case("(i32, f32)", & Type::Tuple(vec ! [Type::I32, Type::F32]))]
+#[test_case("(u8,)", & Type::Tuple(vec ! [Type::U8]))]
+#[test_case("(u8)", & Type::U8; "Grouped type")]
+#[test_case("*(function () => i32)", & FunctionSignature::new(vec ! [], Type::I32).as_type().as_pointer())]
+#[test_case("?*i32", & Type::I32.as_nullable())]
+#[test_case("*?*const u8", & Type::U8.as_const().as_nullable().as_pointer())]
+#[test_case("bool", & Type::Bool)]
+#[test_case("*[dyn Shape]", & Type::Dyn("Shape".into()).as_slice().as_pointer())]
+#[test_case("dyn shapes::Shape", & Type::Dyn("shapes::Shape".into()))]
 #[test_case("const const const const i32", & Type::I32.as_const())]
+#[test_case("Vec<Vec<i32>>", & Type::template("Vec", vec ! [Type::template("Vec", vec ! [Type::I32])] ))]
+#[test_case("function () => unit", & FunctionSignature::new(vec ! [], Type::Unit).into())]
+#[test_case("function (f32, u8) => unit", & FunctionSignature::new(vec ! [Type::F32, Type::U8], Type::Unit).into())]
+#[test_case("*function (i32) => function () => i32", & FunctionSignature::new(vec ! [Type::I32], FunctionSignature::new(vec ! [], Type::I32).into()).as_type().as_pointer())]
+#[test_case("[function (i32) => i32; 2]", & FunctionSignature::new(vec ! [Type::I32], Type::I32).as_type().as_array(2))]
 fn consume_type(source: &'static str, ty: &Type) {
 	let mut ctx = lexer::tokenize(source.into()).unwrap().into();
 	assert_eq!(&category::consume_type(&mut ctx).unwrap(), ty);
 }
 
+#[test_case("[i32 3]"; "Missing semicolon")]
+#[test_case("[i32; ]"; "Missing length")]
+#[test_case("[i32; 3"; "Unclosed")]
+fn invalid_array_type(source: &'static str) {
+	let mut ctx = lexer::tokenize(source.into()).unwrap().into();
+	assert!(category::consume_type(&mut ctx).is_err());
+}
+
 #[test]
 fn consume_expression() {
 	let t = |s: &'static str|
-		expression::consume(&mut TokenStream::from(lexer::tokenize(s.into()).unwrap())).unwrap();
+		expression::consume(&mut TokenStream::from(lexer::tokenize(s.into()).unwrap())).unwrap().node;
 
 	assert_eq!(t("wha "), "wha".into());
 	assert_eq!(t("a*b"), Expression::Binary {
 		lhs: Box::new("a".into()),
 		operator: Binary::Multiply,
 		rhs: Box::new("b".into()),
+		ty: Type::Infer,
 	});
 	assert_eq!(t("a*b + c"), Expression::Binary {
-		lhs: Expression::Binary {
+		lhs: Box::new(Expression::Binary {
 			lhs: Box::new("a".into()),
 			operator: Binary::Multiply,
 			rhs: Box::new("b".into()),
-		}.into(),
+			ty: Type::Infer,
+		}.into()),
 		operator: Binary::Add,
 		rhs: Box::new("c".into()),
+		ty: Type::Infer,
 	});
 	assert_eq!(t("4 + 2"), Expression::Binary {
 		lhs: Box::new(Literal::Integer(4).into()),
 		operator: Binary::Add,
 		rhs: Box::new(Literal::Integer(2).into()),
+		ty: Type::Infer,
 	});
 }
 
 #[test]
 fn consume_statement() {
-	let t = |s: &'static str| statement::consume(&mut lexer::tokenize(s.into()).unwrap().into()).unwrap();
+	let t = |s: &'static str| statement::consume(&mut lexer::tokenize(s.into()).unwrap().into()).unwrap().node;
 
 	assert_eq!(t("var a: i32"), Statement::Declaration {
 		var: Variable::new("a", Type::I32),
@@ -72,33 +106,219 @@ fn consume_statement() {
 
 	assert_eq!(t("var a: i32 = bruh"), Statement::Declaration {
 		var: Variable::new("a", Type::I32),
-		initialisation: Some(Expression::ObjectReference("bruh".into())),
+		initialisation: Some(Expression::ObjectReference("bruh".into()).into()),
 	});
 
 	assert_eq!(t("let a: i32 = bruh"), Statement::Declaration {
 		var: Variable::new("a", Type::I32.as_const()),
-		initialisation: Some(Expression::ObjectReference("bruh".into())),
+		initialisation: Some(Expression::ObjectReference("bruh".into()).into()),
 	});
 
 	//
 
 	assert_eq!(t("let a: *const i32 = bruh"), Statement::Declaration {
 		var: Variable::new("a", Type::I32.as_const().as_pointer().as_const()),
-		initialisation: Some(Expression::ObjectReference("bruh".into())),
+		initialisation: Some(Expression::ObjectReference("bruh".into()).into()),
+	});
+
+	assert_eq!(t("let a = bruh"), Statement::Declaration {
+		var: Variable::new("a", Type::Infer.as_const()),
+		initialisation: Some(Expression::ObjectReference("bruh".into()).into()),
+	});
+
+	assert_eq!(t("var a = [1, 2]"), Statement::Declaration {
+		var: Variable::new("a", Type::Infer),
+		initialisation: Some(Expression::Array(vec![Literal::Integer(1).into(), Literal::Integer(2).into()]).into()),
+	});
+
+	assert_eq!(t("let (q, r: i32) = divmod(a, b)"), Statement::Destructure {
+		pattern: Pattern::Tuple(vec![
+			Pattern::Binding(Variable::new("q", Type::Infer.as_const())).into(),
+			Pattern::Binding(Variable::new("r", Type::I32.as_const())).into(),
+		]).into(),
+		initialisation: Expression::FunctionCall {
+			function: Box::new("divmod".into()),
+			arguments: vec!["a".into(), "b".into()],
+		}.into(),
+		otherwise: None,
+	});
+
+	assert_eq!(t("assert(n == 2)"), Statement::Assert(Expression::Binary {
+		lhs: Box::new("n".into()),
+		operator: Binary::Equals,
+		rhs: Box::new(Literal::Integer(2).into()),
+		ty: Type::Infer,
+	}.into()));
+
+	assert_eq!(t("defer { delete p }"), Statement::Defer(Expression::Scope(vec![Statement::Delete("p".into()).into()]).into()));
+	assert_eq!(t("defer delete p"), Statement::Defer(Expression::Scope(vec![Statement::Delete("p".into()).into()]).into()));
+	assert_eq!(t("defer close(f)"), Statement::Defer(Expression::FunctionCall {
+		function: Box::new("close".into()),
+		arguments: vec!["f".into()],
+	}.into()));
+
+	assert_eq!(t("var (a, b) = t"), Statement::Destructure {
+		pattern: Pattern::Tuple(vec![
+			Pattern::Binding(Variable::new("a", Type::Infer)).into(),
+			Pattern::Binding(Variable::new("b", Type::Infer)).into(),
+		]).into(),
+		initialisation: "t".into(),
+		otherwise: None,
+	});
+
+	assert_eq!(t("let [0, x, ..] = xs else { return 1 }"), Statement::Destructure {
+		pattern: Pattern::Array {
+			elements: vec![
+				Pattern::Literal(Literal::Integer(0)).into(),
+				Pattern::Binding(Variable::new("x", Type::Infer.as_const())).into(),
+			],
+			rest: true,
+		}.into(),
+		initialisation: "xs".into(),
+		otherwise: Some(Box::new(Expression::Scope(vec![Statement::Return(Literal::Integer(1).into()).into()]).into())),
+	});
+}
+
+#[test]
+fn consume_if() {
+	let t = |s: &'static str|
+		expression::consume(&mut TokenStream::from(lexer::tokenize(s.into()).unwrap())).unwrap().node;
+	let scope = |x: Expression| Expression::Scope(vec![x.into()]);
+
+	assert_eq!(t("if p != null { 1 }"), Expression::If {
+		condition: Box::new(Expression::Binary {
+			lhs: Box::new("p".into()),
+			operator: Binary::NotEquals,
+			rhs: Box::new(Literal::Null.into()),
+			ty: Type::Infer,
+		}.into()),
+		then: Box::new(scope(Literal::Integer(1).into()).into()),
+		otherwise: None,
+	});
+
+	let chain = Expression::If {
+		condition: Box::new("a".into()),
+		then: Box::new(scope(Literal::Integer(1).into()).into()),
+		otherwise: Some(Box::new(Expression::If {
+			condition: Box::new("b".into()),
+			then: Box::new(scope(Literal::Integer(2).into()).into()),
+			otherwise: Some(Box::new(scope(Literal::Integer(3).into()).into())),
+		}.into())),
+	};
+	assert_eq!(t("if a { 1 } else if b { 2 } else { 3 }"), chain);
+	assert_eq!(t("if a {\n\t1\n}\nelse if b {\n\t2\n}\nelse {\n\t3\n}"), chain);
+
+	assert!(expression::consume(&mut TokenStream::from(lexer::tokenize("if a 1".into()).unwrap())).is_err());
+}
+
+#[test]
+fn consume_tuple() {
+	let t = |s: &'static str|
+		expression::consume(&mut TokenStream::from(lexer::tokenize(s.into()).unwrap())).unwrap().node;
+
+	assert_eq!(t("(a)"), "a".into());
+	assert_eq!(t("(a,)"), Expression::Tuple(vec!["a".into()]));
+	assert_eq!(t("(a, 2)"), Expression::Tuple(vec!["a".into(), Literal::Integer(2).into()]));
+	assert_eq!(t("t.1"), Expression::Binary {
+		lhs: Box::new("t".into()),
+		operator: Binary::Dot,
+		rhs: Box::new(Literal::Integer(1).into()),
+		ty: Type::Infer,
+	});
+}
+
+#[test]
+fn consume_pattern() {
+	let t = |s: &'static str| category::consume_pattern(&mut lexer::tokenize(s.into()).unwrap().into()).map(|x| x.node);
+	let binding = |name: &'static str| Spanned::from(Pattern::Binding(Variable::new(name, Type::Infer)));
+
+	assert_eq!(t("(a)").unwrap(), Pattern::Binding(Variable::new("a", Type::Infer)));
+	assert_eq!(t("(a,)").unwrap(), Pattern::Tuple(vec![binding("a")]));
+	assert_eq!(t("(_, 'c', b: u8)").unwrap(), Pattern::Tuple(vec![
+		Pattern::Wildcard.into(),
+		Pattern::Literal(Literal::Character('c')).into(),
+		Pattern::Binding(Variable::new("b", Type::U8)).into(),
+	]));
+	assert_eq!(t("(q, [first, ..])").unwrap(), Pattern::Tuple(vec![
+		binding("q"),
+		Pattern::Array { elements: vec![binding("first")], rest: true }.into(),
+	]));
+	assert_eq!(t("[]").unwrap(), Pattern::Array { elements: vec![], rest: false });
+	assert_eq!(t("[..]").unwrap(), Pattern::Array { elements: vec![], rest: true });
+
+	assert!(t("[.., a]").is_err());
+	assert!(t("(a b)").is_err());
+
+	let function = category::consume_function(&mut lexer::tokenize("((x, _): (i32, u8), [y, ..]: [i32; 3]) => x".into()).unwrap().into(), Type::Unit).unwrap();
+	assert_eq!(function.signature, FunctionSignature::new_named(vec![
+		Variable::new("argument::0", Type::Tuple(vec![Type::I32, Type::U8])),
+		Variable::new("argument::1", Type::I32.as_array(3)),
+	], Type::Unit));
+	let Expression::Scope(body) = &function.body.node else { panic!("Expected a scope") };
+	assert!(matches!(body.as_slice(), [
+		Spanned { node: Statement::Destructure { pattern: Spanned { node: Pattern::Tuple(_), .. }, .. }, .. },
+		Spanned { node: Statement::Destructure { pattern: Spanned { node: Pattern::Array { rest: true, .. }, .. }, .. }, .. },
+		Spanned { node: Statement::Expression(_), .. },
+	]));
+}
+
+#[test]
+fn untyped_uninitialised_variable() {
+	let t = |s: &'static str| statement::consume(&mut lexer::tokenize(s.into()).unwrap().into());
+
+	assert!(matches!(t("var a"), Err(parser::Error::UntypedUninitialisedVariable(ident)) if ident == "a".into()));
+	assert!(t("var a: i32").is_ok());
+	assert!(t("let a: i32").is_err());
+}
+
+#[test]
+fn consume_postfix() {
+	let t = |s: &'static str|
+		expression::consume(&mut TokenStream::from(lexer::tokenize(s.into()).unwrap())).unwrap().node;
+
+	assert_eq!(t("f()"), Expression::FunctionCall {
+		function: Box::new("f".into()),
+		arguments: vec![],
+	});
+
+	assert_eq!(t("f(a, 2)[i]"), Expression::ArrayAccess {
+		expr: Box::new(Expression::FunctionCall {
+			function: Box::new("f".into()),
+			arguments: vec!["a".into(), Literal::Integer(2).into()],
+		}.into()),
+		index: Box::new("i".into()),
+	});
+
+	assert_eq!(t("a + b[1]"), Expression::Binary {
+		lhs: Box::new("a".into()),
+		operator: Binary::Add,
+		rhs: Box::new(Expression::ArrayAccess {
+			expr: Box::new("b".into()),
+			index: Box::new(Literal::Integer(1).into()),
+		}.into()),
+		ty: Type::Infer,
 	});
 }
 
 #[test]
 fn consume_top_level() {
-	let t = |s: &'static str| TokenStream::parse(lexer::tokenize(s.into()).unwrap()).unwrap();
+	let t = |s: &'static str| TokenStream::parse(lexer::tokenize(s.into()).unwrap()).into_result().unwrap()
+		.into_iter()
+		.map(|x| x.node)
+		.collect::<Vec<_>>();
 
 	{
 		let what_unit = vec![TopLevelStatement::Function {
 			ident: "what".into(),
+			generics: vec![],
 			function: Function {
 				signature: FunctionSignature::new_named(vec![], Type::Unit),
-				body: Box::new(Expression::Scope(vec![])),
+				body: Box::new(Expression::Scope(vec![]).into()),
+				captures: vec![],
 			},
+			attributes: vec![],
+			visibility: Visibility::Private,
+			constant: false,
 		}];
 
 		assert_eq!(t("function what() {}"), what_unit);
@@ -106,41 +326,670 @@ fn consume_top_level() {
 
 		assert_eq!(t(r#"function huh(x: f32, y: f32) {}"#), vec![TopLevelStatement::Function {
 			ident: "huh".into(),
+			generics: vec![],
 			function: Function {
 				signature: FunctionSignature::new_named(vec![
 					Variable::new("x", Type::F32),
 					Variable::new("y", Type::F32),
 				], Type::Unit),
-				body: Box::new(Expression::Scope(vec![])),
+				body: Box::new(Expression::Scope(vec![]).into()),
+				captures: vec![],
 			},
+			attributes: vec![],
+			visibility: Visibility::Private,
+			constant: false,
 		}]);
 
 		assert_eq!(t(r#"function huh() -> Bruh {
 			{}
 		}"#), vec![TopLevelStatement::Function {
 			ident: "huh".into(),
+			generics: vec![],
 			function: Function {
 				signature: FunctionSignature::new_named(vec![], Type::custom("Bruh")),
-				body: Box::new(Expression::Scope(vec![Expression::Scope(vec![]).into()])),
+				body: Box::new(Expression::Scope(vec![Expression::Scope(vec![]).into()]).into()),
+				captures: vec![],
 			},
+			attributes: vec![],
+			visibility: Visibility::Private,
+			constant: false,
 		}]);
 
 		assert_eq!(t(r#"function man() -> Bruh {
 			man
 		}"#), vec![TopLevelStatement::Function {
 			ident: "man".into(),
+			generics: vec![],
 			function: Function {
 				signature: FunctionSignature::new_named(vec![], Type::custom("Bruh")),
-				body: Box::new(Expression::Scope(vec![Expression::ObjectReference("man".into()).into()])),
+				body: Box::new(Expression::Scope(vec![Expression::ObjectReference("man".into()).into()]).into()),
+				captures: vec![],
 			},
+			attributes: vec![],
+			visibility: Visibility::Private,
+			constant: false,
 		}]);
 
 		assert_eq!(t(r#"function lambda() -> f32 => bruh"#), vec![TopLevelStatement::Function {
 			ident: "lambda".into(),
+			generics: vec![],
 			function: Function {
 				signature: FunctionSignature::new_named(vec![], Type::F32),
-				body: Box::new(Expression::ObjectReference("bruh".into())),
+				body: Box::new(Expression::ObjectReference("bruh".into()).into()),
+				captures: vec![],
 			},
+			attributes: vec![],
+			visibility: Visibility::Private,
+			constant: false,
 		}]);
 	}
 }
+
+#[test]
+fn consume_imports() {
+	let t = |s: &'static str| TokenStream::parse(lexer::tokenize(s.into()).unwrap()).into_result().unwrap()
+		.into_iter()
+		.map(|x| x.node)
+		.collect::<Vec<_>>();
+
+	assert_eq!(t("import \"geometry.pp\"\nuse geometry::area"), vec![
+		TopLevelStatement::Import("geometry.pp".into()),
+		TopLevelStatement::Use("geometry::area".into()),
+	]);
+
+	assert_eq!(parse_expression("shapes::circle::area(2)"), Expression::FunctionCall {
+		function: Box::new(Expression::ObjectReference("shapes::circle::area".into()).into()),
+		arguments: vec![Expression::Literal(Literal::Integer(2)).into()],
+	});
+
+	assert!(TokenStream::parse(lexer::tokenize("import geometry".into()).unwrap()).into_result().is_err());
+	assert!(TokenStream::parse(lexer::tokenize("use geometry::".into()).unwrap()).into_result().is_err());
+}
+
+#[test]
+fn consume_lambda() {
+	let t = |s: &'static str|
+		expression::consume(&mut TokenStream::from(lexer::tokenize(s.into()).unwrap())).unwrap().node;
+
+	let doubled = Function::new(
+		FunctionSignature::new_named(vec![Variable::new("x", Type::I32)], Type::I32),
+		Box::new(Expression::Binary {
+			lhs: Box::new("x".into()),
+			operator: Binary::Multiply,
+			rhs: Box::new(Literal::Integer(2).into()),
+			ty: Type::Infer,
+		}.into()),
+	);
+
+	assert_eq!(t("function (x: i32) -> i32 => x * 2"), doubled.clone().into());
+	assert_eq!(t("function (x: i32) -> i32 { x * 2 }"), Function {
+		body: Box::new(Expression::Scope(vec![(*doubled.body).clone().into()]).into()),
+		..doubled.clone()
+	}.into());
+
+	assert_eq!(t("function () => a"), Function::new(
+		FunctionSignature::new_named(vec![], Type::Infer),
+		Box::new("a".into()),
+	).into());
+
+	assert_eq!(
+		doubled.as_type(),
+		FunctionSignature::new(vec![Type::I32], Type::I32).as_type()
+	);
+}
+
+/// Binary operators from loosest to tightest binding, written out separately from
+/// [`Binary::precedence`] so that the table itself is under test
+const BINARY_LEVELS: &[&[(&str, Binary)]] = &[
+	&[("=", Binary::Assignment)],
+	&[("or", Binary::Or)],
+	&[("and", Binary::And)],
+	&[("xor", Binary::BitXor)],
+	&[("==", Binary::Equals), ("!=", Binary::NotEquals)],
+	&[("<", Binary::Less), ("<=", Binary::LessOrEquals), (">", Binary::Greater), (">=", Binary::GreaterOrEquals)],
+	&[("<<", Binary::ShiftLeft), (">>", Binary::ShiftRight)],
+	&[("+", Binary::Add), ("-", Binary::Minus)],
+	&[("*", Binary::Multiply), ("/", Binary::Divide), ("%", Binary::Mod)],
+	&[(".", Binary::Dot)],
+];
+
+const UNARY_OPERATORS: &[(&str, Unary)] = &[
+	("-", Unary::Negate),
+	("not ", Unary::Not),
+	("&", Unary::Reference),
+	("*", Unary::Dereference),
+];
+
+fn parse_expression(source: &str) -> Expression {
+	expression::consume(&mut TokenStream::from(lexer::tokenize(source.into()).unwrap())).unwrap().node
+}
+
+fn binary(lhs: impl Into<Expression>, operator: Binary, rhs: impl Into<Expression>) -> Expression {
+	Expression::Binary { lhs: Box::new(Spanned::from(lhs)), operator, rhs: Box::new(Spanned::from(rhs)), ty: Type::Infer }
+}
+
+fn unary(operator: Unary, expr: impl Into<Expression>) -> Expression {
+	Expression::Unary { operator, expr: Box::new(Spanned::from(expr)) }
+}
+
+#[test]
+fn binary_precedence_pairs() {
+	let operators = BINARY_LEVELS.iter()
+		.enumerate()
+		.flat_map(|(level, operators)| operators.iter().map(move |(source, op)| (level, *source, *op)));
+
+	for (first_level, first_source, first) in operators.clone() {
+		for (second_level, second_source, second) in operators.clone() {
+			let source = format!("a {first_source} b {second_source} c");
+
+			let groups_left = match first_level.cmp(&second_level) {
+				std::cmp::Ordering::Greater => true,
+				std::cmp::Ordering::Less => false,
+				std::cmp::Ordering::Equal => first != Binary::Assignment,
+			};
+
+			let expected = if groups_left {
+				binary(binary("a", first, "b"), second, "c")
+			} else {
+				binary("a", first, binary("b", second, "c"))
+			};
+
+			assert_eq!(parse_expression(&source), expected, "{source}");
+		}
+	}
+}
+
+#[test]
+fn unary_precedence_pairs() {
+	for (unary_source, op) in UNARY_OPERATORS {
+		for (binary_source, bin) in BINARY_LEVELS.iter().flat_map(|x| x.iter()) {
+			// only '.' binds tighter than a unary operator
+			let source = format!("{unary_source}a {binary_source} b");
+			let expected = if *bin == Binary::Dot {
+				unary(*op, binary("a", *bin, "b"))
+			} else {
+				binary(unary(*op, "a"), *bin, "b")
+			};
+			assert_eq!(parse_expression(&source), expected, "{source}");
+
+			if *bin == Binary::Dot { continue; }
+
+			let source = format!("a {binary_source} {unary_source}b");
+			assert_eq!(parse_expression(&source), binary("a", *bin, unary(*op, "b")), "{source}");
+		}
+	}
+}
+
+#[test]
+fn postfix_precedence() {
+	let call = |function: Expression, arguments: Vec<Expression>| Expression::FunctionCall {
+		function: Box::new(function.into()),
+		arguments: arguments.into_iter().map(Spanned::from).collect(),
+	};
+	let index = |expr: Expression, index: Expression| Expression::ArrayAccess {
+		expr: Box::new(expr.into()),
+		index: Box::new(index.into()),
+	};
+
+	assert_eq!(parse_expression("-f(x)"), unary(Unary::Negate, call("f".into(), vec!["x".into()])));
+	assert_eq!(parse_expression("*a[0]"), unary(Unary::Dereference, index("a".into(), Literal::Integer(0).into())));
+	assert_eq!(parse_expression("a.b(c)"), call(binary("a", Binary::Dot, "b"), vec!["c".into()]));
+	assert_eq!(parse_expression("a.b[c]"), index(binary("a", Binary::Dot, "b"), "c".into()));
+	assert_eq!(parse_expression("f(x).y"), binary(call("f".into(), vec!["x".into()]), Binary::Dot, "y"));
+	assert_eq!(parse_expression("*p.x = -a + b"), binary(
+		unary(Unary::Dereference, binary("p", Binary::Dot, "x")),
+		Binary::Assignment,
+		binary(unary(Unary::Negate, "a"), Binary::Add, "b"),
+	));
+	assert_eq!(parse_expression("- -a"), unary(Unary::Negate, unary(Unary::Negate, "a")));
+}
+
+fn parse_recovering(source: &str) -> parser::Parsed {
+	TokenStream::parse(lexer::tokenize(source.into()).unwrap())
+}
+
+fn function_body(statement: &TopLevelStatement) -> Vec<Statement> {
+	let TopLevelStatement::Function { function, .. } = statement else { panic!("Expected a function") };
+	let Expression::Scope(body) = &function.body.node else { panic!("Expected a scope") };
+	body.iter().map(|x| x.node.clone()).collect()
+}
+
+#[test]
+fn recover_statements() {
+	let parsed = parse_recovering("function f() { let = 1; var; return 2 } function g() { return 3 }");
+
+	assert_eq!(parsed.diagnostics.len(), 2);
+	assert_eq!(parsed.statements.len(), 2);
+	assert_eq!(function_body(&parsed.statements[0]), &[
+		Statement::Error,
+		Statement::Error,
+		Statement::Return(Literal::Integer(2).into()),
+	]);
+	assert_eq!(function_body(&parsed.statements[1]), &[Statement::Return(Literal::Integer(3).into())]);
+}
+
+#[test]
+fn recover_arguments() {
+	let parsed = parse_recovering("function f() { g(1, +, 3) }");
+
+	assert_eq!(parsed.diagnostics.len(), 1);
+	assert_eq!(function_body(&parsed.statements[0]), &[Statement::Expression(Expression::FunctionCall {
+		function: Box::new("g".into()),
+		arguments: vec![Literal::Integer(1).into(), Expression::Error.into(), Literal::Integer(3).into()],
+	}.into())]);
+}
+
+#[test]
+fn recover_top_level() {
+	let parsed = parse_recovering("5 ) function f() {} function (");
+
+	assert_eq!(parsed.statements.len(), 1);
+	assert!(matches!(parsed.diagnostics.as_slice(), [
+		parser::Diagnostic { error: parser::Error::ExpectedEndOfFile(_), .. },
+		parser::Diagnostic { error: parser::Error::ExpectedToken { .. }, .. },
+	]), "{:?}", parsed.diagnostics);
+}
+
+#[test]
+fn recover_unclosed_scope() {
+	let parsed = parse_recovering("function f() {\n\treturn 1");
+
+	assert!(parsed.statements.is_empty());
+	assert_eq!(parsed.diagnostics.len(), 1);
+	assert_eq!(parsed.diagnostics[0].error.to_string(), parser::Error::expected_token(
+		parser::token::Token::EOF,
+		parser::token::Parenthetical::CurlyClose,
+	).to_string());
+	assert_eq!(parsed.diagnostics[0].trace.begin_pos.row, 1);
+}
+
+#[test]
+fn statement_terminators() {
+	let parsed = parse_recovering("function f() {\n\ta\n\t-b\n}");
+	assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+	assert_eq!(function_body(&parsed.statements[0]), &[
+		Statement::Expression("a".into()),
+		Statement::Expression(unary(Unary::Negate, "b").into()),
+	]);
+
+	let parsed = parse_recovering("function f() { a; ; b; }");
+	assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+	assert_eq!(function_body(&parsed.statements[0]), &[
+		Statement::Expression("a".into()),
+		Statement::Expression("b".into()),
+	]);
+
+	let parsed = parse_recovering("function f() { a b }");
+	assert_eq!(function_body(&parsed.statements[0]), &[Statement::Error]);
+	assert!(matches!(parsed.diagnostics.as_slice(), [parser::Diagnostic {
+		error: parser::Error::ExpectedToken { expected: parser::token::Token::Operator(parser::token::Operator::SemiColon), .. },
+		..
+	}]), "{:?}", parsed.diagnostics);
+}
+
+#[test]
+fn bodies_on_their_own_line() {
+	let parsed = parse_recovering("function f() -> i32\n{\n\tif a\n\t{\n\t\t1\n\t}\n\telse\n\t{\n\t\t2\n\t}\n}\nfunction g()\n{\n}");
+	assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+	assert_eq!(function_body(&parsed.statements[0]), &[Statement::Expression(Expression::If {
+		condition: Box::new("a".into()),
+		then: Box::new(Expression::Scope(vec![Statement::Expression(Literal::Integer(1).into()).into()]).into()),
+		otherwise: Some(Box::new(Expression::Scope(vec![Statement::Expression(Literal::Integer(2).into()).into()]).into())),
+	}.into())]);
+	assert_eq!(function_body(&parsed.statements[1]), &[]);
+
+	let parsed = parse_recovering("impl Point\n{\n}\ninterface Shape\n{\n}\ntest \"runs\"\n{\n}");
+	assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+	assert_eq!(parsed.statements.len(), 3);
+
+	// a scope on the line after a statement is still a statement of its own
+	let parsed = parse_recovering("function f() {\n\ta\n\t{ b }\n}");
+	assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+	assert_eq!(function_body(&parsed.statements[0]).len(), 2);
+}
+
+#[test]
+fn expression_spans() {
+	let expr = expression::consume(&mut TokenStream::from(lexer::tokenize("f(a) +\n  b * (c)".into()).unwrap())).unwrap();
+	let span = |expr: &Spanned<Expression>| (
+		(expr.trace.begin_pos.row, expr.trace.begin_pos.column),
+		(expr.trace.end_pos.row, expr.trace.end_pos.column),
+	);
+
+	let Expression::Binary { lhs, rhs, .. } = &expr.node else { panic!("Expected a binary expression") };
+	assert_eq!(span(&expr), ((0, 1), (1, 10)));
+	assert_eq!(span(lhs), ((0, 1), (0, 5)));
+	assert_eq!(span(rhs), ((1, 3), (1, 10)));
+
+	let Expression::FunctionCall { arguments, .. } = &lhs.node else { panic!("Expected a call") };
+	assert_eq!(span(&arguments[0]), ((0, 3), (0, 4)));
+
+	let Expression::Binary { rhs, .. } = &rhs.node else { panic!("Expected a binary expression") };
+	// parenthesis are included in the trace of what they wrap
+	assert_eq!(span(rhs), ((1, 7), (1, 10)));
+}
+
+#[test]
+fn statement_spans() {
+	let parsed = parse_recovering("function f() {\n\tlet a = 2\n\treturn a;\n}");
+	let TopLevelStatement::Function { function, .. } = &parsed.statements[0].node else { panic!("Expected a function") };
+	let Expression::Scope(body) = &function.body.node else { panic!("Expected a scope") };
+
+	let rows = body.iter().map(|x| (x.trace.begin_pos.row, x.trace.end_pos.row)).collect::<Vec<_>>();
+	assert_eq!(rows, vec![(1, 1), (2, 2)]);
+	assert_eq!(body[1].trace.begin_pos.column, 2);
+	assert_eq!(parsed.statements[0].trace.end_pos.row, 3);
+}
+
+#[test]
+fn consume_attributes() {
+	let parsed = parse_recovering("@inline @deprecated(\"use g\")\nfunction f() {}\n@extern(\"C\")\nfunction g() {}");
+	assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+
+	let attributes = parsed.statements.iter().map(|x| match &x.node {
+		TopLevelStatement::Function { attributes, .. } => attributes.iter().map(|x| x.node.clone()).collect(),
+		_ => vec![],
+	}).collect::<Vec<_>>();
+
+	assert_eq!(attributes, vec![
+		vec![Attribute::Inline, Attribute::Deprecated("use g".into())],
+		vec![Attribute::Extern("C".into())],
+	]);
+
+	// spans cover the attributes, along with the statement they are attached to
+	assert_eq!(parsed.statements[1].trace.begin_pos.row, 2);
+
+	// every declaration keeps the attributes written on it
+	let parsed = parse_recovering("@packed\ntype Header = (u8, u32)\n@export const N: usize = 2\n@deprecated(\"use Area\") interface Shape {}");
+	assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+
+	let attributes = parsed.statements.iter().map(|x| match &x.node {
+		TopLevelStatement::TypeAlias { attributes, .. }
+		| TopLevelStatement::Const { attributes, .. }
+		| TopLevelStatement::Interface { attributes, .. } => attributes.iter().map(|x| x.node.clone()).collect(),
+		_ => vec![],
+	}).collect::<Vec<_>>();
+
+	assert_eq!(attributes, vec![
+		vec![Attribute::Packed],
+		vec![Attribute::Export],
+		vec![Attribute::Deprecated("use Area".into())],
+	]);
+}
+
+#[test]
+fn invalid_attributes() {
+	let parsed = parse_recovering("@unknown @inline(\"x\") @packed\nfunction f() {}\n@test import \"a.pp\"");
+
+	assert_eq!(parsed.statements.len(), 2);
+	let errors = parsed.diagnostics.iter().map(|x| x.error.to_string()).collect::<Vec<_>>();
+	assert_eq!(errors, [
+		"Unknown attribute '@unknown'",
+		"Attribute '@inline' takes 0 argument(s), given 1",
+		"Attribute '@packed' cannot be used on a function",
+		"Attribute '@test' cannot be used on an import",
+	]);
+
+	// located at the attribute rather than the declaration
+	assert_eq!(parsed.diagnostics[2].trace.begin_pos.column, 23);
+
+	let parsed = parse_recovering("@packed type Mat4 = [[f32; 4]; 4]\n@inline const N: usize = 2\n@deprecated(\"no\") impl Point {}");
+	assert_eq!(parsed.statements.len(), 3);
+	let errors = parsed.diagnostics.iter().map(|x| x.error.to_string()).collect::<Vec<_>>();
+	assert_eq!(errors, [
+		"Attribute '@packed' cannot be used on a type alias",
+		"Attribute '@inline' cannot be used on a variable",
+		"Attribute '@deprecated' cannot be used on an impl block",
+	]);
+
+	// a broken attribute does not take the function after it down with it
+	let parsed = parse_recovering("@deprecated(5)\nfunction f() {}");
+	assert_eq!(parsed.statements.len(), 1);
+	assert_eq!(parsed.diagnostics.len(), 1);
+}
+
+#[test]
+fn consume_test() {
+	let parsed = parse_recovering("@inline test \"empty list\" {\n\tassert(no_cap)\n}\nfunction test() {}\ntest \"missing body\"");
+
+	let TopLevelStatement::Function { ident, function, attributes, .. } = &parsed.statements[0].node else {
+		panic!("Expected a function");
+	};
+	assert_eq!(ident, &Identifier::from("empty list"));
+	assert_eq!(function.signature, FunctionSignature::new_named(vec![], Type::Unit));
+	assert_eq!(function.body.node, Expression::Scope(vec![Statement::Assert(Literal::Bool(true).into()).into()]));
+	assert_eq!(attributes.iter().map(|x| x.node.clone()).collect::<Vec<_>>(), [Attribute::Inline, Attribute::Test]);
+
+	// 'test' is only special in front of the name of a test
+	assert!(matches!(&parsed.statements[1].node, TopLevelStatement::Function { ident, .. } if ident.0 == "test"));
+	assert_eq!(parsed.statements.len(), 2);
+	assert_eq!(parsed.diagnostics.len(), 1);
+}
+
+#[test]
+fn consume_visibility() {
+	let parsed = parse_recovering("pub function f() {}\n@inline pub function g() {}\nfunction h() {}\npub import \"a.pp\"");
+
+	let visibilities = parsed.statements.iter().filter_map(|x| match &x.node {
+		TopLevelStatement::Function { visibility, .. } => Some(*visibility),
+		_ => None,
+	}).collect::<Vec<_>>();
+
+	assert_eq!(visibilities, [Visibility::Public, Visibility::Public, Visibility::Private]);
+	assert_eq!(parsed.diagnostics.len(), 1);
+	assert_eq!(parsed.diagnostics[0].error.to_string(), "'pub' cannot be used on an import");
+	assert_eq!(parsed.diagnostics[0].trace.begin_pos.row, 3);
+
+	let parsed = parse_recovering("pub use a::b\n@inline use a::c");
+	let errors = parsed.diagnostics.iter().map(|x| x.error.to_string()).collect::<Vec<_>>();
+	assert_eq!(errors, ["'pub' cannot be used on a 'use'", "Attribute '@inline' cannot be used on a 'use'"]);
+}
+
+#[test]
+fn consume_type_alias() {
+	let parsed = parse_recovering("type Mat4 = [[f32; 4]; 4]\npub type Pair<T, U> = *Map<T, U>\ntype geometry::Bad = i32");
+
+	assert_eq!(parsed.statements.iter().map(|x| x.node.clone()).collect::<Vec<_>>(), [
+		TopLevelStatement::TypeAlias {
+			ident: "Mat4".into(),
+			parameters: vec![],
+			ty: Type::F32.as_array(4).as_array(4),
+			attributes: vec![],
+			visibility: Visibility::Private,
+		},
+		TopLevelStatement::TypeAlias {
+			ident: "Pair".into(),
+			parameters: vec!["T".into(), "U".into()],
+			ty: Type::template("Map", vec![Type::custom("T"), Type::custom("U")]).as_pointer(),
+			attributes: vec![],
+			visibility: Visibility::Public,
+		},
+	]);
+
+	// alias names are plain identifiers, though the types they stand for can be paths
+	assert_eq!(parsed.diagnostics.len(), 1);
+	assert_eq!(category::consume_type(&mut TokenStream::from(lexer::tokenize("geometry::Vec2".into()).unwrap())).unwrap(), Type::custom("geometry::Vec2"));
+}
+
+#[test]
+fn consume_const() {
+	let parsed = parse_recovering("pub const N: usize = 4 * 4\nconst M = 2\ntype Grid = [[u8; N]; N * 2]");
+
+	assert_eq!(parsed.statements.iter().map(|x| x.node.clone()).collect::<Vec<_>>()[..2], [
+		TopLevelStatement::Const {
+			constant: Variable::new("N", Type::USize),
+			value: Expression::Binary {
+				lhs: Box::new(Literal::Integer(4).into()),
+				operator: Binary::Multiply,
+				rhs: Box::new(Literal::Integer(4).into()),
+				ty: Type::Infer,
+			}.into(),
+			attributes: vec![],
+			visibility: Visibility::Public,
+		},
+		TopLevelStatement::TypeAlias {
+			ident: "Grid".into(),
+			parameters: vec![],
+			ty: Type::ConstArray {
+				ty: Box::new(Type::ConstArray { ty: Box::new(Type::U8), length: Length(Box::new("N".into())) }),
+				length: Length(Box::new(Expression::Binary {
+					lhs: Box::new("N".into()),
+					operator: Binary::Multiply,
+					rhs: Box::new(Literal::Integer(2).into()),
+					ty: Type::Infer,
+				}.into())),
+			},
+			attributes: vec![],
+			visibility: Visibility::Private,
+		},
+	]);
+
+	// constants always have their type written
+	assert_eq!(parsed.diagnostics.len(), 1);
+
+	let parsed = parse_recovering("pub const function square(x: u32) -> u32 => x * x");
+	assert_eq!(parsed.statements.into_iter().map(|x| x.node).collect::<Vec<_>>(), [TopLevelStatement::Function {
+		ident: "square".into(),
+		generics: vec![],
+		function: Function::new(
+			FunctionSignature::new_named(vec![Variable::new("x", Type::U32)], Type::U32),
+			Box::new(Expression::Binary {
+				lhs: Box::new("x".into()),
+				operator: Binary::Multiply,
+				rhs: Box::new("x".into()),
+				ty: Type::Infer,
+			}.into()),
+		),
+		attributes: vec![],
+		visibility: Visibility::Public,
+		constant: true,
+	}]);
+}
+
+#[test]
+fn consume_impl() {
+	let parsed = parse_recovering("impl Point {\n\tpub function new() -> Point => (0, 0)\n\n\tfunction x(self: *const Point) -> i32 => self.0\n}\npub impl [i32] {}");
+
+	let new = Function::new(
+		FunctionSignature::new_named(vec![], Type::custom("Point")),
+		Box::new(Expression::Tuple(vec![Literal::Integer(0).into(), Literal::Integer(0).into()]).into()),
+	);
+	let x = Function::new(
+		FunctionSignature::new_named(vec![Variable::new("self", Type::custom("Point").as_const().as_pointer())], Type::I32),
+		Box::new(Expression::Binary {
+			lhs: Box::new("self".into()),
+			operator: Binary::Dot,
+			rhs: Box::new(Literal::Integer(0).into()),
+			ty: Type::Infer,
+		}.into()),
+	);
+
+	assert_eq!(parsed.statements.iter().map(|x| x.node.clone()).collect::<Vec<_>>(), [
+		TopLevelStatement::Impl {
+			ty: Type::custom("Point"),
+			interface: None,
+			methods: vec![
+				Spanned::new(Method { ident: "Point::new".into(), function: new, visibility: Visibility::Public }, parser::token::Trace::default()),
+				Spanned::new(Method { ident: "Point::x".into(), function: x, visibility: Visibility::Private }, parser::token::Trace::default()),
+			],
+		},
+		TopLevelStatement::Impl { ty: Type::I32.as_slice(), interface: None, methods: vec![] },
+	]);
+
+	// methods can only belong to named types, and are made public one by one
+	assert_eq!(parsed.diagnostics.len(), 2);
+
+	// calls of a method are calls of a field, told apart by the checker
+	assert_eq!(parse_expression("p.x()"), Expression::FunctionCall {
+		function: Box::new(Expression::Binary {
+			lhs: Box::new("p".into()),
+			operator: Binary::Dot,
+			rhs: Box::new("x".into()),
+			ty: Type::Infer,
+		}.into()),
+		arguments: vec![],
+	});
+	assert_eq!(parse_expression("Point::new()"), Expression::FunctionCall {
+		function: Box::new(Expression::ObjectReference("Point::new".into()).into()),
+		arguments: vec![],
+	});
+}
+
+#[test]
+fn consume_interface() {
+	let parsed = parse_recovering(r"pub interface Shape {
+	function area(self) -> f64
+	function scale(self: *Self, by: f64)
+}
+impl Shape for Circle {}
+function total<T: Shape + geometry::Named, U>(items: *[T]) {}
+impl [i32] for Circle {}");
+
+	assert_eq!(parsed.statements.iter().map(|x| x.node.clone()).collect::<Vec<_>>(), [
+		TopLevelStatement::Interface {
+			ident: "Shape".into(),
+			methods: vec![
+				("area".into(), FunctionSignature::new_named(vec![Variable::new("self", Type::custom("Self"))], Type::F64)),
+				("scale".into(), FunctionSignature::new_named(vec![
+					Variable::new("self", Type::custom("Self").as_pointer()),
+					Variable::new("by", Type::F64),
+				], Type::Unit)),
+			],
+			attributes: vec![],
+			visibility: Visibility::Public,
+		},
+		TopLevelStatement::Impl { ty: Type::custom("Circle"), interface: Some("Shape".into()), methods: vec![] },
+		TopLevelStatement::Function {
+			ident: "total".into(),
+			generics: vec![
+				Generic { ident: "T".into(), bounds: vec!["Shape".into(), "geometry::Named".into()] },
+				Generic { ident: "U".into(), bounds: vec![] },
+			],
+			function: Function::new(
+				FunctionSignature::new_named(vec![Variable::new("items", Type::custom("T").as_slice().as_pointer())], Type::Unit),
+				Box::new(Expression::Scope(vec![]).into()),
+			),
+			attributes: vec![],
+			visibility: Visibility::Private,
+			constant: false,
+		},
+	]);
+
+	// only interfaces can be implemented for a type
+	assert_eq!(parsed.diagnostics.len(), 1);
+}
+
+/// Any type that can be written in source, built with the same helpers the parser uses so that
+/// redundant forms such as `const const i32` are never generated
+fn any_type() -> impl Strategy<Value = Type> {
+	let names = || prop::sample::select(vec!["Vec", "Map", "Point"]);
+
+	let leaf = prop_oneof![
+		prop::sample::select(vec![
+			Type::Unit, Type::I64, Type::I32, Type::I8, Type::U64, Type::U32, Type::U8,
+			Type::F32, Type::F64, Type::USize, Type::Bool, Type::Char, Type::Infer,
+		]),
+		names().prop_map(Type::custom),
+	];
+
+	leaf.prop_recursive(4, 32, 3, move |inner| prop_oneof![
+		inner.clone().prop_map(|x| x.as_pointer()),
+		inner.clone().prop_map(|x| x.as_nullable()),
+		inner.clone().prop_map(|x| x.as_const()),
+		(inner.clone(), 0..16usize).prop_map(|(x, length)| x.as_array(length)),
+		inner.clone().prop_map(|x| x.as_slice()),
+		prop::collection::vec(inner.clone(), 1..4).prop_map(Type::Tuple),
+		(names(), prop::collection::vec(inner.clone(), 1..3)).prop_map(|(name, args)| Type::template(name, args)),
+		(prop::collection::vec(inner.clone(), 0..3), inner).prop_map(|(args, returns)| FunctionSignature::new(args, returns).into()),
+	])
+}
+
+proptest! {
+	#[test]
+	fn type_name_round_trip(ty in any_type()) {
+		let mut stream = TokenStream::from(lexer::tokenize(ty.name()).unwrap());
+
+		prop_assert_eq!(category::consume_type(&mut stream).unwrap(), ty);
+		prop_assert!(stream.is_eof());
+	}
+}
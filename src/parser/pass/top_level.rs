@@ -1,10 +1,11 @@
-use crate::parser::ast::function::{Function, FunctionSignature};
-use crate::parser::ast::{Expression, Statement, TopLevelStatement};
+use crate::parser::ast::function::{Function, FunctionSignature, Generic};
+use crate::parser::ast::{Expression, Method, Spanned, Statement, TopLevelStatement, Visibility};
+use crate::parser::ast::attribute::{Attribute, Target};
 use crate::parser::ast::variable::Type;
 use crate::parser::context::TokenStream;
 use crate::parser::pass::{category, expression};
-use crate::parser::Result;
-use crate::parser::token::{Keyword, Literal, Operator, Parenthetical, Token};
+use crate::parser::{Error, Result};
+use crate::parser::token::{Keyword, Literal, Operator, Parenthetical, Token, Trace};
 
 
 pub fn function_pass(stream: &mut TokenStream) -> Result<Option<TopLevelStatement>> {
@@ -15,42 +16,254 @@ pub fn function_pass(stream: &mut TokenStream) -> Result<Option<TopLevelStatemen
 	stream.consume(Keyword::Function)?;
 
 	let ident = stream.consume_identifier()?;
+	let generics = consume_generics(stream)?;
+	let function = category::consume_function(stream, Type::Unit)?;
 
-	stream.consume(Parenthetical::NormalOpen);
+	Ok(Some(TopLevelStatement::Function {
+		ident,
+		generics,
+		function,
+		attributes: vec![],
+		visibility: Visibility::Private,
+		constant: false,
+	}))
+}
+
+/// Whether a token begins a test block. 'test' is only a keyword in front of the name of a test,
+/// so it can still be the name of the `@test` attribute or of a variable
+pub fn is_test(token: &Token) -> bool {
+	matches!(token, Token::Identifier(ident) if ident.0 == "test")
+}
+
+/// Consumes a test block, eg. `test "empty list has no items" { assert(len(list) == 0) }`, which is
+/// a function marked `@test` named after the test
+pub fn test_pass(stream: &mut TokenStream) -> Result<Option<TopLevelStatement>> {
+	let Token::Literal(Literal::String(name)) = stream.peek(1).clone() else {
+		return Ok(None);
+	};
+	if !is_test(stream.curr_token()) {
+		return Ok(None);
+	}
 
+	let begin = stream.curr_trace().clone();
+	stream.next();
+	stream.next();
+	let trace = stream.trace_from(&begin);
 
-	let mut args = vec![];
+	stream.skip_line_break_before_body();
+	let body_begin = stream.curr_trace().clone();
+	let Some(body) = expression::scope_pass(stream)? else {
+		return Error::expected_token(stream.curr_token().clone(), Parenthetical::CurlyOpen).into();
+	};
 
-	while !stream.is_curr(Parenthetical::NormalClose) {
-		args.push(category::consume_variable(stream)?);
+	Ok(Some(TopLevelStatement::Function {
+		ident: name.into(),
+		generics: vec![],
+		function: Function::new(
+			FunctionSignature::new_named(vec![], Type::Unit),
+			Box::new(Spanned::new(body, stream.trace_from(&body_begin))),
+		),
+		attributes: vec![Spanned::new(Attribute::Test, trace)],
+		visibility: Visibility::Private,
+		constant: false,
+	}))
+}
+
+/// Consumes the type parameters of a generic function if it has any, eg. `<T: Shape + Debug, U>`
+fn consume_generics(stream: &mut TokenStream) -> Result<Vec<Generic>> {
+	let mut generics = vec![];
+	if stream.try_consume(Operator::Less).is_none() {
+		return Ok(generics);
+	}
 
-		if stream.is_curr(Operator::Comma) {
-			stream.next();
-			continue;
+	while !stream.is_curr(Operator::Greater) {
+		let ident = stream.consume_identifier()?;
+
+		let mut bounds = vec![];
+		if stream.try_consume(Operator::Colon).is_some() {
+			bounds.push(category::consume_path(stream)?);
+			while stream.try_consume(Operator::Add).is_some() {
+				bounds.push(category::consume_path(stream)?);
+			}
 		}
-		break;
+
+		generics.push(Generic { ident, bounds });
+		if stream.try_consume(Operator::Comma).is_none() { break; }
 	}
-	stream.consume(Parenthetical::NormalClose);
 
-	let returns = if stream.is_curr(Operator::ThinArrow) {
-		stream.next();
-		category::consume_type(stream)?
-	} else {
-		Type::Unit
-	};
+	stream.consume(Operator::Greater)?;
+	Ok(generics)
+}
+
+/// Consumes an interface, eg. `interface Shape { function area(self) -> f64 }`
+pub fn interface_pass(stream: &mut TokenStream) -> Result<Option<TopLevelStatement>> {
+	if stream.try_consume(Keyword::Interface).is_none() {
+		return Ok(None);
+	}
 
-	let signature = FunctionSignature::new_named(args, returns);
+	let ident = stream.consume_identifier()?;
+	stream.skip_line_break_before_body();
+	stream.consume(Parenthetical::CurlyOpen)?;
 
-	let body = Box::new(if let Some(scope) = expression::scope_pass(stream)? {
-		scope
-	} else {
-		stream.consume(Operator::Arrow)?;
-		expression::consume(stream)?
-	});
+	let mut methods = vec![];
+	while !stream.is_curr(Parenthetical::CurlyClose) && !stream.is_eof() {
+		// methods are separated by newlines, which the lexer turns into ';'
+		if stream.try_consume(Operator::SemiColon).is_some() { continue; }
 
+		stream.consume(Keyword::Function)?;
+		let name = category::consume_member_name(stream)?;
+		methods.push((name, category::consume_signature(stream, Type::Unit)?));
+	}
 
-	Ok(Some(TopLevelStatement::Function {
+	stream.consume(Parenthetical::CurlyClose)?;
+	Ok(Some(TopLevelStatement::Interface { ident, methods, attributes: vec![], visibility: Visibility::Private }))
+}
+
+/// Consumes a constant, eg. `const N: usize = 4 * 4`, or a const function, eg.
+/// `const function square(x: u32) -> u32 => x * x`
+pub fn const_pass(stream: &mut TokenStream) -> Result<Option<TopLevelStatement>> {
+	if stream.try_consume(Keyword::Const).is_none() {
+		return Ok(None);
+	}
+
+	if stream.is_curr(Keyword::Function) {
+		let mut function = function_pass(stream)?;
+		if let Some(TopLevelStatement::Function { constant, .. }) = &mut function {
+			*constant = true;
+		}
+		return Ok(function);
+	}
+
+	let constant = category::consume_variable(stream)?;
+	stream.consume(Operator::Assignment)?;
+
+	Ok(Some(TopLevelStatement::Const {
+		constant,
+		value: expression::consume(stream)?,
+		attributes: vec![],
+		visibility: Visibility::Private,
+	}))
+}
+
+/// Consumes a type alias, eg. `type Pair<T> = [T; 2]`
+pub fn type_alias_pass(stream: &mut TokenStream) -> Result<Option<TopLevelStatement>> {
+	if stream.try_consume(Keyword::Type).is_none() {
+		return Ok(None);
+	}
+
+	let ident = stream.consume_identifier()?;
+
+	let mut parameters = vec![];
+	if stream.try_consume(Operator::Less).is_some() {
+		while !stream.is_curr(Operator::Greater) {
+			parameters.push(stream.consume_identifier()?);
+			if stream.try_consume(Operator::Comma).is_none() { break; }
+		}
+		stream.consume(Operator::Greater)?;
+	}
+
+	stream.consume(Operator::Assignment)?;
+
+	Ok(Some(TopLevelStatement::TypeAlias {
 		ident,
-		function: Function { signature, body },
+		parameters,
+		ty: category::consume_type(stream)?,
+		attributes: vec![],
+		visibility: Visibility::Private,
 	}))
-}
\ No newline at end of file
+}
+
+/// Consumes an impl block, eg. `impl Point { pub function new(x: f32, y: f32) -> Point => (x, y) }`
+/// or `impl Shape for Circle { .. }`
+pub fn impl_pass(stream: &mut TokenStream) -> Result<Option<TopLevelStatement>> {
+	if stream.try_consume(Keyword::Impl).is_none() {
+		return Ok(None);
+	}
+
+	let mut begin = stream.curr_trace().clone();
+	let mut ty = category::consume_type(stream)?;
+
+	let mut interface = None;
+	if stream.try_consume(Keyword::For).is_some() {
+		let Type::Custom { name, .. } = ty else {
+			return Error::unexpected_token(Keyword::For).into();
+		};
+
+		interface = Some(name);
+		begin = stream.curr_trace().clone();
+		ty = category::consume_type(stream)?;
+	}
+
+	let name = match &ty {
+		Type::Custom { name, .. } => name.clone(),
+		ty => {
+			let trace = stream.trace_from(&begin);
+			stream.report_at(Error::ImplTarget(ty.clone()), trace);
+			ty.name().into()
+		}
+	};
+
+	stream.skip_line_break_before_body();
+	stream.consume(Parenthetical::CurlyOpen)?;
+
+	let mut methods = vec![];
+	while !stream.is_curr(Parenthetical::CurlyClose) && !stream.is_eof() {
+		// functions are separated by newlines, which the lexer turns into ';'
+		if stream.try_consume(Operator::SemiColon).is_some() { continue; }
+
+		let begin = stream.curr_trace().clone();
+		let visibility = if consume_visibility(stream).is_some() { Visibility::Public } else { Visibility::Private };
+
+		stream.consume(Keyword::Function)?;
+		let ident = format!("{name}::{}", category::consume_member_name(stream)?).into();
+		let function = category::consume_function(stream, Type::Unit)?;
+
+		methods.push(Spanned::new(Method { ident, function, visibility }, stream.trace_from(&begin)));
+	}
+
+	stream.consume(Parenthetical::CurlyClose)?;
+	Ok(Some(TopLevelStatement::Impl { ty, interface, methods }))
+}
+
+pub fn import_pass(stream: &mut TokenStream) -> Result<Option<TopLevelStatement>> {
+	if stream.try_consume(Keyword::Import).is_none() {
+		return Ok(None);
+	}
+
+	match stream.take_curr() {
+		Token::Literal(Literal::String(path)) => Ok(Some(TopLevelStatement::Import(path))),
+		token => Error::expected_token(token, Literal::String(String::new())).into(),
+	}
+}
+
+pub fn use_pass(stream: &mut TokenStream) -> Result<Option<TopLevelStatement>> {
+	if stream.try_consume(Keyword::Use).is_none() {
+		return Ok(None);
+	}
+
+	Ok(Some(TopLevelStatement::Use(category::consume_path(stream)?)))
+}
+
+/// Consumes the 'pub' in front of a top level statement, returning where it was if there is one
+pub fn consume_visibility(stream: &mut TokenStream) -> Option<Trace> {
+	let trace = stream.curr_trace().clone();
+	stream.try_consume(Keyword::Pub).map(|_| trace)
+}
+
+/// Makes the statement public if it was written with a 'pub', reporting statements that cannot be
+pub fn apply_visibility(stream: &mut TokenStream, statement: &mut TopLevelStatement, public: Option<Trace>) {
+	let Some(trace) = public else { return; };
+
+	match statement {
+		TopLevelStatement::Function { visibility, .. }
+		| TopLevelStatement::GlobalVariable { visibility, .. }
+		| TopLevelStatement::Const { visibility, .. }
+		| TopLevelStatement::TypeAlias { visibility, .. }
+		| TopLevelStatement::Interface { visibility, .. } => *visibility = Visibility::Public,
+		TopLevelStatement::Import(_) => stream.report_at(Error::MisplacedVisibility(Target::Import), trace),
+		// names brought in with 'use' are not passed on to the modules importing this one
+		TopLevelStatement::Use(_) => stream.report_at(Error::MisplacedVisibility(Target::Use), trace),
+		// methods are made public one by one
+		TopLevelStatement::Impl { .. } => stream.report_at(Error::MisplacedVisibility(Target::Impl), trace),
+	}
+}
@@ -4,7 +4,14 @@ use crate::parser::ast::variable::Type;
 use crate::parser::context::TokenStream;
 use crate::parser::lexer;
 use crate::parser::pass::category;
-use crate::parser::token::{Keyword, Literal, Operator, Parenthetical, Token};
+use crate::parser::token::{Keyword, Literal, Operator, Parenthetical, Token, TracedTokenList};
+
+/// Tokenizes and strips the EOF that always ends the list
+fn tokenize(contents: String) -> lexer::Result<TracedTokenList> {
+	let mut tokens = lexer::tokenize(contents)?;
+	assert_eq!(tokens.pop().map(|x| x.token), Some(Token::EOF));
+	Ok(tokens)
+}
 
 #[test_case("function", Keyword::Function; "Function Keyword")]
 #[test_case("return", Keyword::Return; "Return Keyword")]
@@ -14,9 +21,16 @@ use crate::parser::token::{Keyword, Literal, Operator, Parenthetical, Token};
 #[test_case("let", Keyword::Let; "Let Keyword")]
 #[test_case("var", Keyword::Var; "Var Keyword")]
 #[test_case("in", Keyword::In; "In Keyword")]
+#[test_case("if", Keyword::If; "If Keyword")]
+#[test_case("else", Keyword::Else; "Else Keyword")]
+#[test_case("defer", Keyword::Defer; "Defer Keyword")]
+#[test_case("impl", Keyword::Impl; "Impl Keyword")]
+#[test_case("interface", Keyword::Interface; "Interface Keyword")]
+#[test_case("dyn", Keyword::Dyn; "Dyn Keyword")]
+#[test_case("assert", Keyword::Assert; "Assert Keyword")]
 fn keyword(contents: &str, keyword: Keyword) -> lexer::Result<()> {
 	let contents = contents.to_string();
-	let tokens = lexer::tokenize(contents)?;
+	let tokens = tokenize(contents)?;
 
 	assert_eq!(tokens.len(), 1);
 	assert_eq!(tokens[0].token, Token::Keyword(keyword));
@@ -30,7 +44,7 @@ fn keyword(contents: &str, keyword: Keyword) -> lexer::Result<()> {
 #[test_case("wha_42_b muh", & ["wha_42_b", "muh"]; "Identifier with underscores")]
 fn identifier(contents: &str, idents: &[&'static str]) -> lexer::Result<()> {
 	let contents = contents.to_string();
-	let tokens = lexer::tokenize(contents)?;
+	let tokens = tokenize(contents)?;
 
 	assert_eq!(tokens.len(), idents.len());
 
@@ -48,7 +62,7 @@ fn identifier(contents: &str, idents: &[&'static str]) -> lexer::Result<()> {
 #[test_case("000___420___69___", & [42069]; "Int Literal with shit ton of underscores")]
 fn int_literals(contents: &str, idents: &[i64]) -> lexer::Result<()> {
 	let contents = contents.to_string();
-	let tokens = lexer::tokenize(contents)?;
+	let tokens = tokenize(contents)?;
 
 	assert_eq!(tokens.len(), idents.len());
 	for (tt, literal) in tokens.into_iter().zip(idents.iter()) {
@@ -64,7 +78,7 @@ fn int_literals(contents: &str, idents: &[i64]) -> lexer::Result<()> {
 #[test_case("72.78 735.53", & [72.78, 735.53]; "Multiple Float Literals")]
 fn float_literals(contents: &str, idents: &[f64]) -> lexer::Result<()> {
 	let contents = contents.to_string();
-	let tokens = lexer::tokenize(contents)?;
+	let tokens = tokenize(contents)?;
 
 	assert_eq!(tokens.len(), idents.len());
 	for (tt, literal) in tokens.into_iter().zip(idents.iter()) {
@@ -74,6 +88,21 @@ fn float_literals(contents: &str, idents: &[f64]) -> lexer::Result<()> {
 	Ok(())
 }
 
+#[test]
+fn tuple_fields() -> lexer::Result<()> {
+	let tokens = tokenize("t.0.1".into())?.into_iter().map(|x| x.token).collect::<Vec<_>>();
+
+	assert_eq!(tokens, [
+		Token::Identifier("t".into()),
+		Operator::Dot.into(),
+		Literal::Integer(0).into(),
+		Operator::Dot.into(),
+		Literal::Integer(1).into(),
+	]);
+
+	Ok(())
+}
+
 #[test_case(":", & [Operator::Colon]; "Colon Operator")]
 #[test_case("+", & [Operator::Add]; "Add Operator")]
 #[test_case("-", & [Operator::Minus]; "Minus Operator")]
@@ -115,7 +144,7 @@ Operator::Reference];
 "All Operators")]
 fn operators_simple(contents: &str, expected: &[Operator]) -> lexer::Result<()> {
 	let contents = contents.to_string();
-	let tokens = lexer::tokenize(contents)?;
+	let tokens = tokenize(contents)?;
 
 	assert_eq!(tokens.len(), expected.len());
 	for (tt, operator) in tokens.into_iter().zip(expected.iter()) {
@@ -130,7 +159,7 @@ fn operators_simple(contents: &str, expected: &[Operator]) -> lexer::Result<()>
 #[test_case(r#" "\u0289 bruh" "#, & ["\u{289} bruh"]; "String Literal with unicode escape")]
 fn string_literal(contents: &str, expected: &[&'static str]) -> lexer::Result<()> {
 	let contents = contents.to_string();
-	let tokens = lexer::tokenize(contents)?;
+	let tokens = tokenize(contents)?;
 
 	assert_eq!(tokens.len(), expected.len());
 	for (tt, operator) in tokens.into_iter().zip(expected.iter()) {
@@ -144,7 +173,7 @@ fn string_literal(contents: &str, expected: &[&'static str]) -> lexer::Result<()
 #[test_case(r#" '\u0050' '\n'"#, & ['\u{50}', '\n']; "Char literal with escape")]
 fn char_literal(contents: &str, expected: &[char]) -> lexer::Result<()> {
 	let contents = contents.to_string();
-	let tokens = lexer::tokenize(contents)?;
+	let tokens = tokenize(contents)?;
 
 	assert_eq!(tokens.len(), expected.len());
 	for (tt, operator) in tokens.into_iter().zip(expected.iter()) {
@@ -162,7 +191,7 @@ fn program_is_even() -> lexer::Result<()> {
 		}
  "#;
 
-	let tokens = lexer::tokenize(CONTENTS.to_string())?;
+	let tokens = tokenize(CONTENTS.to_string())?;
 	let expected = vec![
 		Keyword::Function.into(),
 		Token::Identifier("is_even".into()),
@@ -187,3 +216,67 @@ fn program_is_even() -> lexer::Result<()> {
 
 	Ok(())
 }
+
+#[test_case("a\nb", & [Token::Identifier("a".into()), Operator::SemiColon.into(), Token::Identifier("b".into())]; "Newline after identifier")]
+#[test_case("a +\nb", & [Token::Identifier("a".into()), Operator::Add.into(), Token::Identifier("b".into())]; "Newline after operator")]
+#[test_case("f(a,\nb)", & [
+	Token::Identifier("f".into()),
+	Parenthetical::NormalOpen.into(),
+	Token::Identifier("a".into()),
+	Operator::Comma.into(),
+	Token::Identifier("b".into()),
+	Parenthetical::NormalClose.into(),
+]; "Newline inside parenthesis")]
+#[test_case("{ 1\n}\n", & [
+	Parenthetical::CurlyOpen.into(),
+	Literal::Integer(1).into(),
+	Parenthetical::CurlyClose.into(),
+]; "Newline before closing")]
+#[test_case("{\n\n1\n\n2\n}", & [
+	Parenthetical::CurlyOpen.into(),
+	Literal::Integer(1).into(),
+	Operator::SemiColon.into(),
+	Literal::Integer(2).into(),
+	Parenthetical::CurlyClose.into(),
+]; "Blank lines")]
+fn semicolon_insertion(contents: &str, expected: &[Token]) -> lexer::Result<()> {
+	let tokens: Vec<_> = tokenize(contents.to_string())?.into_iter().map(|x| x.token).collect();
+	assert_eq!(tokens, expected);
+
+	Ok(())
+}
+
+#[test_case(""; "Empty")]
+#[test_case("  \n\t "; "Whitespace")]
+fn eof_sentinel(contents: &str) -> lexer::Result<()> {
+	assert!(tokenize(contents.to_string())?.is_empty());
+
+	let mut stream = TokenStream::from(lexer::tokenize(contents.to_string())?);
+	assert!(stream.is_eof());
+	assert_eq!(stream.next(), &Token::EOF);
+	assert_eq!(stream.peek(5), &Token::EOF);
+
+	Ok(())
+}
+
+#[test]
+fn stream_lookahead() -> lexer::Result<()> {
+	let mut stream = TokenStream::from(lexer::tokenize("a + b".to_string())?);
+
+	assert_eq!(stream.peek(0), &Token::Identifier("a".into()));
+	assert_eq!(stream.peek(1), &Operator::Add.into());
+	assert_eq!(stream.peek(2), &Token::Identifier("b".into()));
+	assert_eq!(stream.peek(3), &Token::EOF);
+	assert_eq!(stream.peek(100), &Token::EOF);
+
+	let checkpoint = stream.checkpoint();
+	stream.next();
+	stream.report(parser::Error::unexpected_token(Operator::Add));
+	assert_eq!(stream.curr_token(), &Operator::Add.into());
+
+	stream.rewind(checkpoint);
+	assert_eq!(stream.curr_token(), &Token::Identifier("a".into()));
+	assert_eq!(stream.checkpoint(), checkpoint);
+
+	Ok(())
+}
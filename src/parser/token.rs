@@ -1,4 +1,5 @@
-use std::fmt::{Debug};
+use std::fmt;
+use std::fmt::{Debug, Formatter};
 use crate::parser::ast::Expression;
 use crate::parser::ast::variable::Identifier;
 
@@ -17,7 +18,7 @@ impl From<TracedToken> for Token {
 	}
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
 pub struct FilePos {
 	pub row: usize,
 	pub column: usize,
@@ -32,13 +33,52 @@ impl FilePos {
 	}
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Trace {
 	pub source_file: String,
 	pub begin_pos: FilePos,
 	pub end_pos: FilePos,
 }
 
+impl Trace {
+	/// Trace from the beginning of this one to the end of another
+	pub fn to(&self, end: &Self) -> Self {
+		Self {
+			source_file: self.source_file.clone(),
+			begin_pos: self.begin_pos,
+			end_pos: end.end_pos,
+		}
+	}
+}
+
+impl fmt::Display for Trace {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		// rows are counted from 0 while editors count lines from 1
+		write!(f, "{}:{}:{}", self.source_file, self.begin_pos.row + 1, self.begin_pos.column)
+	}
+}
+
+/// Error that can be wrapped along with the location it happened at, usually as a variant
+/// `At { error, trace }` of itself
+pub trait Located: Sized {
+	/// Wraps the error along with its location
+	fn located(self, trace: Trace) -> Self;
+
+	/// Error that was wrapped and where it happened, if this is a wrapped error
+	fn location(&self) -> Option<(&Self, &Trace)>;
+
+	/// Attaches where the error happened, errors that already know where they happened keep the
+	/// innermost and so most precise location
+	fn at(self, trace: &Trace) -> Self {
+		if self.location().is_some() { self } else { self.located(trace.clone()) }
+	}
+
+	/// Error without the location it happened at
+	fn kind(&self) -> &Self {
+		self.location().map_or(self, |(error, _)| error.kind())
+	}
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
 	Keyword(Keyword),
@@ -64,6 +104,8 @@ pub enum Literal {
 	Float(f64),
 	Bool(bool),
 	Unit,
+	/// Pointer that points to nothing, only usable as a nullable pointer, eg. `?*i32`
+	Null,
 }
 
 impl From<Literal> for Token {
@@ -99,6 +141,9 @@ impl Parenthetical {
 	const fn is_opener_for(self, closing: Self) -> bool {
 		closing.is_closer_for(self)
 	}
+	pub const fn is_opener(self) -> bool {
+		matches!(self, Self::NormalOpen | Self::BracketOpen | Self::CurlyOpen)
+	}
 }
 
 impl From<Parenthetical> for Token {
@@ -138,6 +183,12 @@ pub enum Operator {
 
 	ThinArrow,
 	Arrow,
+	/// `::` between the parts of a path, eg. `geometry::area`
+	PathSeparator,
+	/// `@` starting an attribute, eg. `@inline`
+	At,
+	/// `?` marking a pointer that may be null, eg. `?*i32`
+	Question,
 }
 
 impl From<Operator> for Token {
@@ -158,6 +209,19 @@ pub enum Keyword {
 	In,
 	As,
 	Const,
+	New,
+	Delete,
+	Import,
+	Use,
+	Pub,
+	Type,
+	If,
+	Else,
+	Defer,
+	Impl,
+	Interface,
+	Dyn,
+	Assert,
 }
 
 impl From<Keyword> for Token {
@@ -181,6 +245,19 @@ impl TryFrom<&str> for Keyword {
 			"in" => Self::In,
 			"const" => Self::Const,
 			"as" => Self::As,
+			"new" => Self::New,
+			"delete" => Self::Delete,
+			"import" => Self::Import,
+			"use" => Self::Use,
+			"pub" => Self::Pub,
+			"type" => Self::Type,
+			"if" => Self::If,
+			"else" => Self::Else,
+			"defer" => Self::Defer,
+			"impl" => Self::Impl,
+			"interface" => Self::Interface,
+			"dyn" => Self::Dyn,
+			"assert" => Self::Assert,
 			_ => return Err(())
 		})
 	}
@@ -0,0 +1,123 @@
+use std::fmt::Write;
+use serde_json::json;
+use crate::analysis::attribute::Registry;
+use crate::interpreter;
+use crate::parser::ast::{Spanned, TopLevelStatement};
+use crate::parser::ast::variable::Identifier;
+use crate::parser::token::{Located, Trace};
+
+#[cfg(test)]
+mod test;
+
+/// Stack every test runs on, deep enough for the interpreter to reach its recursion limit
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Result of running a single test
+#[derive(Debug)]
+pub struct Outcome {
+	pub name: Identifier,
+	/// Error the test stopped with, `None` when it passed
+	pub failure: Option<interpreter::Error>,
+}
+
+impl Outcome {
+	/// Where the test failed, such as the assertion that did not hold
+	pub const fn location(&self) -> Option<&Trace> {
+		match &self.failure {
+			Some(interpreter::Error::At { trace, .. }) => Some(trace),
+			_ => None,
+		}
+	}
+
+	/// Failure without its location, which is given by [`Outcome::location`]
+	pub fn message(&self) -> Option<String> {
+		self.failure.as_ref().map(|x| x.kind().to_string())
+	}
+}
+
+/// Outcomes of the tests of a program, along with how many were left out by the filter
+#[derive(Debug)]
+pub struct Report {
+	pub outcomes: Vec<Outcome>,
+	pub filtered_out: usize,
+}
+
+/// Runs every test of a type checked program whose name contains the filter, ordered by name.
+/// Tests are the test blocks and `@test` functions, each run with memory and a thread of its own
+pub fn run(statements: &[Spanned<TopLevelStatement>], filter: Option<&str>) -> Report {
+	let tests = Registry::collect(statements).with("test").into_iter().cloned().collect::<Vec<_>>();
+	let total = tests.len();
+
+	let outcomes: Vec<_> = tests.into_iter()
+		.filter(|name| filter.is_none_or(|filter| name.0.contains(filter)))
+		.map(|name| {
+			let failure = std::thread::scope(|scope| {
+				let test = std::thread::Builder::new()
+					.stack_size(STACK_SIZE)
+					.spawn_scoped(scope, || interpreter::run_test(statements, &name).err())
+					.expect("Could not start a thread to run the test on");
+				test.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+			});
+			Outcome { name, failure }
+		})
+		.collect();
+
+	Report { filtered_out: total - outcomes.len(), outcomes }
+}
+
+impl Report {
+	pub fn passed(&self) -> usize {
+		self.outcomes.iter().filter(|x| x.failure.is_none()).count()
+	}
+
+	pub fn failed(&self) -> usize {
+		self.outcomes.len() - self.passed()
+	}
+
+	/// Line for every test followed by a summary, for people to read
+	pub fn human(&self) -> String {
+		let mut report = String::new();
+
+		for outcome in &self.outcomes {
+			let _ = match (outcome.message(), outcome.location()) {
+				(None, _) => writeln!(report, "test {} ... ok", outcome.name),
+				(Some(message), Some(location)) => writeln!(report, "test {} ... FAILED\n\t{location}: {message}", outcome.name),
+				(Some(message), None) => writeln!(report, "test {} ... FAILED\n\t{message}", outcome.name),
+			};
+		}
+
+		let result = if self.failed() == 0 { "ok" } else { "FAILED" };
+		let _ = write!(
+			report,
+			"\ntest result: {result}. {} passed; {} failed; {} filtered out",
+			self.passed(),
+			self.failed(),
+			self.filtered_out,
+		);
+		report
+	}
+
+	/// Every outcome and the summary as a single JSON object, for tools such as CI to read
+	pub fn json(&self) -> String {
+		let tests: Vec<_> = self.outcomes.iter()
+			.map(|outcome| json!({
+				"name": outcome.name.0,
+				"passed": outcome.failure.is_none(),
+				"message": outcome.message(),
+				"location": outcome.location().map(|trace| json!({
+					"file": trace.source_file,
+					// counted from 1 like the locations in the human readable report
+					"line": trace.begin_pos.row + 1,
+					"column": trace.begin_pos.column,
+				})),
+			}))
+			.collect();
+
+		json!({
+			"tests": tests,
+			"passed": self.passed(),
+			"failed": self.failed(),
+			"filtered_out": self.filtered_out,
+		}).to_string()
+	}
+}
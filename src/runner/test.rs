@@ -0,0 +1,92 @@
+#![allow(clippy::unwrap_used)]
+
+use crate::{analysis, interpreter, parser, runner};
+use crate::parser::ast::{Spanned, TopLevelStatement};
+use crate::parser::token::Located;
+
+const SOURCE: &str = r#"const function square(x: i32) -> i32 => x * x
+
+test "square of a negative is positive" {
+	assert(square(-3) == 9)
+}
+
+test "square of two" {
+	let four = square(2)
+	assert(four == 5)
+}
+
+@test
+function leaks() {
+	let p = new i32(1)
+}
+
+function helper() {}"#;
+
+fn checked(source: &'static str) -> Vec<Spanned<TopLevelStatement>> {
+	let mut statements = parser::parse(source.into()).unwrap().into_result().unwrap();
+	analysis::capture::analyse(&mut statements);
+	analysis::checker::check(&mut statements).unwrap();
+	statements
+}
+
+#[test]
+fn outcomes() {
+	let report = runner::run(&checked(SOURCE), None);
+
+	let names: Vec<_> = report.outcomes.iter().map(|x| x.name.0.as_str()).collect();
+	assert_eq!(names, ["leaks", "square of a negative is positive", "square of two"]);
+	assert_eq!((report.passed(), report.failed(), report.filtered_out), (1, 2, 0));
+
+	let leak = &report.outcomes[0];
+	assert!(matches!(leak.failure.as_ref().map(interpreter::Error::kind), Some(interpreter::Error::MemoryLeak(_))));
+
+	// the span of the assertion that did not hold
+	let failed = &report.outcomes[2];
+	assert_eq!(failed.message().as_deref(), Some("Assertion failed"));
+	let location = failed.location().unwrap();
+	assert_eq!((location.begin_pos.row, location.begin_pos.column), (8, 2));
+	assert_eq!((location.end_pos.row, location.end_pos.column), (8, 19));
+}
+
+#[test]
+fn filter() {
+	let report = runner::run(&checked(SOURCE), Some("square"));
+
+	assert_eq!(report.outcomes.len(), 2);
+	assert_eq!(report.filtered_out, 1);
+	assert_eq!(report.human(), "test square of a negative is positive ... ok\n\
+		test square of two ... FAILED\n\t:9:2: Assertion failed\n\
+		\ntest result: FAILED. 1 passed; 1 failed; 1 filtered out");
+}
+
+#[test]
+fn json() {
+	let report = runner::run(&checked(SOURCE), Some("negative"));
+	let json: serde_json::Value = serde_json::from_str(&report.json()).unwrap();
+
+	assert_eq!(json, serde_json::json!({
+		"tests": [{ "name": "square of a negative is positive", "passed": true, "message": null, "location": null }],
+		"passed": 1,
+		"failed": 0,
+		"filtered_out": 2,
+	}));
+
+	let report = runner::run(&checked(SOURCE), Some("two"));
+	let json: serde_json::Value = serde_json::from_str(&report.json()).unwrap();
+	assert_eq!(json["tests"][0]["location"], serde_json::json!({ "file": "", "line": 9, "column": 2 }));
+}
+
+#[test]
+fn recursion() {
+	let report = runner::run(&checked(r#"function f(n: i32) -> i32 => f(n + 1)
+
+	test "recurse" { f(0) }
+
+	test "after" { assert(1 + 1 == 2) }"#), None);
+
+	// running out of calls fails the one test, the others are still run and reported
+	assert_eq!((report.passed(), report.failed()), (1, 1));
+	let recurse = &report.outcomes[1];
+	assert_eq!(recurse.message().as_deref(), Some("Went more than 128 function calls deep"));
+	assert_eq!(recurse.location().map(|x| x.begin_pos.row), Some(0));
+}